@@ -26,6 +26,10 @@ local state = {
     fullscreen = false,
     muted = false,
     volume = 100,
+    -- Label of the next episode, pushed by the app ("next-episode" message); nil hides the button
+    next_episode = nil,
+    -- Clickable areas of the last render: { {action, x1, y1, x2, y2}, ... }
+    hit_boxes = {},
 }
 
 -- Premium color palette
@@ -47,11 +51,29 @@ local styles = {
     icon_dim = "{\\blur0\\bord0\\1c&HB0B0B0&}",
 }
 
+-- Asks the app to do something it owns (queue, subtitle picker, ...).
+-- Arrives in the webview as an `mpv-script-message` event.
+local function app_action(action, ...)
+    mp.commandv("script-message", "gds-app-action", action, ...)
+end
+
 -- Utility functions
 local function clamp(value, min, max)
     return math.max(min, math.min(max, value))
 end
 
+-- Rough width of `text` in OSD pixels at font size `fs`, counted per codepoint
+-- (not UTF-8 byte): half an em each, a full em for Hangul/CJK (U+3000..U+DFFF).
+local function text_width(text, fs)
+    local width = 0
+    for ch in text:gmatch("[%z\1-\127\194-\244][\128-\191]*") do
+        local lead = ch:byte(1)
+        local wide = #ch == 3 and lead >= 0xE3 and lead <= 0xED
+        width = width + (wide and fs or fs * 0.5)
+    end
+    return width
+end
+
 local function format_time(seconds)
     if not seconds or seconds < 0 then return "--:--" end
     local hours = math.floor(seconds / 3600)
@@ -279,6 +301,10 @@ end
 local function render()
     local osd_w, osd_h = mp.get_osd_size()
     if osd_w <= 0 then return end
+    -- Draw in OSD pixels so positions match mouse-pos for clicks
+    state.osd.res_x = osd_w
+    state.osd.res_y = osd_h
+    state.hit_boxes = {}
     
     local ass = assdraw.ass_new()
     
@@ -373,7 +399,23 @@ local function render()
     draw_icon_volume(ass, rightControlsX - buttonSize * 2.5, controlY, buttonSize * 0.8, muted, 0)
     
     -- Subtitles
-    draw_icon_subtitle(ass, rightControlsX - buttonSize * 1.3, controlY, buttonSize * 0.8, 0)
+    local subX = rightControlsX - buttonSize * 1.3
+    draw_icon_subtitle(ass, subX, controlY, buttonSize * 0.8, 0)
+    local half = buttonSize * 0.4
+    table.insert(state.hit_boxes, {"subtitle-picker", subX - half, controlY - half, subX + half, controlY + half})
+    
+    -- Next episode (left of the volume icon)
+    if state.next_episode then
+        local nextX = rightControlsX - buttonSize * 3.3
+        local label = state.next_episode .. "  ▶"
+        ass:new_event()
+        ass:append(styles.time)
+        ass:pos(nextX, controlY)
+        ass:an(6)
+        ass:append(label)
+        local width = text_width(label, 22) -- styles.time is \fs22
+        table.insert(state.hit_boxes, {"next-episode", nextX - width, controlY - 18, nextX, controlY + 18})
+    end
     
     -- Fullscreen
     draw_icon_fullscreen(ass, rightControlsX - buttonSize * 0.3, controlY, buttonSize * 0.8, 0)
@@ -430,6 +472,21 @@ local function check_mouse_pos()
     end
 end
 
+-- Clicks on OSC buttons become app actions; anywhere else just shows the OSC
+local function on_click()
+    if state.osc_visible then
+        local mx = mp.get_property_number("mouse-pos/x", -1)
+        local my = mp.get_property_number("mouse-pos/y", -1)
+        for _, box in ipairs(state.hit_boxes) do
+            if mx >= box[2] and mx <= box[4] and my >= box[3] and my <= box[5] then
+                app_action(box[1])
+                break
+            end
+        end
+    end
+    show_osc()
+end
+
 -- Initialize
 mp.observe_property("pause", "bool", on_pause_change)
 mp.observe_property("time-pos", "number", tick)
@@ -445,6 +502,17 @@ mp.add_periodic_timer(0.1, check_mouse_pos)
 -- Also show on any key press
 mp.add_key_binding(nil, "show-osc", show_osc)
 mp.register_script_message("show-osc", show_osc)
+mp.add_forced_key_binding("MBTN_LEFT", "osc-click", on_click)
+
+-- App actions, also bindable from input.conf (e.g. `N script-binding gds_osc/next-episode`)
+mp.add_key_binding(nil, "next-episode", function() app_action("next-episode") end)
+mp.add_key_binding(nil, "subtitle-picker", function() app_action("subtitle-picker") end)
+
+-- Sent by the app: `next-episode <label>`, or an empty label when there is none
+mp.register_script_message("next-episode", function(label)
+    state.next_episode = (label and label ~= "") and label or nil
+    if state.osc_visible then render() end
+end)
 
 -- Show on property changes that indicate user activity
 mp.observe_property("fullscreen", "bool", show_osc)
@@ -0,0 +1,681 @@
+// Virtual categories from category.yaml.
+//
+// A virtual category merges the explorer listings of several GDS paths, filters
+// them by keywords and hides index buckets (가/나/다/0Z) and season folders.
+// The file is human-edited, so parsing rejects unknown keys and reports where
+// the problem is instead of yielding an empty rail. The evaluator only works on
+// listings that were already fetched.
+//
+// The live file is category.yaml in the app config dir, re-validated whenever
+// it changes on disk; the bundled copy is used while there is none. An invalid
+// edit is reported (`categories-invalid`, with line/column) and the last good
+// rules stay in use.
+use crate::error::{FlashplexError, FlashplexResult};
+use crate::gds::{ExplorerListRequest, GdsItem, GdsState, MediaRef};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashSet};
+use std::fmt;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime};
+use tauri::{Emitter, Manager};
+use unicode_normalization::UnicodeNormalization;
+
+pub const CATEGORY_FILE_VERSION: u32 = 1;
+pub const CATEGORIES_CHANGED_EVENT: &str = "categories-changed";
+pub const CATEGORIES_INVALID_EVENT: &str = "categories-invalid";
+/// Copy shipped with the frontend; used until a valid user file exists.
+pub const BUNDLED_CATEGORY_YAML: &str = include_str!("../../src/config/category.yaml");
+const CATEGORY_FILE: &str = "category.yaml";
+const WATCH_INTERVAL: Duration = Duration::from_secs(2);
+const SOURCE_LIST_LIMIT: u32 = 2000;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct VirtualCategory {
+    pub id: String,
+    pub label: String,
+    pub sources: Vec<String>,
+    /// Any-match filter on name/path; empty keeps everything.
+    #[serde(default)]
+    pub include_keywords: Vec<String>,
+    #[serde(default)]
+    pub exclude_keywords: Vec<String>,
+    #[serde(default)]
+    pub exclude_bucket_folders: bool,
+    #[serde(default)]
+    pub exclude_season_folders: bool,
+    /// Lower is shown first.
+    #[serde(default)]
+    pub sort_priority: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct CategoryGroup {
+    pub default: String,
+    pub categories: Vec<VirtualCategory>,
+}
+
+impl CategoryGroup {
+    pub fn category(&self, id: &str) -> Option<&VirtualCategory> {
+        self.categories.iter().find(|c| c.id == id)
+    }
+}
+
+/// Sections are the app's top-level categories; each holds named groups
+/// (e.g. `tv_show.domestic_virtual`). Sections are spelled out rather than
+/// flattened so a misspelled one is rejected with its position.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct CategoryConfig {
+    pub version: u32,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub tv_show: BTreeMap<String, CategoryGroup>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub movie: BTreeMap<String, CategoryGroup>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub animation: BTreeMap<String, CategoryGroup>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CategoryError {
+    pub message: String,
+    /// 1-based; None when the problem can't be pinned to a line.
+    pub line: Option<usize>,
+    pub column: Option<usize>,
+}
+
+impl fmt::Display for CategoryError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match (self.line, self.column) {
+            (Some(line), Some(column)) => write!(f, "{} (line {}, column {})", self.message, line, column),
+            (Some(line), None) => write!(f, "{} (line {})", self.message, line),
+            _ => write!(f, "{}", self.message),
+        }
+    }
+}
+
+impl From<CategoryError> for FlashplexError {
+    fn from(e: CategoryError) -> Self {
+        FlashplexError::invalid_input(format!("category.yaml: {}", e))
+    }
+}
+
+// Position of the `nth` (0-based) line containing `needle`, for errors found after parsing.
+fn locate(text: &str, needle: &str, nth: usize) -> (Option<usize>, Option<usize>) {
+    text.lines()
+        .enumerate()
+        .filter_map(|(i, line)| line.find(needle).map(|col| (Some(i + 1), Some(line[..col].chars().count() + 1))))
+        .nth(nth)
+        .unwrap_or((None, None))
+}
+
+impl CategoryConfig {
+    pub fn parse(text: &str) -> Result<Self, CategoryError> {
+        let config: CategoryConfig = serde_yaml::from_str(text).map_err(|e| {
+            let location = e.location();
+            CategoryError {
+                message: e.to_string(),
+                line: location.as_ref().map(|l| l.line()),
+                column: location.as_ref().map(|l| l.column()),
+            }
+        })?;
+        config.validate(text)?;
+        Ok(config)
+    }
+
+    fn validate(&self, text: &str) -> Result<(), CategoryError> {
+        let error_at = |message: String, needle: &str, nth: usize| {
+            let (line, column) = locate(text, needle, nth);
+            Err(CategoryError { message, line, column })
+        };
+        let error = |message: String, needle: &str| error_at(message, needle, 0);
+        if self.version != CATEGORY_FILE_VERSION {
+            return error(
+                format!("unsupported version {} (expected {})", self.version, CATEGORY_FILE_VERSION),
+                "version:",
+            );
+        }
+        for (section, groups) in self.sections() {
+            for (name, group) in groups {
+                let key = format!("{}:", name);
+                if group.categories.is_empty() {
+                    return error(format!("{}.{} has no categories", section, name), &key);
+                }
+                let mut ids = HashSet::new();
+                for category in &group.categories {
+                    let id_line = format!("id: {}", category.id);
+                    if category.id.trim().is_empty() {
+                        return error(format!("{}.{}: category without an id", section, name), "id:");
+                    }
+                    if !ids.insert(category.id.as_str()) {
+                        // Point at the repeat, not the first use.
+                        return error_at(format!("{}.{}: duplicate id '{}'", section, name, category.id), &id_line, 1);
+                    }
+                    if category.sources.iter().all(|s| s.trim().is_empty()) {
+                        return error(format!("{}.{}.{}: no sources", section, name, category.id), &id_line);
+                    }
+                }
+                if group.category(&group.default).is_none() {
+                    return error(
+                        format!("{}.{}: default '{}' is not a category id", section, name, group.default),
+                        &format!("default: {}", group.default),
+                    );
+                }
+            }
+        }
+        Ok(())
+    }
+
+    pub fn sections(&self) -> [(&'static str, &BTreeMap<String, CategoryGroup>); 3] {
+        [("tv_show", &self.tv_show), ("movie", &self.movie), ("animation", &self.animation)]
+    }
+
+    pub fn group(&self, section: &str, group: Option<&str>) -> FlashplexResult<(&str, &CategoryGroup)> {
+        let groups = self
+            .sections()
+            .into_iter()
+            .find(|(name, _)| *name == section)
+            .map(|(_, groups)| groups)
+            .ok_or_else(|| FlashplexError::invalid_input(format!("unknown category section '{}'", section)))?;
+        let found = match group {
+            Some(name) => groups.get_key_value(name),
+            None => groups.iter().next(),
+        };
+        found
+            .map(|(name, group)| (name.as_str(), group))
+            .ok_or_else(|| FlashplexError::invalid_input(format!("unknown category group in '{}'", section)))
+    }
+}
+
+// --- Evaluation ---
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SortBy {
+    Name,
+    Date,
+    Size,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SortOrder {
+    Asc,
+    Desc,
+}
+
+fn fold(text: &str) -> String {
+    text.nfc().collect::<String>().to_lowercase()
+}
+
+fn folder_name(item: &GdsItem) -> &str {
+    if !item.name.is_empty() {
+        return item.name.trim();
+    }
+    item.path.trim_end_matches('/').rsplit('/').next().unwrap_or("").trim()
+}
+
+/// Index buckets: a single Hangul syllable, Latin letter or digit, or "0Z".
+pub fn is_bucket_folder(name: &str) -> bool {
+    let mut chars = name.chars();
+    match (chars.next(), chars.next()) {
+        (Some(c), None) => c.is_ascii_alphanumeric() || ('가'..='힣').contains(&c),
+        _ => name.eq_ignore_ascii_case("0z"),
+    }
+}
+
+/// "Season 1", "시즌2", "S01" and the like.
+pub fn is_season_folder(name: &str) -> bool {
+    let name = fold(name);
+    if name.contains("season") || name.contains("시즌") {
+        return true;
+    }
+    let name = name.trim();
+    name.strip_prefix('s')
+        .is_some_and(|rest| (1..=3).contains(&rest.len()) && rest.chars().all(|c| c.is_ascii_digit()))
+}
+
+impl VirtualCategory {
+    pub fn matches(&self, item: &GdsItem) -> bool {
+        let hay = fold(&format!("{} {}", item.name, item.path));
+        let has = |kw: &String| hay.contains(&fold(kw));
+        if !self.include_keywords.is_empty() && !self.include_keywords.iter().any(has) {
+            return false;
+        }
+        if self.exclude_keywords.iter().any(has) {
+            return false;
+        }
+        if item.is_dir {
+            let name = folder_name(item);
+            if self.exclude_bucket_folders && is_bucket_folder(name) {
+                return false;
+            }
+            if self.exclude_season_folders && is_season_folder(name) {
+                return false;
+            }
+        }
+        true
+    }
+
+    /// Merges `listings` (one per source, same order as `sources`), drops duplicates
+    /// and items rejected by the rules, then sorts.
+    pub fn evaluate(&self, listings: Vec<Vec<GdsItem>>, sort_by: SortBy, order: SortOrder) -> Vec<GdsItem> {
+        let mut seen = HashSet::new();
+        let mut items: Vec<GdsItem> = listings
+            .into_iter()
+            .flatten()
+            .filter(|item| seen.insert(fold(item.path.trim_end_matches('/'))))
+            .filter(|item| self.matches(item))
+            .collect();
+        items.sort_by(|a, b| {
+            let by_name = || fold(&a.name).cmp(&fold(&b.name));
+            let key = match sort_by {
+                SortBy::Name => by_name(),
+                SortBy::Date => a.mtime.cmp(&b.mtime),
+                SortBy::Size => a.size.cmp(&b.size),
+            };
+            let key = if order == SortOrder::Desc { key.reverse() } else { key };
+            key.then_with(by_name)
+        });
+        items
+    }
+}
+
+// --- State & commands ---
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CategorySource {
+    User,
+    Bundled,
+}
+
+/// Rules in use; `categories-changed` payload.
+#[derive(Debug, Clone, Serialize)]
+pub struct CategoryView {
+    pub source: CategorySource,
+    /// Where user rules are read from, whether or not it exists.
+    pub file: String,
+    pub config: CategoryConfig,
+    /// Why the user file is not in use, if it is invalid.
+    pub error: Option<CategoryError>,
+}
+
+/// `categories-invalid` payload.
+#[derive(Debug, Clone, Serialize)]
+pub struct CategoryInvalid {
+    pub file: String,
+    #[serde(flatten)]
+    pub error: CategoryError,
+}
+
+pub enum CategoryReload {
+    Changed(CategoryView),
+    Invalid(CategoryInvalid),
+}
+
+// What the file looked like when it was last read.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum FileStamp {
+    Missing,
+    Present { modified: Option<SystemTime>, len: u64 },
+}
+
+pub struct CategoryStore {
+    file: PathBuf,
+    config: CategoryConfig,
+    source: CategorySource,
+    seen: Option<FileStamp>,
+    error: Option<CategoryError>,
+}
+
+impl CategoryStore {
+    pub fn open(file: PathBuf) -> Self {
+        let mut store = CategoryStore {
+            file,
+            config: CategoryConfig::parse(BUNDLED_CATEGORY_YAML).expect("bundled category.yaml is valid"),
+            source: CategorySource::Bundled,
+            seen: None,
+            error: None,
+        };
+        store.reload();
+        store
+    }
+
+    fn stamp(&self) -> FileStamp {
+        match std::fs::metadata(&self.file) {
+            Ok(meta) => FileStamp::Present { modified: meta.modified().ok(), len: meta.len() },
+            Err(_) => FileStamp::Missing,
+        }
+    }
+
+    /// Re-reads the user file if it changed since the last call. Invalid content
+    /// leaves the current rules untouched.
+    pub fn reload(&mut self) -> Option<CategoryReload> {
+        let stamp = self.stamp();
+        if self.seen.as_ref() == Some(&stamp) {
+            return None;
+        }
+        self.seen = Some(stamp.clone());
+
+        if stamp == FileStamp::Missing {
+            self.error = None;
+            if self.source == CategorySource::Bundled {
+                return None;
+            }
+            log_info!("CATEGORY", "{} removed; using bundled rules", self.file.display());
+            self.config = CategoryConfig::parse(BUNDLED_CATEGORY_YAML).expect("bundled category.yaml is valid");
+            self.source = CategorySource::Bundled;
+            return Some(CategoryReload::Changed(self.view()));
+        }
+
+        let parsed = std::fs::read_to_string(&self.file)
+            .map_err(|e| CategoryError { message: e.to_string(), line: None, column: None })
+            .and_then(|text| CategoryConfig::parse(&text));
+        match parsed {
+            Ok(config) => {
+                log_info!("CATEGORY", "Loaded {}", self.file.display());
+                self.config = config;
+                self.source = CategorySource::User;
+                self.error = None;
+                Some(CategoryReload::Changed(self.view()))
+            }
+            Err(error) => {
+                log_warn!("CATEGORY", "{} is invalid, keeping previous rules: {}", self.file.display(), error);
+                self.error = Some(error.clone());
+                Some(CategoryReload::Invalid(CategoryInvalid {
+                    file: self.file.display().to_string(),
+                    error,
+                }))
+            }
+        }
+    }
+
+    pub fn view(&self) -> CategoryView {
+        CategoryView {
+            source: self.source,
+            file: self.file.display().to_string(),
+            config: self.config.clone(),
+            error: self.error.clone(),
+        }
+    }
+}
+
+pub struct CategoryState(pub Arc<Mutex<CategoryStore>>);
+
+impl CategoryState {
+    pub fn open(app: &tauri::AppHandle) -> Self {
+        let dir = app
+            .path()
+            .app_config_dir()
+            .unwrap_or_else(|_| std::env::temp_dir().join("flashplex"));
+        CategoryState(Arc::new(Mutex::new(CategoryStore::open(dir.join(CATEGORY_FILE)))))
+    }
+
+    pub fn snapshot(&self) -> FlashplexResult<CategoryConfig> {
+        Ok(self.0.lock()?.config.clone())
+    }
+}
+
+/// Polls the user file and broadcasts every change (valid or not) to the webview.
+pub fn spawn_watcher(app: tauri::AppHandle) {
+    let spawned = std::thread::Builder::new()
+        .name("category-watch".to_string())
+        .spawn(move || loop {
+            std::thread::sleep(WATCH_INTERVAL);
+            let Some(state) = app.try_state::<CategoryState>() else {
+                continue;
+            };
+            let reload = match state.0.lock() {
+                Ok(mut store) => store.reload(),
+                Err(_) => None,
+            };
+            match reload {
+                Some(CategoryReload::Changed(view)) => {
+                    let _ = app.emit(CATEGORIES_CHANGED_EVENT, view);
+                }
+                Some(CategoryReload::Invalid(invalid)) => {
+                    let _ = app.emit(CATEGORIES_INVALID_EVENT, invalid);
+                }
+                None => {}
+            }
+        });
+    if let Err(e) = spawned {
+        log_warn!("CATEGORY", "category.yaml watcher not started: {}", e);
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SourceListing {
+    pub path: String,
+    pub count: usize,
+    pub error: Option<FlashplexError>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct VirtualCategoryList {
+    pub section: String,
+    pub group: String,
+    pub category: String,
+    pub label: String,
+    pub sources: Vec<SourceListing>,
+    /// Matching items before `offset`/`limit`.
+    pub total: usize,
+    pub has_more: bool,
+    pub list: Vec<GdsItem>,
+}
+
+#[tauri::command(rename_all = "snake_case")]
+pub fn get_categories(state: tauri::State<'_, CategoryState>) -> FlashplexResult<CategoryView> {
+    Ok(state.0.lock()?.view())
+}
+
+/// Lists `category` (default: the group's default) of `section`/`group` from the live server.
+/// A failing source is reported in `sources`; the call only fails when all of them do.
+#[tauri::command(rename_all = "snake_case")]
+#[allow(clippy::too_many_arguments)]
+pub async fn list_virtual_category(
+    gds: tauri::State<'_, GdsState>,
+    categories: tauri::State<'_, CategoryState>,
+    section: String,
+    group: Option<String>,
+    category: Option<String>,
+    source_id: Option<u32>,
+    sort_by: Option<SortBy>,
+    sort_order: Option<SortOrder>,
+    offset: Option<usize>,
+    limit: Option<usize>,
+) -> FlashplexResult<VirtualCategoryList> {
+    let config = categories.snapshot()?;
+    let (group_name, group) = config.group(&section, group.as_deref())?;
+    let id = category.unwrap_or_else(|| group.default.clone());
+    let rule = group
+        .category(&id)
+        .ok_or_else(|| FlashplexError::invalid_input(format!("unknown category '{}'", id)))?
+        .clone();
+    let group_name = group_name.to_string();
+
+    let client = gds.client()?;
+    let mut listings = Vec::new();
+    let mut sources = Vec::new();
+    let mut last_error = None;
+    for path in rule.sources.iter().filter(|p| !p.trim().is_empty()) {
+        let request = ExplorerListRequest {
+            media: MediaRef {
+                path: path.clone(),
+                source_id: source_id.unwrap_or(0),
+            },
+            limit: Some(SOURCE_LIST_LIMIT),
+        };
+        match client.explorer_list(&request).await {
+            Ok(res) => {
+                sources.push(SourceListing { path: path.clone(), count: res.list.len(), error: None });
+                listings.push(res.list);
+            }
+            Err(e) => {
+                log_warn!("CATEGORY", "{}: source {} failed: {}", rule.id, path, e);
+                sources.push(SourceListing { path: path.clone(), count: 0, error: Some(e.clone()) });
+                last_error = Some(e);
+            }
+        }
+    }
+    if listings.is_empty() {
+        if let Some(e) = last_error {
+            return Err(e);
+        }
+    }
+
+    let items = rule.evaluate(listings, sort_by.unwrap_or(SortBy::Date), sort_order.unwrap_or(SortOrder::Desc));
+    let total = items.len();
+    let offset = offset.unwrap_or(0).min(total);
+    let limit = limit.unwrap_or(total);
+    let list: Vec<GdsItem> = items.into_iter().skip(offset).take(limit).collect();
+    log_debug!("CATEGORY", "{}.{}.{}: {} of {} item(s)", section, group_name, rule.id, list.len(), total);
+    Ok(VirtualCategoryList {
+        section,
+        group: group_name,
+        category: rule.id,
+        label: rule.label,
+        sources,
+        total,
+        has_more: offset + list.len() < total,
+        list,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const GROUP: &str = "version: 1
+tv_show:
+  domestic:
+    default: a
+    categories:
+      - id: a
+        label: A
+        sources: [VIDEO/A]
+";
+
+    fn parse_err(text: &str) -> CategoryError {
+        CategoryConfig::parse(text).unwrap_err()
+    }
+
+    fn item(name: &str, path: &str, is_dir: bool, mtime: &str, size: u64) -> GdsItem {
+        GdsItem {
+            name: name.to_string(),
+            path: path.to_string(),
+            is_dir,
+            size: Some(size),
+            mtime: Some(mtime.to_string()),
+            source_id: None,
+            extra: serde_json::Map::new(),
+        }
+    }
+
+    fn rule(yaml_tail: &str) -> VirtualCategory {
+        let text = format!("id: v\nlabel: V\nsources: [VIDEO/A, VIDEO/B]\n{}", yaml_tail);
+        serde_yaml::from_str(&text).unwrap()
+    }
+
+    fn names(items: &[GdsItem]) -> Vec<&str> {
+        items.iter().map(|i| i.name.as_str()).collect()
+    }
+
+    #[test]
+    fn bundled_rules_parse() {
+        let config = CategoryConfig::parse(BUNDLED_CATEGORY_YAML).unwrap();
+        assert!(config.group("tv_show", None).is_ok());
+        assert!(config.group("music", None).is_err());
+    }
+
+    #[test]
+    fn unknown_key_is_reported_where_it_is() {
+        let err = parse_err(&format!("{}        colour: red\n", GROUP));
+        assert!(err.message.contains("colour"), "{}", err);
+        assert_eq!((err.line, err.column), (Some(9), Some(9)));
+
+        let err = parse_err(&GROUP.replace("tv_show:", "tv_shows:"));
+        assert!(err.message.contains("tv_shows"), "{}", err);
+        assert_eq!(err.line, Some(2));
+    }
+
+    #[test]
+    fn duplicate_id_points_at_the_repeat() {
+        let err = parse_err(&format!("{}      - id: a\n        label: A2\n        sources: [VIDEO/B]\n", GROUP));
+        assert!(err.message.contains("duplicate id 'a'"), "{}", err);
+        assert_eq!((err.line, err.column), (Some(9), Some(9)));
+    }
+
+    #[test]
+    fn default_must_be_a_category_id() {
+        let err = parse_err(&GROUP.replace("default: a", "default: b"));
+        assert!(err.message.contains("default 'b'"), "{}", err);
+        assert_eq!((err.line, err.column), (Some(4), Some(5)));
+
+        let err = parse_err(&GROUP.replace("version: 1", "version: 2"));
+        assert_eq!(err.line, Some(1));
+    }
+
+    #[test]
+    fn bucket_and_season_folders() {
+        for name in ["가", "힣", "A", "7", "0Z", "0z"] {
+            assert!(is_bucket_folder(name), "{}", name);
+        }
+        for name in ["가나", "AB", "ㄱ", "", "0Z1"] {
+            assert!(!is_bucket_folder(name), "{}", name);
+        }
+        for name in ["Season 1", "SEASON 02", "시즌2", "S01", "s1", "s123"] {
+            assert!(is_season_folder(name), "{}", name);
+        }
+        for name in ["S", "S1234", "Seoul", "Specials", "무한도전"] {
+            assert!(!is_season_folder(name), "{}", name);
+        }
+    }
+
+    #[test]
+    fn evaluate_merges_dedupes_filters_and_sorts() {
+        let rule = rule("exclude_keywords: [trailer]\nexclude_bucket_folders: true\nexclude_season_folders: true\n");
+        let listings = vec![
+            vec![
+                item("Bravo", "VIDEO/A/Bravo", true, "2024-01-02", 20),
+                item("가", "VIDEO/A/가", true, "2024-01-09", 0),
+                item("Alpha", "VIDEO/A/Alpha", true, "2024-01-01", 30),
+            ],
+            vec![
+                // Same folder reached through the second source, with a trailing slash.
+                item("Bravo", "VIDEO/A/Bravo/", true, "2024-01-05", 20),
+                item("Season 1", "VIDEO/B/Season 1", true, "2024-01-08", 0),
+                item("Charlie trailer", "VIDEO/B/Charlie trailer.mp4", false, "2024-01-07", 5),
+                item("Delta", "VIDEO/B/Delta", true, "2024-01-03", 10),
+            ],
+        ];
+
+        let by_date = rule.evaluate(listings.clone(), SortBy::Date, SortOrder::Desc);
+        assert_eq!(names(&by_date), ["Delta", "Bravo", "Alpha"]);
+        // The first listing wins a duplicate.
+        assert_eq!(by_date[1].mtime.as_deref(), Some("2024-01-02"));
+
+        assert_eq!(names(&rule.evaluate(listings.clone(), SortBy::Name, SortOrder::Asc)), ["Alpha", "Bravo", "Delta"]);
+        assert_eq!(names(&rule.evaluate(listings, SortBy::Size, SortOrder::Asc)), ["Delta", "Bravo", "Alpha"]);
+    }
+
+    #[test]
+    fn include_keywords_match_name_or_path_ignoring_case() {
+        let rule = rule("include_keywords: [On Air]\n");
+        let items = rule.evaluate(
+            vec![vec![
+                item("Show [on air]", "VIDEO/A/x", true, "1", 0),
+                item("Other", "VIDEO/A/ON AIR/Other", true, "2", 0),
+                item("Done", "VIDEO/A/Done", true, "3", 0),
+                // Bucket folders are only dropped when the rule asks for it.
+                item("가", "VIDEO/A/on air/가", true, "4", 0),
+            ]],
+            SortBy::Date,
+            SortOrder::Asc,
+        );
+        assert_eq!(names(&items), ["Show [on air]", "Other", "가"]);
+    }
+}
@@ -0,0 +1,172 @@
+// Secrets (GDS API keys) kept out of settings.json and localStorage.
+//
+// File-backed store: a ChaCha20-Poly1305 encrypted JSON map in the app data dir,
+// with its random key in a separate owner-only file. Not a substitute for the OS
+// keychain, but it keeps the key out of plain config files and logs.
+use crate::error::{FlashplexError, FlashplexResult};
+use crate::storage::write_atomic;
+use chacha20poly1305::aead::{Aead, AeadCore, KeyInit, OsRng};
+use chacha20poly1305::{ChaCha20Poly1305, Key, Nonce};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+const KEY_FILE: &str = "credentials.key";
+const DATA_FILE: &str = "credentials.bin";
+const MAGIC: &[u8; 4] = b"FPC1";
+const NONCE_LEN: usize = 12;
+
+/// Account name of the API key for the active server.
+pub const GDS_API_KEY: &str = "gds_api_key";
+
+pub struct CredentialStore {
+    key_file: PathBuf,
+    data_file: PathBuf,
+    secrets: HashMap<String, String>,
+}
+
+fn read_or_create_key(path: &Path) -> FlashplexResult<Key> {
+    if let Ok(bytes) = std::fs::read(path) {
+        if bytes.len() == 32 {
+            return Ok(*Key::from_slice(&bytes));
+        }
+        log_warn!("CREDENTIALS", "Ignoring malformed {}", path.display());
+    }
+    let key = ChaCha20Poly1305::generate_key(&mut OsRng);
+    write_atomic(path, key.as_slice())?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        std::fs::set_permissions(path, std::fs::Permissions::from_mode(0o600))
+            .map_err(|e| FlashplexError::internal(format!("chmod {}: {}", path.display(), e)))?;
+    }
+    Ok(key)
+}
+
+impl CredentialStore {
+    /// Opens the store in `dir`; unreadable data starts empty (the key can be re-entered).
+    pub fn open(dir: &Path) -> Self {
+        let mut store = CredentialStore {
+            key_file: dir.join(KEY_FILE),
+            data_file: dir.join(DATA_FILE),
+            secrets: HashMap::new(),
+        };
+        if let Ok(bytes) = std::fs::read(&store.data_file) {
+            match store.decrypt(&bytes) {
+                Ok(secrets) => store.secrets = secrets,
+                Err(e) => log_warn!("CREDENTIALS", "Ignoring unreadable {}: {}", store.data_file.display(), e),
+            }
+        }
+        store
+    }
+
+    fn cipher(&self) -> FlashplexResult<ChaCha20Poly1305> {
+        Ok(ChaCha20Poly1305::new(&read_or_create_key(&self.key_file)?))
+    }
+
+    fn decrypt(&self, bytes: &[u8]) -> FlashplexResult<HashMap<String, String>> {
+        if bytes.len() < MAGIC.len() + NONCE_LEN || &bytes[..MAGIC.len()] != MAGIC {
+            return Err(FlashplexError::internal("bad credentials header"));
+        }
+        let (nonce, sealed) = bytes[MAGIC.len()..].split_at(NONCE_LEN);
+        let plain = self
+            .cipher()?
+            .decrypt(Nonce::from_slice(nonce), sealed)
+            .map_err(|_| FlashplexError::internal("credentials failed to decrypt"))?;
+        Ok(serde_json::from_slice(&plain)?)
+    }
+
+    fn save(&self) -> FlashplexResult<()> {
+        let nonce = ChaCha20Poly1305::generate_nonce(&mut OsRng);
+        let sealed = self
+            .cipher()?
+            .encrypt(&nonce, serde_json::to_vec(&self.secrets)?.as_slice())
+            .map_err(|_| FlashplexError::internal("credentials failed to encrypt"))?;
+        let mut out = Vec::with_capacity(MAGIC.len() + NONCE_LEN + sealed.len());
+        out.extend_from_slice(MAGIC);
+        out.extend_from_slice(&nonce);
+        out.extend_from_slice(&sealed);
+        write_atomic(&self.data_file, &out)
+    }
+
+    pub fn get(&self, account: &str) -> Option<String> {
+        self.secrets.get(account).cloned()
+    }
+
+    /// Stores `secret` for `account`; an empty secret removes it.
+    pub fn set(&mut self, account: &str, secret: &str) -> FlashplexResult<()> {
+        let secret = secret.trim();
+        if secret.is_empty() {
+            return self.delete(account);
+        }
+        if self.secrets.get(account).map(String::as_str) == Some(secret) {
+            return Ok(());
+        }
+        self.secrets.insert(account.to_string(), secret.to_string());
+        self.save()
+    }
+
+    pub fn delete(&mut self, account: &str) -> FlashplexResult<()> {
+        if self.secrets.remove(account).is_some() {
+            self.save()?;
+        }
+        Ok(())
+    }
+}
+
+pub struct CredentialState(pub Arc<Mutex<CredentialStore>>);
+
+impl CredentialState {
+    pub fn open(app: &tauri::AppHandle) -> Self {
+        use tauri::Manager;
+        let dir = app
+            .path()
+            .app_data_dir()
+            .unwrap_or_else(|_| std::env::temp_dir().join("flashplex"));
+        CredentialState(Arc::new(Mutex::new(CredentialStore::open(&dir))))
+    }
+
+    pub fn get(&self, account: &str) -> Option<String> {
+        self.0.lock().ok().and_then(|store| store.get(account))
+    }
+}
+
+/// Masks `apikey=` query values so URLs can be logged.
+pub fn redact(text: &str) -> String {
+    const NEEDLE: &str = "apikey=";
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(pos) = rest.to_ascii_lowercase().find(NEEDLE) {
+        let value_start = pos + NEEDLE.len();
+        out.push_str(&rest[..value_start]);
+        let value_len = rest[value_start..]
+            .find(|c: char| c == '&' || c == '#' || c == '"' || c == '\'' || c.is_whitespace())
+            .unwrap_or(rest.len() - value_start);
+        if value_len > 0 {
+            out.push_str("***");
+        }
+        rest = &rest[value_start + value_len..];
+    }
+    out.push_str(rest);
+    out
+}
+
+// Commands
+
+/// Stores the API key and re-points the GDS client at it.
+#[tauri::command(rename_all = "snake_case")]
+pub fn set_api_key(
+    app: tauri::AppHandle,
+    state: tauri::State<'_, CredentialState>,
+    key: String,
+) -> FlashplexResult<()> {
+    state.0.lock()?.set(GDS_API_KEY, &key)?;
+    log_info!("CREDENTIALS", "API key {}", if key.trim().is_empty() { "cleared" } else { "updated" });
+    crate::settings::reconfigure_gds(&app);
+    Ok(())
+}
+
+#[tauri::command(rename_all = "snake_case")]
+pub fn has_api_key(state: tauri::State<'_, CredentialState>) -> FlashplexResult<bool> {
+    Ok(state.0.lock()?.get(GDS_API_KEY).is_some())
+}
@@ -0,0 +1,761 @@
+// Offline downloads.
+//
+// Episodes and movies are saved from `stream`, plus their `external_subtitle`
+// when the server has one, into the downloads dir (the `download_dir` setting,
+// else `downloads/` in the app data dir), one folder per item. The queue lives in
+// downloads.json in the app data dir, keyed like the watch history by
+// `source_id` + `bpath`, so it survives restarts. Transfers write to a `.part`
+// file and continue with an HTTP Range request after a pause, a dropped
+// connection or a restart. At most `download_concurrency` (setting) run at once.
+// Progress goes out as `download-progress`, queue changes as `downloads-changed`,
+// and `launch_mpv_player` plays a completed item from disk (`local_media`).
+use crate::error::{FlashplexError, FlashplexResult};
+use crate::gds::{encode_bpath, GdsClient, GdsState, MediaRef};
+use crate::storage::write_atomic;
+use serde::{Deserialize, Serialize};
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU8, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tauri::{Emitter, Manager};
+use tauri_plugin_http::reqwest;
+
+const QUEUE_FILE: &str = "downloads.json";
+const QUEUE_VERSION: u32 = 1;
+pub const DOWNLOAD_PROGRESS_EVENT: &str = "download-progress";
+pub const DOWNLOADS_CHANGED_EVENT: &str = "downloads-changed";
+const PROGRESS_INTERVAL: Duration = Duration::from_millis(500);
+// Left free on the target volume on top of the remaining bytes.
+const FREE_SPACE_MARGIN: u64 = 512 * 1024 * 1024;
+
+// Control flag of a running transfer, checked between chunks.
+const RUN: u8 = 0;
+const PAUSE: u8 = 1;
+const CANCEL: u8 = 2;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DownloadStatus {
+    Queued,
+    Downloading,
+    Paused,
+    Completed,
+    Failed,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DownloadItem {
+    /// `source_id:bpath`.
+    pub id: String,
+    pub title: String,
+    pub path: String,
+    pub source_id: u32,
+    pub status: DownloadStatus,
+    pub file: PathBuf,
+    pub subtitle_file: Option<PathBuf>,
+    pub bytes_done: u64,
+    /// Unknown until the server answered.
+    pub bytes_total: Option<u64>,
+    pub error: Option<String>,
+    /// Unix seconds.
+    pub added_at: i64,
+    pub completed_at: Option<i64>,
+}
+
+impl DownloadItem {
+    fn media(&self) -> MediaRef {
+        MediaRef {
+            path: self.path.clone(),
+            source_id: self.source_id,
+        }
+    }
+
+    fn part_file(&self) -> PathBuf {
+        let mut name = self.file.file_name().unwrap_or_default().to_os_string();
+        name.push(".part");
+        self.file.with_file_name(name)
+    }
+
+    // The item's folder; removed with it.
+    fn folder(&self) -> Option<&Path> {
+        self.file.parent()
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct DownloadRequest {
+    pub title: String,
+    pub path: String,
+    #[serde(default)]
+    pub source_id: u32,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DownloadProgress {
+    pub id: String,
+    pub bytes_done: u64,
+    pub bytes_total: Option<u64>,
+    pub bytes_per_sec: u64,
+}
+
+#[derive(Serialize, Deserialize)]
+struct QueueFile {
+    version: u32,
+    items: Vec<DownloadItem>,
+}
+
+fn download_id(source_id: u32, path: &str) -> String {
+    format!("{}:{}", source_id, encode_bpath(path))
+}
+
+fn now_unix() -> i64 {
+    chrono::Utc::now().timestamp()
+}
+
+// "<source_id>-<hash>/<file name>" under the downloads dir.
+fn target_file(dir: &Path, source_id: u32, path: &str) -> PathBuf {
+    let mut hasher = DefaultHasher::new();
+    encode_bpath(path).hash(&mut hasher);
+    let name: String = path
+        .rsplit('/')
+        .find(|part| !part.trim().is_empty())
+        .unwrap_or("video")
+        .chars()
+        .map(|c| if matches!(c, '\\' | ':' | '*' | '?' | '"' | '<' | '>' | '|') || c.is_control() { '_' } else { c })
+        .collect();
+    dir.join(format!("{}-{:016x}", source_id, hasher.finish())).join(name)
+}
+
+#[derive(Debug)]
+enum Outcome {
+    Done,
+    Paused,
+    Canceled,
+}
+
+pub struct DownloadManager {
+    file: PathBuf,
+    dir: PathBuf,
+    concurrency: usize,
+    items: Vec<DownloadItem>,
+    // Running transfers.
+    active: HashMap<String, Arc<AtomicU8>>,
+}
+
+impl DownloadManager {
+    /// Loads `file`; transfers that were running when the app quit are queued again.
+    pub fn open(file: PathBuf, dir: PathBuf, concurrency: usize) -> Self {
+        let mut items = match std::fs::read_to_string(&file) {
+            Ok(text) => match serde_json::from_str::<QueueFile>(&text) {
+                Ok(parsed) => parsed.items,
+                Err(e) => {
+                    log_warn!("DOWNLOAD", "Ignoring unreadable {}: {}", file.display(), e);
+                    Vec::new()
+                }
+            },
+            Err(_) => Vec::new(),
+        };
+        for item in &mut items {
+            if item.status == DownloadStatus::Downloading {
+                item.status = DownloadStatus::Queued;
+            }
+            if item.status == DownloadStatus::Completed && !item.file.is_file() {
+                item.status = DownloadStatus::Failed;
+                item.error = Some("downloaded file is missing".to_string());
+            }
+        }
+        log_info!("DOWNLOAD", "Loaded {} queued downloads from {}", items.len(), file.display());
+        DownloadManager {
+            file,
+            dir,
+            concurrency: concurrency.max(1),
+            items,
+            active: HashMap::new(),
+        }
+    }
+
+    pub fn list(&self) -> Vec<DownloadItem> {
+        self.items.clone()
+    }
+
+    fn save(&self) -> FlashplexResult<()> {
+        let file = QueueFile {
+            version: QUEUE_VERSION,
+            items: self.items.clone(),
+        };
+        write_atomic(&self.file, &serde_json::to_vec_pretty(&file)?)
+    }
+
+    fn save_logged(&self) {
+        if let Err(e) = self.save() {
+            log_warn!("DOWNLOAD", "Write failed: {}", e);
+        }
+    }
+
+    fn item_mut(&mut self, id: &str) -> FlashplexResult<&mut DownloadItem> {
+        self.items
+            .iter_mut()
+            .find(|item| item.id == id)
+            .ok_or_else(|| FlashplexError::invalid_input(format!("no download '{}'", id)))
+    }
+
+    /// Adds `req` to the queue. An item already in it is returned as-is, or queued
+    /// again when it had failed.
+    pub fn enqueue(&mut self, req: &DownloadRequest) -> FlashplexResult<DownloadItem> {
+        if req.path.trim().is_empty() {
+            return Err(FlashplexError::invalid_input("path is required"));
+        }
+        let id = download_id(req.source_id, &req.path);
+        if let Some(item) = self.items.iter_mut().find(|item| item.id == id) {
+            if item.status == DownloadStatus::Failed {
+                item.status = DownloadStatus::Queued;
+                item.error = None;
+            }
+            let item = item.clone();
+            self.save()?;
+            return Ok(item);
+        }
+        let item = DownloadItem {
+            file: target_file(&self.dir, req.source_id, &req.path),
+            id,
+            title: req.title.clone(),
+            path: req.path.clone(),
+            source_id: req.source_id,
+            status: DownloadStatus::Queued,
+            subtitle_file: None,
+            bytes_done: 0,
+            bytes_total: None,
+            error: None,
+            added_at: now_unix(),
+            completed_at: None,
+        };
+        self.items.push(item.clone());
+        self.save()?;
+        Ok(item)
+    }
+
+    pub fn pause(&mut self, id: &str) -> FlashplexResult<()> {
+        if let Some(control) = self.active.get(id) {
+            // The transfer marks the item paused when it stops.
+            control.store(PAUSE, Ordering::Relaxed);
+            return Ok(());
+        }
+        let item = self.item_mut(id)?;
+        if item.status == DownloadStatus::Queued {
+            item.status = DownloadStatus::Paused;
+            self.save()?;
+        }
+        Ok(())
+    }
+
+    pub fn resume(&mut self, id: &str) -> FlashplexResult<()> {
+        let item = self.item_mut(id)?;
+        if matches!(item.status, DownloadStatus::Paused | DownloadStatus::Failed) {
+            item.status = DownloadStatus::Queued;
+            item.error = None;
+            self.save()?;
+        }
+        Ok(())
+    }
+
+    /// Stops `id` if it is running and removes it with its files (completed ones too).
+    pub fn cancel(&mut self, id: &str) -> FlashplexResult<()> {
+        if let Some(control) = self.active.get(id) {
+            // The transfer removes the item once it has stopped writing.
+            control.store(CANCEL, Ordering::Relaxed);
+            return Ok(());
+        }
+        let index = self
+            .items
+            .iter()
+            .position(|item| item.id == id)
+            .ok_or_else(|| FlashplexError::invalid_input(format!("no download '{}'", id)))?;
+        let item = self.items.remove(index);
+        remove_files(&item);
+        self.save()
+    }
+
+    // Marks up to the concurrency limit of queued items as running.
+    fn start_next(&mut self) -> Vec<(DownloadItem, Arc<AtomicU8>)> {
+        let mut started = Vec::new();
+        while self.active.len() < self.concurrency {
+            let Some(item) = self.items.iter_mut().find(|item| item.status == DownloadStatus::Queued) else {
+                break;
+            };
+            item.status = DownloadStatus::Downloading;
+            item.error = None;
+            let control = Arc::new(AtomicU8::new(RUN));
+            self.active.insert(item.id.clone(), control.clone());
+            started.push((item.clone(), control));
+        }
+        if !started.is_empty() {
+            self.save_logged();
+        }
+        started
+    }
+
+    fn set_progress(&mut self, id: &str, bytes_done: u64, bytes_total: Option<u64>) {
+        if let Ok(item) = self.item_mut(id) {
+            item.bytes_done = bytes_done;
+            item.bytes_total = bytes_total;
+        }
+    }
+
+    fn finish(&mut self, id: &str, result: FlashplexResult<(Outcome, Option<PathBuf>)>) {
+        self.active.remove(id);
+        let Some(index) = self.items.iter().position(|item| item.id == id) else {
+            return;
+        };
+        let item = &mut self.items[index];
+        match result {
+            Ok((Outcome::Done, subtitle_file)) => {
+                item.status = DownloadStatus::Completed;
+                item.subtitle_file = subtitle_file;
+                item.completed_at = Some(now_unix());
+                if let Ok(meta) = std::fs::metadata(&item.file) {
+                    item.bytes_done = meta.len();
+                    item.bytes_total = Some(meta.len());
+                }
+                log_info!("DOWNLOAD", "Completed {}", item.file.display());
+            }
+            Ok((Outcome::Paused, _)) => item.status = DownloadStatus::Paused,
+            Ok((Outcome::Canceled, _)) => {
+                let item = self.items.remove(index);
+                remove_files(&item);
+                log_info!("DOWNLOAD", "Canceled {}", item.path);
+            }
+            Err(e) => {
+                log_warn!("DOWNLOAD", "{} failed: {}", item.path, e);
+                item.status = DownloadStatus::Failed;
+                item.error = Some(e.to_string());
+            }
+        }
+        self.save_logged();
+    }
+
+    /// Local file and subtitle of a completed download of `path`, for playback.
+    pub fn local_media(&self, path: &str, source_id: u32) -> Option<(String, Option<String>)> {
+        let id = download_id(source_id, path);
+        let item = self
+            .items
+            .iter()
+            .find(|item| item.id == id && item.status == DownloadStatus::Completed)?;
+        if !item.file.is_file() {
+            return None;
+        }
+        let subtitle = item
+            .subtitle_file
+            .as_ref()
+            .filter(|file| file.is_file())
+            .map(|file| file.to_string_lossy().to_string());
+        Some((item.file.to_string_lossy().to_string(), subtitle))
+    }
+}
+
+fn remove_files(item: &DownloadItem) {
+    if let Some(folder) = item.folder() {
+        if let Err(e) = std::fs::remove_dir_all(folder) {
+            if e.kind() != std::io::ErrorKind::NotFound {
+                log_warn!("DOWNLOAD", "Could not remove {}: {}", folder.display(), e);
+            }
+        }
+    }
+}
+
+/// Bytes available to this user on the volume holding `dir`, where we can tell.
+#[cfg(unix)]
+#[allow(clippy::unnecessary_cast)]
+fn available_space(dir: &Path) -> Option<u64> {
+    use std::os::unix::ffi::OsStrExt;
+    let path = std::ffi::CString::new(dir.as_os_str().as_bytes()).ok()?;
+    let mut stat: libc::statvfs = unsafe { std::mem::zeroed() };
+    if unsafe { libc::statvfs(path.as_ptr(), &mut stat) } != 0 {
+        return None;
+    }
+    Some(stat.f_bavail as u64 * stat.f_frsize as u64)
+}
+
+#[cfg(not(unix))]
+fn available_space(_dir: &Path) -> Option<u64> {
+    None
+}
+
+fn io_error(path: &Path, e: std::io::Error) -> FlashplexError {
+    FlashplexError::internal(format!("{}: {}", path.display(), e))
+}
+
+// "bytes 100-199/1000" -> 1000
+fn content_range_total(response: &reqwest::Response) -> Option<u64> {
+    response
+        .headers()
+        .get(reqwest::header::CONTENT_RANGE)?
+        .to_str()
+        .ok()?
+        .rsplit_once('/')?
+        .1
+        .trim()
+        .parse()
+        .ok()
+}
+
+// mpv picks the subtitle parser from the extension as well as the content.
+fn subtitle_extension(bytes: &[u8]) -> &'static str {
+    let head = String::from_utf8_lossy(&bytes[..bytes.len().min(64)]);
+    let head = head.trim_start_matches('\u{feff}').trim_start();
+    if head.starts_with("[Script Info]") {
+        "ass"
+    } else if head.starts_with("WEBVTT") {
+        "vtt"
+    } else {
+        "srt"
+    }
+}
+
+// The transfer itself. Returns how it stopped and the subtitle file, if any.
+async fn transfer(app: &tauri::AppHandle, item: &DownloadItem, control: &AtomicU8) -> FlashplexResult<(Outcome, Option<PathBuf>)> {
+    let client = app
+        .try_state::<GdsState>()
+        .ok_or(FlashplexError::ServerNotConfigured)?
+        .client()?;
+    let on_progress = |progress: DownloadProgress| {
+        if let Some(state) = app.try_state::<DownloadsState>() {
+            if let Ok(mut manager) = state.0.lock() {
+                manager.set_progress(&progress.id, progress.bytes_done, progress.bytes_total);
+            }
+        }
+        let _ = app.emit(DOWNLOAD_PROGRESS_EVENT, progress);
+    };
+    fetch(&client, item, control, available_space, on_progress).await
+}
+
+// `transfer` without the app: the free-space probe and progress sink are passed in.
+async fn fetch(
+    client: &GdsClient,
+    item: &DownloadItem,
+    control: &AtomicU8,
+    free_space: fn(&Path) -> Option<u64>,
+    mut on_progress: impl FnMut(DownloadProgress),
+) -> FlashplexResult<(Outcome, Option<PathBuf>)> {
+    let media = item.media();
+    let part = item.part_file();
+    let folder = item.folder().unwrap_or(Path::new("."));
+    std::fs::create_dir_all(folder).map_err(|e| io_error(folder, e))?;
+
+    let mut offset = std::fs::metadata(&part).map(|m| m.len()).unwrap_or(0);
+    let mut response = client.open_stream(&media, offset).await?;
+    let status = response.status();
+    let total = if status == reqwest::StatusCode::PARTIAL_CONTENT && offset > 0 {
+        content_range_total(&response)
+    } else if status == reqwest::StatusCode::RANGE_NOT_SATISFIABLE && offset > 0 {
+        // The part file already holds everything the server has.
+        if content_range_total(&response) != Some(offset) {
+            let _ = std::fs::remove_file(&part);
+            return Err(FlashplexError::internal("partial file doesn't match the server copy; it will restart"));
+        }
+        Some(offset)
+    } else if status.is_success() {
+        // No Range support: start over.
+        offset = 0;
+        response.content_length()
+    } else {
+        return Err(FlashplexError::from_status(status.as_u16(), "stream"));
+    };
+
+    if let (Some(total), Some(free)) = (total, free_space(folder)) {
+        let needed = total.saturating_sub(offset);
+        if free < needed + FREE_SPACE_MARGIN {
+            return Err(FlashplexError::internal(format!(
+                "Not enough free space: {} MB needed, {} MB available",
+                (needed + FREE_SPACE_MARGIN) / (1024 * 1024),
+                free / (1024 * 1024)
+            )));
+        }
+    }
+
+    if status != reqwest::StatusCode::RANGE_NOT_SATISFIABLE {
+        let mut file = if offset > 0 {
+            std::fs::OpenOptions::new().append(true).open(&part)
+        } else {
+            std::fs::File::create(&part)
+        }
+        .map_err(|e| io_error(&part, e))?;
+        let mut done = offset;
+        let mut last_emit = Instant::now();
+        let mut window_bytes = done;
+        while let Some(chunk) = response.chunk().await? {
+            match control.load(Ordering::Relaxed) {
+                PAUSE => return Ok((Outcome::Paused, None)),
+                CANCEL => return Ok((Outcome::Canceled, None)),
+                _ => {}
+            }
+            file.write_all(&chunk).map_err(|e| io_error(&part, e))?;
+            done += chunk.len() as u64;
+            let elapsed = last_emit.elapsed();
+            if elapsed >= PROGRESS_INTERVAL {
+                on_progress(DownloadProgress {
+                    id: item.id.clone(),
+                    bytes_done: done,
+                    bytes_total: total,
+                    bytes_per_sec: ((done - window_bytes) as f64 / elapsed.as_secs_f64()) as u64,
+                });
+                last_emit = Instant::now();
+                window_bytes = done;
+            }
+        }
+        file.flush().map_err(|e| io_error(&part, e))?;
+        if total.is_some_and(|total| done < total) {
+            return Err(FlashplexError::Network {
+                timeout: false,
+                message: format!("connection closed after {} of {} bytes", done, total.unwrap_or_default()),
+            });
+        }
+    }
+    std::fs::rename(&part, &item.file).map_err(|e| io_error(&item.file, e))?;
+
+    // A missing subtitle doesn't fail the download.
+    let subtitle_file = match client.external_subtitle(&media).await {
+        Ok(Some(bytes)) => {
+            let file = item.file.with_extension(subtitle_extension(&bytes));
+            match std::fs::write(&file, &bytes) {
+                Ok(()) => Some(file),
+                Err(e) => {
+                    log_warn!("DOWNLOAD", "Could not save subtitle {}: {}", file.display(), e);
+                    None
+                }
+            }
+        }
+        Ok(None) => None,
+        Err(e) => {
+            log_warn!("DOWNLOAD", "No subtitle for {}: {}", item.path, e);
+            None
+        }
+    };
+    Ok((Outcome::Done, subtitle_file))
+}
+
+pub struct DownloadsState(pub Arc<Mutex<DownloadManager>>);
+
+impl DownloadsState {
+    pub fn open(app: &tauri::AppHandle, settings: &crate::settings::Settings) -> Self {
+        let data_dir = app
+            .path()
+            .app_data_dir()
+            .unwrap_or_else(|_| std::env::temp_dir().join("flashplex"));
+        let manager = DownloadManager::open(
+            data_dir.join(QUEUE_FILE),
+            download_dir(&data_dir, settings),
+            settings.download_concurrency as usize,
+        );
+        DownloadsState(Arc::new(Mutex::new(manager)))
+    }
+
+    pub fn local_media(&self, path: &str, source_id: u32) -> Option<(String, Option<String>)> {
+        self.0.lock().ok()?.local_media(path, source_id)
+    }
+}
+
+fn download_dir(data_dir: &Path, settings: &crate::settings::Settings) -> PathBuf {
+    settings
+        .download_dir
+        .as_deref()
+        .map(str::trim)
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .unwrap_or_else(|| data_dir.join("downloads"))
+}
+
+/// Takes over a changed download dir (for new items) and concurrency limit.
+pub fn apply_settings(app: &tauri::AppHandle, settings: &crate::settings::Settings) {
+    let Some(state) = app.try_state::<DownloadsState>() else {
+        return;
+    };
+    if let Ok(mut manager) = state.0.lock() {
+        let data_dir = manager.file.parent().map(Path::to_path_buf).unwrap_or_default();
+        manager.dir = download_dir(&data_dir, settings);
+        manager.concurrency = (settings.download_concurrency as usize).max(1);
+    }
+    pump(app);
+}
+
+fn downloads_changed(app: &tauri::AppHandle) {
+    if let Some(state) = app.try_state::<DownloadsState>() {
+        if let Ok(manager) = state.0.lock() {
+            let _ = app.emit(DOWNLOADS_CHANGED_EVENT, manager.list());
+        }
+    }
+}
+
+/// Starts queued items while there is room under the concurrency limit.
+pub fn pump(app: &tauri::AppHandle) {
+    let Some(state) = app.try_state::<DownloadsState>() else {
+        return;
+    };
+    let started = match state.0.lock() {
+        Ok(mut manager) => manager.start_next(),
+        Err(_) => return,
+    };
+    if started.is_empty() {
+        return;
+    }
+    downloads_changed(app);
+    for (item, control) in started {
+        let app = app.clone();
+        log_info!("DOWNLOAD", "Starting {} -> {}", item.path, item.file.display());
+        tauri::async_runtime::spawn(async move {
+            let result = transfer(&app, &item, &control).await;
+            if let Some(state) = app.try_state::<DownloadsState>() {
+                if let Ok(mut manager) = state.0.lock() {
+                    manager.finish(&item.id, result);
+                }
+            }
+            downloads_changed(&app);
+            pump(&app);
+        });
+    }
+}
+
+// Commands
+
+#[tauri::command(rename_all = "snake_case")]
+pub fn download_list(state: tauri::State<'_, DownloadsState>) -> FlashplexResult<Vec<DownloadItem>> {
+    Ok(state.0.lock()?.list())
+}
+
+#[tauri::command(rename_all = "snake_case")]
+pub fn download_enqueue(
+    app: tauri::AppHandle,
+    state: tauri::State<'_, DownloadsState>,
+    request: DownloadRequest,
+) -> FlashplexResult<DownloadItem> {
+    let item = state.0.lock()?.enqueue(&request)?;
+    downloads_changed(&app);
+    pump(&app);
+    Ok(item)
+}
+
+#[tauri::command(rename_all = "snake_case")]
+pub fn download_pause(app: tauri::AppHandle, state: tauri::State<'_, DownloadsState>, id: String) -> FlashplexResult<()> {
+    state.0.lock()?.pause(&id)?;
+    downloads_changed(&app);
+    Ok(())
+}
+
+#[tauri::command(rename_all = "snake_case")]
+pub fn download_resume(app: tauri::AppHandle, state: tauri::State<'_, DownloadsState>, id: String) -> FlashplexResult<()> {
+    state.0.lock()?.resume(&id)?;
+    downloads_changed(&app);
+    pump(&app);
+    Ok(())
+}
+
+/// Cancels a queued or running download, or deletes a finished one.
+#[tauri::command(rename_all = "snake_case")]
+pub fn download_cancel(app: tauri::AppHandle, state: tauri::State<'_, DownloadsState>, id: String) -> FlashplexResult<()> {
+    state.0.lock()?.cancel(&id)?;
+    downloads_changed(&app);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::http_cache::ResponseCache;
+    use crate::test_http::{response, serve, StandIn};
+
+    const PATH: &str = "/VIDEO/show.mkv";
+
+    // An item in its own scratch dir, with `part` already downloaded.
+    fn item(name: &str, part: &[u8]) -> DownloadItem {
+        let dir = std::env::temp_dir().join(format!("flashplex-downloads-{}-{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        let item = DownloadItem {
+            id: download_id(1, PATH),
+            title: "show".to_string(),
+            path: PATH.to_string(),
+            source_id: 1,
+            status: DownloadStatus::Downloading,
+            file: target_file(&dir, 1, PATH),
+            subtitle_file: None,
+            bytes_done: 0,
+            bytes_total: None,
+            error: None,
+            added_at: 0,
+            completed_at: None,
+        };
+        if !part.is_empty() {
+            std::fs::create_dir_all(item.folder().unwrap()).unwrap();
+            std::fs::write(item.part_file(), part).unwrap();
+        }
+        item
+    }
+
+    fn run(server: &StandIn, item: &DownloadItem, free_space: fn(&Path) -> Option<u64>) -> FlashplexResult<(Outcome, Option<PathBuf>)> {
+        let cache = Arc::new(Mutex::new(ResponseCache::new()));
+        let client = GdsClient::new(reqwest::Client::new(), cache, &server.base_url, "secret");
+        let control = AtomicU8::new(RUN);
+        tauri::async_runtime::block_on(fetch(&client, item, &control, free_space, |_| {}))
+    }
+
+    fn no_subtitle() -> Vec<u8> {
+        response("404 Not Found", &[], b"")
+    }
+
+    #[test]
+    fn partial_content_appends_to_the_part_file() {
+        let item = item("resume", b"0123");
+        let server = serve(vec![
+            response("206 Partial Content", &[("Content-Range", "bytes 4-9/10")], b"456789"),
+            response("200 OK", &[], b"WEBVTT\n\n00:00.000 --> 00:01.000\nhi\n"),
+        ]);
+        let (outcome, subtitle) = run(&server, &item, |_| None).unwrap();
+        assert!(matches!(outcome, Outcome::Done));
+        assert_eq!(std::fs::read(&item.file).unwrap(), b"0123456789");
+        assert!(!item.part_file().exists());
+        assert_eq!(subtitle, Some(item.file.with_extension("vtt")));
+        assert!(server.requests()[0].to_ascii_lowercase().contains("range: bytes=4-"));
+    }
+
+    #[test]
+    fn full_response_restarts_from_zero() {
+        let item = item("restart", b"stale");
+        let server = serve(vec![response("200 OK", &[], b"0123456789"), no_subtitle()]);
+        let (outcome, subtitle) = run(&server, &item, |_| None).unwrap();
+        assert!(matches!(outcome, Outcome::Done));
+        assert_eq!(std::fs::read(&item.file).unwrap(), b"0123456789");
+        assert_eq!(subtitle, None);
+    }
+
+    #[test]
+    fn range_not_satisfiable_with_matching_total_completes() {
+        let item = item("complete", b"0123456789");
+        let server = serve(vec![
+            response("416 Range Not Satisfiable", &[("Content-Range", "bytes */10")], b""),
+            no_subtitle(),
+        ]);
+        let (outcome, _) = run(&server, &item, |_| None).unwrap();
+        assert!(matches!(outcome, Outcome::Done));
+        assert_eq!(std::fs::read(&item.file).unwrap(), b"0123456789");
+    }
+
+    #[test]
+    fn range_not_satisfiable_with_other_total_drops_the_part_file() {
+        let item = item("mismatch", b"0123");
+        let server = serve(vec![response("416 Range Not Satisfiable", &[("Content-Range", "bytes */10")], b"")]);
+        assert!(run(&server, &item, |_| None).is_err());
+        assert!(!item.part_file().exists());
+        assert!(!item.file.exists());
+    }
+
+    #[test]
+    fn refuses_when_the_volume_is_short_on_space() {
+        let item = item("space", b"");
+        let server = serve(vec![response("200 OK", &[], b"0123456789")]);
+        let err = run(&server, &item, |_| Some(FREE_SPACE_MARGIN)).unwrap_err();
+        assert!(err.to_string().contains("Not enough free space"), "{}", err);
+        assert!(!item.file.exists());
+    }
+}
@@ -0,0 +1,239 @@
+// Error type shared by every Tauri command.
+//
+// Serialized as `{ code, message, retryable, ...fields }` so the frontend can
+// switch on `code` instead of matching English text.
+use serde::ser::SerializeMap;
+use serde::{Serialize, Serializer};
+use std::fmt;
+use tauri_plugin_http::reqwest;
+
+// mpv variants are only constructed by the libmpv players (macOS, Linux).
+#[cfg_attr(not(any(target_os = "macos", target_os = "linux")), allow(dead_code))]
+#[derive(Debug, Clone)]
+pub enum FlashplexError {
+    PlayerNotActive,
+    /// The player thread didn't answer `command` in time (e.g. still initializing).
+    PlayerBusy { command: String },
+    /// Creating another player would exceed the configured maximum.
+    PlayerLimit { max: usize },
+    UnsupportedPlatform { feature: String },
+    ServerNotConfigured,
+    InvalidInput { message: String },
+    Network { message: String, timeout: bool },
+    Auth { status: u16 },
+    Http { status: u16, endpoint: String },
+    ServerJson { endpoint: String, message: String },
+    Server { endpoint: String, message: String },
+    MpvInit { profile: String, code: Option<i32>, message: String },
+    MpvCommand { command: String, code: Option<i32>, message: String },
+    WindowNotFound { label: String },
+    MainThread { message: String },
+    Internal { message: String },
+}
+
+pub type FlashplexResult<T> = Result<T, FlashplexError>;
+
+impl FlashplexError {
+    pub fn code(&self) -> &'static str {
+        match self {
+            FlashplexError::PlayerNotActive => "PlayerNotActive",
+            FlashplexError::PlayerBusy { .. } => "PlayerBusy",
+            FlashplexError::PlayerLimit { .. } => "PlayerLimit",
+            FlashplexError::UnsupportedPlatform { .. } => "UnsupportedPlatform",
+            FlashplexError::ServerNotConfigured => "ServerNotConfigured",
+            FlashplexError::InvalidInput { .. } => "InvalidInput",
+            FlashplexError::Network { .. } => "Network",
+            FlashplexError::Auth { .. } => "Auth",
+            FlashplexError::Http { .. } => "Http",
+            FlashplexError::ServerJson { .. } => "ServerJson",
+            FlashplexError::Server { .. } => "Server",
+            FlashplexError::MpvInit { .. } => "MpvInit",
+            FlashplexError::MpvCommand { .. } => "MpvCommand",
+            FlashplexError::WindowNotFound { .. } => "WindowNotFound",
+            FlashplexError::MainThread { .. } => "MainThread",
+            FlashplexError::Internal { .. } => "Internal",
+        }
+    }
+
+    /// Whether retrying the same call later can reasonably succeed.
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            FlashplexError::Network { .. } | FlashplexError::PlayerBusy { .. } => true,
+            FlashplexError::Http { status, .. } => *status == 429 || *status >= 500,
+            _ => false,
+        }
+    }
+
+    pub fn unsupported(feature: &str) -> Self {
+        FlashplexError::UnsupportedPlatform { feature: feature.to_string() }
+    }
+
+    pub fn invalid_input(message: impl Into<String>) -> Self {
+        FlashplexError::InvalidInput { message: message.into() }
+    }
+
+    pub fn window_not_found(label: &str) -> Self {
+        FlashplexError::WindowNotFound { label: label.to_string() }
+    }
+
+    pub fn channel_closed(what: &str) -> Self {
+        FlashplexError::MainThread { message: format!("{} channel closed", what) }
+    }
+
+    pub fn internal(message: impl Into<String>) -> Self {
+        FlashplexError::Internal { message: message.into() }
+    }
+
+    /// Maps a non-2xx HTTP status; 401/403 are reported as Auth so the UI can prompt for a key.
+    pub fn from_status(status: u16, endpoint: &str) -> Self {
+        if status == 401 || status == 403 {
+            FlashplexError::Auth { status }
+        } else {
+            FlashplexError::Http { status, endpoint: endpoint.to_string() }
+        }
+    }
+
+    #[cfg(any(target_os = "macos", target_os = "linux"))]
+    pub fn mpv_command(command: &str, err: libmpv2::Error) -> Self {
+        FlashplexError::MpvCommand {
+            command: command.to_string(),
+            code: mpv_error_code(&err),
+            message: err.to_string(),
+        }
+    }
+
+    #[cfg(any(target_os = "macos", target_os = "linux"))]
+    pub fn mpv_init(profile: &str, err: libmpv2::Error) -> Self {
+        FlashplexError::MpvInit {
+            profile: profile.to_string(),
+            code: mpv_error_code(&err),
+            message: err.to_string(),
+        }
+    }
+}
+
+#[cfg(any(target_os = "macos", target_os = "linux"))]
+fn mpv_error_code(err: &libmpv2::Error) -> Option<i32> {
+    match err {
+        libmpv2::Error::Raw(code) => Some(*code),
+        libmpv2::Error::Loadfile { error } => mpv_error_code(error),
+        _ => None,
+    }
+}
+
+impl fmt::Display for FlashplexError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FlashplexError::PlayerNotActive => write!(f, "Player not active"),
+            FlashplexError::PlayerBusy { command } => write!(f, "Player busy: '{}' timed out", command),
+            FlashplexError::PlayerLimit { max } => write!(f, "Player limit reached ({} running)", max),
+            FlashplexError::UnsupportedPlatform { feature } => {
+                write!(f, "{} is not supported on this platform", feature)
+            }
+            FlashplexError::ServerNotConfigured => write!(f, "GDS server not configured"),
+            FlashplexError::InvalidInput { message } => write!(f, "Invalid input: {}", message),
+            FlashplexError::Network { message, timeout } => {
+                if *timeout {
+                    write!(f, "Network timeout: {}", message)
+                } else {
+                    write!(f, "Network error: {}", message)
+                }
+            }
+            FlashplexError::Auth { status } => write!(f, "Authentication failed (HTTP {})", status),
+            FlashplexError::Http { status, endpoint } => write!(f, "HTTP {} from {}", status, endpoint),
+            FlashplexError::ServerJson { endpoint, message } => {
+                write!(f, "Invalid JSON from {}: {}", endpoint, message)
+            }
+            FlashplexError::Server { endpoint, message } => write!(f, "Server error from {}: {}", endpoint, message),
+            FlashplexError::MpvInit { profile, message, .. } => {
+                write!(f, "mpv init failed ({}): {}", profile, message)
+            }
+            FlashplexError::MpvCommand { command, message, .. } => {
+                write!(f, "mpv command '{}' failed: {}", command, message)
+            }
+            FlashplexError::WindowNotFound { label } => write!(f, "Window '{}' not found", label),
+            FlashplexError::MainThread { message } => write!(f, "Main thread dispatch failed: {}", message),
+            FlashplexError::Internal { message } => write!(f, "{}", message),
+        }
+    }
+}
+
+impl std::error::Error for FlashplexError {}
+
+impl Serialize for FlashplexError {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut map = serializer.serialize_map(None)?;
+        map.serialize_entry("code", self.code())?;
+        map.serialize_entry("message", &self.to_string())?;
+        map.serialize_entry("retryable", &self.is_retryable())?;
+        match self {
+            FlashplexError::UnsupportedPlatform { feature } => {
+                map.serialize_entry("feature", feature)?;
+            }
+            FlashplexError::PlayerBusy { command } => {
+                map.serialize_entry("command", command)?;
+            }
+            FlashplexError::PlayerLimit { max } => {
+                map.serialize_entry("max", max)?;
+            }
+            FlashplexError::Network { timeout, .. } => {
+                map.serialize_entry("timeout", timeout)?;
+            }
+            FlashplexError::Auth { status } => {
+                map.serialize_entry("status", status)?;
+            }
+            FlashplexError::Http { status, endpoint } => {
+                map.serialize_entry("status", status)?;
+                map.serialize_entry("endpoint", endpoint)?;
+            }
+            FlashplexError::ServerJson { endpoint, .. } | FlashplexError::Server { endpoint, .. } => {
+                map.serialize_entry("endpoint", endpoint)?;
+            }
+            FlashplexError::MpvInit { profile, code, .. } => {
+                map.serialize_entry("profile", profile)?;
+                map.serialize_entry("mpv_code", code)?;
+            }
+            FlashplexError::MpvCommand { command, code, .. } => {
+                map.serialize_entry("command", command)?;
+                map.serialize_entry("mpv_code", code)?;
+            }
+            FlashplexError::WindowNotFound { label } => {
+                map.serialize_entry("label", label)?;
+            }
+            _ => {}
+        }
+        map.end()
+    }
+}
+
+impl From<reqwest::Error> for FlashplexError {
+    fn from(e: reqwest::Error) -> Self {
+        if let Some(status) = e.status() {
+            let endpoint = e.url().map(|u| u.path().to_string()).unwrap_or_default();
+            return FlashplexError::from_status(status.as_u16(), &endpoint);
+        }
+        // Strip the URL: it carries the apikey query parameter.
+        FlashplexError::Network {
+            timeout: e.is_timeout(),
+            message: e.without_url().to_string(),
+        }
+    }
+}
+
+impl From<tauri::Error> for FlashplexError {
+    fn from(e: tauri::Error) -> Self {
+        FlashplexError::MainThread { message: e.to_string() }
+    }
+}
+
+impl<T> From<std::sync::PoisonError<T>> for FlashplexError {
+    fn from(e: std::sync::PoisonError<T>) -> Self {
+        FlashplexError::internal(format!("State lock poisoned: {}", e))
+    }
+}
+
+impl From<serde_json::Error> for FlashplexError {
+    fn from(e: serde_json::Error) -> Self {
+        FlashplexError::internal(format!("JSON error: {}", e))
+    }
+}
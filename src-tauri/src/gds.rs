@@ -0,0 +1,1067 @@
+// Typed client for the gds_dviewer HTTP API.
+//
+// Every server call goes through `GdsClient` so URL layout, bpath encoding and
+// response shapes live in one place instead of being rebuilt in main.js.
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use crate::error::{FlashplexError, FlashplexResult};
+use crate::http_cache::{cache_key, ttl_for, CacheMode, CacheStats, CachedResponse, Freshness, ResponseCache, SharedCache};
+use crate::image_cache;
+use crate::media_proxy::MediaProxy;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Deserializer, Serialize};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tauri_plugin_http::reqwest;
+use unicode_normalization::UnicodeNormalization;
+
+const API_PREFIX: &str = "gds_dviewer/normal";
+const CHECK_TIMEOUT: Duration = Duration::from_secs(10);
+
+#[derive(Clone)]
+pub struct GdsClient {
+    base_url: String,
+    api_key: String,
+    http: reqwest::Client,
+    cache: SharedCache,
+}
+
+enum Fetched {
+    Body(CachedResponse),
+    NotModified,
+}
+
+impl GdsClient {
+    pub fn new(http: reqwest::Client, cache: SharedCache, server_url: &str, api_key: &str) -> Self {
+        Self {
+            base_url: normalize_server_url(server_url),
+            api_key: api_key.trim().to_string(),
+            http,
+            cache,
+        }
+    }
+
+    pub fn base_url(&self) -> &str {
+        &self.base_url
+    }
+
+    fn endpoint(&self, name: &str) -> String {
+        format!("{}/{}/{}", self.base_url, API_PREFIX, name.trim_start_matches('/'))
+    }
+
+    // URL handed to mpv: query is encoded here because the consumer is not reqwest.
+    fn signed_url(&self, name: &str, query: &[(&str, String)]) -> String {
+        let mut url = self.endpoint(name);
+        let mut sep = '?';
+        for (k, v) in query.iter().chain(std::iter::once(&("apikey", self.api_key.clone()))) {
+            url.push(sep);
+            url.push_str(k);
+            url.push('=');
+            url.push_str(&urlencoding::encode(v));
+            sep = '&';
+        }
+        url
+    }
+
+    async fn get_json<T: DeserializeOwned>(&self, name: &str, query: &[(&str, String)]) -> FlashplexResult<T> {
+        let query: Vec<(String, String)> = query.iter().map(|(k, v)| (k.to_string(), v.clone())).collect();
+        let value = self.get_value(name, &query, CacheMode::Default).await?;
+        serde_json::from_value(value).map_err(|e| json_error(name, e))
+    }
+
+    /// GET through the response cache (see http_cache.rs). Endpoints without a TTL
+    /// and `CacheMode::NoStore` always go to the server.
+    pub async fn get_value(&self, name: &str, query: &[(String, String)], mode: CacheMode) -> FlashplexResult<serde_json::Value> {
+        let Some(ttl) = ttl_for(name).filter(|_| mode != CacheMode::NoStore) else {
+            return match self.fetch(name, query, None).await? {
+                Fetched::Body(response) => parse_body(name, &response.body),
+                Fetched::NotModified => Err(FlashplexError::from_status(304, name)),
+            };
+        };
+        let key = cache_key(&self.base_url, name, query);
+        let cached = match mode {
+            CacheMode::Default => self.cache.lock()?.get(&key),
+            _ => None,
+        };
+        if let Some(cached) = &cached {
+            match cached.freshness(ttl) {
+                Freshness::Fresh => {
+                    self.cache.lock()?.record_hit(false);
+                    return parse_body(name, &cached.body);
+                }
+                Freshness::Stale => {
+                    self.cache.lock()?.record_hit(true);
+                    self.spawn_refresh(name, query, key, cached.clone());
+                    return parse_body(name, &cached.body);
+                }
+                Freshness::Expired => {}
+            }
+        }
+        self.cache.lock()?.record_miss();
+        match self.fetch(name, query, cached.as_ref()).await {
+            Ok(fetched) => self.store(name, &key, fetched),
+            // Offline: an expired answer beats none.
+            Err(e @ FlashplexError::Network { .. }) => match cached {
+                Some(cached) => {
+                    log_warn!("CACHE", "{} unreachable, serving expired entry: {}", name, e);
+                    self.cache.lock()?.record_hit(true);
+                    parse_body(name, &cached.body)
+                }
+                None => Err(e),
+            },
+            Err(e) => Err(e),
+        }
+    }
+
+    // Stale-while-revalidate: one refresh per key at a time, errors only logged.
+    fn spawn_refresh(&self, name: &str, query: &[(String, String)], key: String, cached: CachedResponse) {
+        let started = self.cache.lock().map(|mut cache| cache.begin_refresh(&key));
+        if !matches!(started, Ok(true)) {
+            return;
+        }
+        let client = self.clone();
+        let name = name.to_string();
+        let query = query.to_vec();
+        tauri::async_runtime::spawn(async move {
+            let result = match client.fetch(&name, &query, Some(&cached)).await {
+                Ok(fetched) => client.store(&name, &key, fetched).map(|_| ()),
+                Err(e) => Err(e),
+            };
+            if let Err(e) = result {
+                log_debug!("CACHE", "Background refresh of {} failed: {}", name, e);
+            }
+            if let Ok(mut cache) = client.cache.lock() {
+                cache.end_refresh(&key);
+            }
+        });
+    }
+
+    // Caches successful answers only; a `ret` other than success is passed through uncached.
+    fn store(&self, name: &str, key: &str, fetched: Fetched) -> FlashplexResult<serde_json::Value> {
+        match fetched {
+            Fetched::NotModified => {
+                let renewed = self.cache.lock()?.renew(key);
+                match renewed {
+                    Some(response) => parse_body(name, &response.body),
+                    None => Err(FlashplexError::from_status(304, name)),
+                }
+            }
+            Fetched::Body(response) => {
+                let value = parse_body(name, &response.body)?;
+                let ok = value
+                    .get("ret")
+                    .and_then(|v| v.as_str())
+                    .is_none_or(|ret| ret.eq_ignore_ascii_case("success"));
+                if ok {
+                    self.cache.lock()?.put(key, response);
+                }
+                Ok(value)
+            }
+        }
+    }
+
+    // One GET; `cached` adds conditional headers when it carries validators.
+    async fn fetch(&self, name: &str, query: &[(String, String)], cached: Option<&CachedResponse>) -> FlashplexResult<Fetched> {
+        let mut request = self
+            .http
+            .get(self.endpoint(name))
+            .query(query)
+            .query(&[("apikey", self.api_key.as_str())]);
+        let cached = cached.filter(|c| c.has_validators());
+        if let Some(cached) = cached {
+            if let Some(etag) = &cached.etag {
+                request = request.header(reqwest::header::IF_NONE_MATCH, etag);
+            }
+            if let Some(modified) = &cached.last_modified {
+                request = request.header(reqwest::header::IF_MODIFIED_SINCE, modified);
+            }
+        }
+        let response = request.send().await?;
+
+        let status = response.status();
+        if status == reqwest::StatusCode::NOT_MODIFIED && cached.is_some() {
+            return Ok(Fetched::NotModified);
+        }
+        if !status.is_success() {
+            return Err(FlashplexError::from_status(status.as_u16(), name));
+        }
+        let header = |h: reqwest::header::HeaderName| {
+            response.headers().get(h).and_then(|v| v.to_str().ok()).map(str::to_string)
+        };
+        let etag = header(reqwest::header::ETAG);
+        let last_modified = header(reqwest::header::LAST_MODIFIED);
+        let body = response.text().await?;
+        Ok(Fetched::Body(CachedResponse::new(body, etag, last_modified)))
+    }
+
+    /// Raw bytes and Content-Type of an image endpoint (thumbnail, album_art,
+    /// proxy_image), for the `gdsimg://` protocol; the key stays in the backend.
+    pub async fn get_bytes(&self, name: &str, query: &[(String, String)]) -> FlashplexResult<(Vec<u8>, Option<String>)> {
+        let response = self
+            .http
+            .get(self.endpoint(name))
+            .query(query)
+            .query(&[("apikey", self.api_key.as_str())])
+            .send()
+            .await?;
+        let status = response.status();
+        if !status.is_success() {
+            return Err(FlashplexError::from_status(status.as_u16(), name));
+        }
+        let content_type = response
+            .headers()
+            .get(reqwest::header::CONTENT_TYPE)
+            .and_then(|v| v.to_str().ok())
+            .map(str::to_string);
+        let bytes = response.bytes().await?;
+        Ok((bytes.to_vec(), content_type))
+    }
+
+    /// GET for the media proxy (see media_proxy.rs): the response is streamed back
+    /// as is, with `range` passed through.
+    pub async fn open_media(&self, name: &str, query: &[(String, String)], range: Option<&str>) -> FlashplexResult<reqwest::Response> {
+        let mut request = self
+            .http
+            .get(self.endpoint(name))
+            .query(query)
+            .query(&[("apikey", self.api_key.as_str())]);
+        if let Some(range) = range {
+            request = request.header(reqwest::header::RANGE, range);
+        }
+        Ok(request.send().await?)
+    }
+
+    /// Form POST (e.g. `save_category_mapping`); the key is added to the form.
+    pub async fn post_form(&self, name: &str, form: &[(String, String)]) -> FlashplexResult<serde_json::Value> {
+        let mut fields = form.to_vec();
+        fields.retain(|(k, _)| k != "apikey");
+        fields.push(("apikey".to_string(), self.api_key.clone()));
+        let response = self.http.post(self.endpoint(name)).form(&fields).send().await?;
+        let status = response.status();
+        if !status.is_success() {
+            return Err(FlashplexError::from_status(status.as_u16(), name));
+        }
+        parse_body(name, &response.text().await?)
+    }
+
+    /// GET of the `stream` endpoint for downloading, continuing at `offset` with a
+    /// Range request. Status handling (200 vs 206 vs 416) is left to the caller.
+    pub async fn open_stream(&self, media: &MediaRef, offset: u64) -> FlashplexResult<reqwest::Response> {
+        let mut request = self.http.get(self.stream_url(media));
+        if offset > 0 {
+            request = request.header(reqwest::header::RANGE, format!("bytes={}-", offset));
+        }
+        Ok(request.send().await?)
+    }
+
+    /// The external subtitle of `media`, or None when the server has none.
+    pub async fn external_subtitle(&self, media: &MediaRef) -> FlashplexResult<Option<Vec<u8>>> {
+        let response = self.http.get(self.external_subtitle_url(media)).send().await?;
+        let status = response.status();
+        if status == reqwest::StatusCode::NOT_FOUND {
+            return Ok(None);
+        }
+        if !status.is_success() {
+            return Err(FlashplexError::from_status(status.as_u16(), "external_subtitle"));
+        }
+        let bytes = response.bytes().await?;
+        Ok((!bytes.is_empty()).then(|| bytes.to_vec()))
+    }
+
+    pub async fn search(&self, req: &SearchRequest) -> FlashplexResult<GdsListResponse> {
+        let res: GdsListResponse = self.get_json("search", &req.to_query()).await?;
+        res.into_success("search")
+    }
+
+    pub async fn explorer_list(&self, req: &ExplorerListRequest) -> FlashplexResult<GdsListResponse> {
+        let mut query = req.media.to_query();
+        if let Some(limit) = req.limit {
+            query.push(("limit", limit.to_string()));
+        }
+        let res: GdsListResponse = self.get_json("explorer/list", &query).await?;
+        res.into_success("explorer/list")
+    }
+
+    pub async fn get_video_info(&self, media: &MediaRef) -> FlashplexResult<VideoInfo> {
+        let res: GdsDataResponse<VideoInfo> = self.get_json("get_video_info", &media.to_query()).await?;
+        res.into_success("get_video_info")
+    }
+
+    pub async fn episode_meta(&self, req: &EpisodeMetaRequest) -> FlashplexResult<Vec<EpisodeMeta>> {
+        let query = vec![
+            ("path", req.path.clone()),
+            ("source_id", req.source_id.to_string()),
+        ];
+        let res: EpisodeMetaResponse = self.get_json("episode_meta", &query).await?;
+        if !res.ret.eq_ignore_ascii_case("success") {
+            return Err(server_error("episode_meta", res.msg, res.ret));
+        }
+        Ok(res.list)
+    }
+
+    pub async fn virtual_list(&self, kind: VirtualEndpoint, req: &VirtualListRequest) -> FlashplexResult<GdsListResponse> {
+        let res: GdsListResponse = self.get_json(kind.endpoint(), &req.to_query()).await?;
+        res.into_success(kind.endpoint())
+    }
+
+    /// Health check: one `search?limit=1` round trip. Failures are reported in the
+    /// result rather than as an error so the UI can show latency and auth together.
+    pub async fn check(&self) -> ServerCheck {
+        let started = Instant::now();
+        let sent = self
+            .http
+            .get(self.endpoint("search"))
+            .query(&[("query", ""), ("limit", "1")])
+            .query(&[("apikey", self.api_key.as_str())])
+            .timeout(CHECK_TIMEOUT)
+            .send()
+            .await;
+        let mut check = ServerCheck {
+            url: self.base_url.clone(),
+            ok: false,
+            latency_ms: 0,
+            status: None,
+            auth: AuthStatus::Unknown,
+            version: None,
+            error: None,
+        };
+        let response = match sent {
+            Ok(response) => response,
+            Err(e) => {
+                check.latency_ms = started.elapsed().as_millis() as u64;
+                check.error = Some(e.into());
+                return check;
+            }
+        };
+        let status = response.status().as_u16();
+        check.status = Some(status);
+        check.version = response
+            .headers()
+            .get("x-gds-version")
+            .or_else(|| response.headers().get(reqwest::header::SERVER))
+            .and_then(|v| v.to_str().ok())
+            .map(str::to_string);
+        let body = response.text().await;
+        check.latency_ms = started.elapsed().as_millis() as u64;
+        if !(200..300).contains(&status) {
+            if status == 401 || status == 403 {
+                check.auth = AuthStatus::Rejected;
+            }
+            check.error = Some(FlashplexError::from_status(status, "search"));
+            return check;
+        }
+        let parsed = body
+            .map_err(FlashplexError::from)
+            .and_then(|text| serde_json::from_str::<serde_json::Value>(&text).map_err(FlashplexError::from));
+        let json = match parsed {
+            Ok(json) => json,
+            Err(e) => {
+                check.error = Some(e);
+                return check;
+            }
+        };
+        if let Some(v) = json.get("version").and_then(|v| v.as_str()) {
+            check.version = Some(v.to_string());
+        }
+        let ret = json.get("ret").and_then(|v| v.as_str()).unwrap_or_default();
+        if ret.eq_ignore_ascii_case("success") {
+            check.ok = true;
+            check.auth = AuthStatus::Ok;
+        } else {
+            // gds_dviewer answers a bad key with 200 + `ret != success` and an apikey message.
+            let msg = json.get("msg").and_then(|v| v.as_str()).map(str::to_string);
+            if msg.as_deref().is_some_and(|m| m.to_ascii_lowercase().contains("apikey")) {
+                check.auth = AuthStatus::Rejected;
+            }
+            check.error = Some(server_error("search", msg, ret.to_string()));
+        }
+        check
+    }
+
+    pub fn stream_url(&self, media: &MediaRef) -> String {
+        self.signed_url("stream", &media.to_query())
+    }
+
+    pub fn external_subtitle_url(&self, media: &MediaRef) -> String {
+        self.signed_url("external_subtitle", &media.to_query())
+    }
+
+    // Sidecar subtitle URLs from get_video_info are server-relative and unsigned.
+    pub fn resolve_server_url(&self, url: &str) -> String {
+        if url.starts_with("http://") || url.starts_with("https://") {
+            return url.to_string();
+        }
+        let mut full = format!("{}/{}", self.base_url, url.trim_start_matches('/'));
+        if !full.contains("apikey=") {
+            full.push(if full.contains('?') { '&' } else { '?' });
+            full.push_str("apikey=");
+            full.push_str(&urlencoding::encode(&self.api_key));
+        }
+        full
+    }
+}
+
+pub fn normalize_server_url(raw: &str) -> String {
+    let trimmed = raw.trim().trim_end_matches('/');
+    if trimmed.starts_with("http://") || trimmed.starts_with("https://") {
+        trimmed.to_string()
+    } else {
+        format!("http://{}", trimmed)
+    }
+}
+
+/// URL-safe base64 of the NFC-normalized, slash-prefixed path (same as main.js `toUrlSafeBase64`).
+pub fn encode_bpath(path: &str) -> String {
+    let mut clean: String = path.nfc().collect();
+    if !clean.starts_with('/') {
+        clean.insert(0, '/');
+    }
+    URL_SAFE_NO_PAD.encode(clean.as_bytes())
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AuthStatus {
+    Ok,
+    Rejected,
+    /// The request never got far enough to tell.
+    Unknown,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ServerCheck {
+    pub url: String,
+    pub ok: bool,
+    pub latency_ms: u64,
+    pub status: Option<u16>,
+    pub auth: AuthStatus,
+    /// From the response (`version` field, else the `Server` header) when available.
+    pub version: Option<String>,
+    pub error: Option<FlashplexError>,
+}
+
+// --- Requests ---
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MediaRef {
+    pub path: String,
+    #[serde(default)]
+    pub source_id: u32,
+}
+
+impl MediaRef {
+    fn to_query(&self) -> Vec<(&'static str, String)> {
+        vec![
+            ("bpath", encode_bpath(&self.path)),
+            ("source_id", self.source_id.to_string()),
+        ]
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SearchRequest {
+    #[serde(default)]
+    pub query: String,
+    pub category: Option<String>,
+    pub path: Option<String>,
+    pub is_dir: Option<bool>,
+    pub recursive: Option<bool>,
+    pub limit: Option<u32>,
+    pub offset: Option<u32>,
+    pub sort_by: Option<String>,
+    pub sort_order: Option<String>,
+    pub source_id: Option<u32>,
+}
+
+impl SearchRequest {
+    fn to_query(&self) -> Vec<(&'static str, String)> {
+        let mut q = vec![("query", self.query.clone())];
+        if let Some(ref v) = self.category {
+            q.push(("category", v.clone()));
+        }
+        if let Some(ref v) = self.path {
+            q.push(("path", v.clone()));
+        }
+        if let Some(v) = self.is_dir {
+            q.push(("is_dir", v.to_string()));
+        }
+        if let Some(v) = self.recursive {
+            q.push(("recursive", v.to_string()));
+        }
+        if let Some(v) = self.limit {
+            q.push(("limit", v.to_string()));
+        }
+        if let Some(v) = self.offset {
+            q.push(("offset", v.to_string()));
+        }
+        if let Some(ref v) = self.sort_by {
+            q.push(("sort_by", v.clone()));
+        }
+        if let Some(ref v) = self.sort_order {
+            q.push(("sort_order", v.clone()));
+        }
+        if let Some(v) = self.source_id {
+            q.push(("source_id", v.to_string()));
+        }
+        q
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExplorerListRequest {
+    #[serde(flatten)]
+    pub media: MediaRef,
+    pub limit: Option<u32>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EpisodeMetaRequest {
+    pub path: String,
+    #[serde(default)]
+    pub source_id: u32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum VirtualEndpoint {
+    SeriesDomestic,
+    MovieVirtual,
+    AnimationVirtual,
+}
+
+impl VirtualEndpoint {
+    fn endpoint(self) -> &'static str {
+        match self {
+            VirtualEndpoint::SeriesDomestic => "series_domestic",
+            VirtualEndpoint::MovieVirtual => "movie_virtual",
+            VirtualEndpoint::AnimationVirtual => "animation_virtual",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VirtualListRequest {
+    pub bucket: String,
+    pub limit: Option<u32>,
+    pub offset: Option<u32>,
+    pub sort_by: Option<String>,
+    pub sort_order: Option<String>,
+    #[serde(default)]
+    pub source_id: u32,
+}
+
+impl VirtualListRequest {
+    fn to_query(&self) -> Vec<(&'static str, String)> {
+        let mut q = vec![
+            ("bucket", self.bucket.clone()),
+            ("source_id", self.source_id.to_string()),
+        ];
+        if let Some(v) = self.limit {
+            q.push(("limit", v.to_string()));
+        }
+        if let Some(v) = self.offset {
+            q.push(("offset", v.to_string()));
+        }
+        if let Some(ref v) = self.sort_by {
+            q.push(("sort_by", v.clone()));
+        }
+        if let Some(ref v) = self.sort_order {
+            q.push(("sort_order", v.clone()));
+        }
+        q
+    }
+}
+
+// --- Responses ---
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GdsItem {
+    #[serde(default)]
+    pub name: String,
+    #[serde(default)]
+    pub path: String,
+    #[serde(default)]
+    pub is_dir: bool,
+    #[serde(default)]
+    pub size: Option<u64>,
+    #[serde(default)]
+    pub mtime: Option<String>,
+    #[serde(default, deserialize_with = "de_lenient_u32")]
+    pub source_id: Option<u32>,
+    // meta_*, poster, album_info, ... are passed through untouched for the UI.
+    #[serde(flatten)]
+    pub extra: serde_json::Map<String, serde_json::Value>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GdsListResponse {
+    #[serde(default)]
+    pub ret: String,
+    // Endpoints disagree on the key: explorer/list uses `list`, virtual endpoints `data` or `items`.
+    #[serde(default, alias = "data", alias = "items")]
+    pub list: Vec<GdsItem>,
+    #[serde(default)]
+    pub count: Option<u64>,
+    #[serde(default)]
+    pub has_more: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub msg: Option<String>,
+}
+
+impl GdsListResponse {
+    fn into_success(self, endpoint: &str) -> FlashplexResult<Self> {
+        if self.ret.eq_ignore_ascii_case("success") {
+            Ok(self)
+        } else {
+            Err(server_error(endpoint, self.msg, self.ret))
+        }
+    }
+}
+
+// Only the parse error goes back to the UI; the body can be huge and may echo the key.
+fn json_error(endpoint: &str, e: serde_json::Error) -> FlashplexError {
+    FlashplexError::ServerJson {
+        endpoint: endpoint.to_string(),
+        message: e.to_string(),
+    }
+}
+
+fn parse_body(endpoint: &str, body: &str) -> FlashplexResult<serde_json::Value> {
+    serde_json::from_str(body).map_err(|e| json_error(endpoint, e))
+}
+
+fn server_error(endpoint: &str, msg: Option<String>, ret: String) -> FlashplexError {
+    FlashplexError::Server {
+        endpoint: endpoint.to_string(),
+        message: msg.unwrap_or(ret),
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct GdsDataResponse<T> {
+    #[serde(default)]
+    ret: String,
+    #[serde(default)]
+    msg: Option<String>,
+    data: Option<T>,
+}
+
+impl<T> GdsDataResponse<T> {
+    fn into_success(self, endpoint: &str) -> FlashplexResult<T> {
+        if !self.ret.eq_ignore_ascii_case("success") {
+            return Err(server_error(endpoint, self.msg, self.ret));
+        }
+        self.data.ok_or_else(|| server_error(endpoint, Some("missing data".to_string()), String::new()))
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VideoInfo {
+    #[serde(default)]
+    pub subtitles: Vec<SubtitleInfo>,
+    #[serde(flatten)]
+    pub extra: serde_json::Map<String, serde_json::Value>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SubtitleInfo {
+    // "sidecar" for files next to the video, anything else is embedded.
+    #[serde(rename = "type", default)]
+    pub kind: String,
+    #[serde(default)]
+    pub url: String,
+    #[serde(default, alias = "language")]
+    pub lang: Option<String>,
+    #[serde(default)]
+    pub title: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct EpisodeMetaResponse {
+    #[serde(default)]
+    ret: String,
+    #[serde(default)]
+    msg: Option<String>,
+    #[serde(default)]
+    list: Vec<EpisodeMeta>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EpisodeMeta {
+    #[serde(default)]
+    pub path: String,
+    #[serde(default, deserialize_with = "de_lenient_u32")]
+    pub episode: Option<u32>,
+    #[serde(default)]
+    pub title: Option<String>,
+    #[serde(default)]
+    pub summary: Option<String>,
+    #[serde(default)]
+    pub aired: Option<String>,
+    #[serde(default)]
+    pub thumb: Option<String>,
+}
+
+// The server emits ids as either numbers or numeric strings.
+fn de_lenient_u32<'de, D: Deserializer<'de>>(d: D) -> Result<Option<u32>, D::Error> {
+    let v = Option::<serde_json::Value>::deserialize(d)?;
+    Ok(match v {
+        Some(serde_json::Value::Number(n)) => n.as_u64().and_then(|n| u32::try_from(n).ok()),
+        Some(serde_json::Value::String(s)) => s.trim().parse().ok(),
+        _ => None,
+    })
+}
+
+// --- Tauri state & commands ---
+
+pub struct GdsState {
+    http: reqwest::Client,
+    cache: SharedCache,
+    active: Arc<Mutex<Option<GdsClient>>>,
+    // Started on first use.
+    proxy: Mutex<Option<Arc<MediaProxy>>>,
+}
+
+impl GdsState {
+    pub fn new() -> Self {
+        Self {
+            http: reqwest::Client::new(),
+            cache: Arc::new(Mutex::new(ResponseCache::new())),
+            active: Arc::new(Mutex::new(None)),
+            proxy: Mutex::new(None),
+        }
+    }
+
+    /// Turns on the disk layer of the response cache (app cache dir, set up in `run`).
+    pub fn set_cache_dir(&self, dir: std::path::PathBuf) {
+        if let Ok(mut cache) = self.cache.lock() {
+            cache.set_dir(dir);
+        }
+    }
+
+    pub fn configure(&self, server_url: &str, api_key: &str) -> GdsClient {
+        let client = GdsClient::new(self.http.clone(), self.cache.clone(), server_url, api_key);
+        if let Ok(mut lock) = self.active.lock() {
+            *lock = Some(client.clone());
+        }
+        client
+    }
+
+    /// Client for explicitly supplied credentials, sharing the same connection pool.
+    pub fn client_for(&self, server_url: &str, api_key: &str) -> GdsClient {
+        GdsClient::new(self.http.clone(), self.cache.clone(), server_url, api_key)
+    }
+
+    pub fn client(&self) -> FlashplexResult<GdsClient> {
+        let lock = self.active.lock()?;
+        lock.clone().ok_or(FlashplexError::ServerNotConfigured)
+    }
+
+    /// Loopback proxy for media loaded by URL outside mpv; follows the active server.
+    pub fn media_proxy(&self) -> FlashplexResult<Arc<MediaProxy>> {
+        let mut proxy = self.proxy.lock()?;
+        if let Some(proxy) = proxy.as_ref() {
+            return Ok(proxy.clone());
+        }
+        let started = Arc::new(MediaProxy::start(self.active.clone())?);
+        *proxy = Some(started.clone());
+        Ok(started)
+    }
+
+    /// Stream/subtitle URLs for playback. A non-empty `url` is used as-is; otherwise
+    /// both are built from `path`, signed with the stored key.
+    pub fn playback_urls(
+        &self,
+        url: Option<String>,
+        subtitle_url: Option<String>,
+        path: Option<&str>,
+        source_id: Option<u32>,
+    ) -> FlashplexResult<(String, Option<String>)> {
+        if let Some(url) = url.filter(|u| !u.is_empty()) {
+            return Ok((url, subtitle_url));
+        }
+        let path = path
+            .filter(|p| !p.is_empty())
+            .ok_or_else(|| FlashplexError::invalid_input("either url or path is required"))?;
+        let media = MediaRef {
+            path: path.to_string(),
+            source_id: source_id.unwrap_or(0),
+        };
+        let client = self.client()?;
+        let subtitle_url = subtitle_url.or_else(|| Some(client.external_subtitle_url(&media)));
+        Ok((client.stream_url(&media), subtitle_url))
+    }
+}
+
+#[tauri::command(rename_all = "snake_case")]
+pub fn gds_configure(state: tauri::State<'_, GdsState>, server_url: String, api_key: String) -> FlashplexResult<String> {
+    let client = state.configure(&server_url, &api_key);
+    log_info!("GDS", "Configured server: {}", client.base_url());
+    Ok(client.base_url().to_string())
+}
+
+#[tauri::command(rename_all = "snake_case")]
+pub async fn gds_search(state: tauri::State<'_, GdsState>, request: SearchRequest) -> FlashplexResult<GdsListResponse> {
+    state.client()?.search(&request).await
+}
+
+#[tauri::command(rename_all = "snake_case")]
+pub async fn gds_explorer_list(state: tauri::State<'_, GdsState>, request: ExplorerListRequest) -> FlashplexResult<GdsListResponse> {
+    state.client()?.explorer_list(&request).await
+}
+
+#[tauri::command(rename_all = "snake_case")]
+pub async fn gds_get_video_info(state: tauri::State<'_, GdsState>, media: MediaRef) -> FlashplexResult<VideoInfo> {
+    state.client()?.get_video_info(&media).await
+}
+
+#[tauri::command(rename_all = "snake_case")]
+pub async fn gds_episode_meta(state: tauri::State<'_, GdsState>, request: EpisodeMetaRequest) -> FlashplexResult<Vec<EpisodeMeta>> {
+    state.client()?.episode_meta(&request).await
+}
+
+#[tauri::command(rename_all = "snake_case")]
+pub async fn gds_virtual_list(
+    state: tauri::State<'_, GdsState>,
+    endpoint: VirtualEndpoint,
+    request: VirtualListRequest,
+) -> FlashplexResult<GdsListResponse> {
+    state.client()?.virtual_list(endpoint, &request).await
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MediaUrlKind {
+    Stream,
+    ExternalSubtitle,
+    Thumbnail,
+    AlbumArt,
+}
+
+/// URL the webview can load without the key: images go through `gdsimg://`
+/// (image_cache.rs), stream and subtitle through the loopback media proxy.
+#[tauri::command(rename_all = "snake_case")]
+pub fn gds_media_url(state: tauri::State<'_, GdsState>, kind: MediaUrlKind, media: MediaRef, width: Option<u32>) -> FlashplexResult<String> {
+    let mut query = media.to_query();
+    Ok(match kind {
+        MediaUrlKind::Stream => state.media_proxy()?.url("stream", &query),
+        MediaUrlKind::ExternalSubtitle => state.media_proxy()?.url("external_subtitle", &query),
+        MediaUrlKind::Thumbnail => {
+            if let Some(w) = width {
+                query.push(("w", w.to_string()));
+            }
+            image_cache::url("thumbnail", &query)
+        }
+        MediaUrlKind::AlbumArt => image_cache::url("album_art", &query),
+    })
+}
+
+#[tauri::command(rename_all = "snake_case")]
+pub fn gds_proxy_image_url(url: String) -> FlashplexResult<String> {
+    Ok(image_cache::url("proxy_image", &[("url", url)]))
+}
+
+/// Base of the media proxy, `http://127.0.0.1:<port>/<token>`: main.js appends
+/// `/<endpoint>?<query>` for stream, preview and subtitle URLs.
+#[tauri::command(rename_all = "snake_case")]
+pub fn gds_media_base(state: tauri::State<'_, GdsState>) -> FlashplexResult<String> {
+    Ok(state.media_proxy()?.base_url().to_string())
+}
+
+/// Form POST of `endpoint` (e.g. `save_category_mapping`); the key is added here.
+#[tauri::command(rename_all = "snake_case")]
+pub async fn gds_post(
+    state: tauri::State<'_, GdsState>,
+    endpoint: String,
+    form: Vec<(String, String)>,
+) -> FlashplexResult<serde_json::Value> {
+    let (name, _) = split_endpoint(&endpoint)?;
+    state.client()?.post_form(&name, &form).await
+}
+
+/// Raw GET of `endpoint` (e.g. `"search?query=x&limit=50"`) through the response
+/// cache, for the main.js calls that don't have a typed command. The key is added here.
+#[tauri::command(rename_all = "snake_case")]
+pub async fn gds_get(
+    state: tauri::State<'_, GdsState>,
+    endpoint: String,
+    cache_mode: Option<CacheMode>,
+) -> FlashplexResult<serde_json::Value> {
+    let (name, query) = split_endpoint(&endpoint)?;
+    state.client()?.get_value(&name, &query, cache_mode.unwrap_or_default()).await
+}
+
+// "name?a=1&b=2" -> ("name", [(a, 1), (b, 2)]), decoding form-style encoding.
+pub(crate) fn split_endpoint(endpoint: &str) -> FlashplexResult<(String, Vec<(String, String)>)> {
+    let (name, query) = endpoint.split_once('?').unwrap_or((endpoint, ""));
+    let name = name.trim().trim_start_matches('/');
+    if name.is_empty() || name.contains("://") || name.split('/').any(|part| part == "..") {
+        return Err(FlashplexError::invalid_input(format!("invalid endpoint '{}'", name)));
+    }
+    let decode = |s: &str| {
+        let s = s.replace('+', " ");
+        urlencoding::decode(&s).map(|d| d.into_owned()).unwrap_or(s)
+    };
+    let pairs = query
+        .split('&')
+        .filter(|pair| !pair.is_empty())
+        .map(|pair| {
+            let (k, v) = pair.split_once('=').unwrap_or((pair, ""));
+            (decode(k), decode(v))
+        })
+        .filter(|(k, _)| k != "apikey")
+        .collect();
+    Ok((name.to_string(), pairs))
+}
+
+#[tauri::command(rename_all = "snake_case")]
+pub fn clear_cache(state: tauri::State<'_, GdsState>) -> FlashplexResult<usize> {
+    let count = state.cache.lock()?.clear();
+    log_info!("CACHE", "Cleared {} cached responses", count);
+    Ok(count)
+}
+
+#[tauri::command(rename_all = "snake_case")]
+pub fn cache_stats(state: tauri::State<'_, GdsState>) -> FlashplexResult<CacheStats> {
+    Ok(state.cache.lock()?.stats())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_http::{response, serve};
+
+    fn client(base_url: &str) -> GdsClient {
+        let cache = Arc::new(Mutex::new(ResponseCache::new()));
+        GdsClient::new(reqwest::Client::new(), cache, base_url, " k&y ")
+    }
+
+    fn get(client: &GdsClient, endpoint: &str) -> FlashplexResult<serde_json::Value> {
+        let (name, query) = split_endpoint(endpoint)?;
+        tauri::async_runtime::block_on(client.get_value(&name, &query, CacheMode::NoStore))
+    }
+
+    #[test]
+    fn normalizes_server_url() {
+        assert_eq!(normalize_server_url(" host:9999/ "), "http://host:9999");
+        assert_eq!(normalize_server_url("https://host/"), "https://host");
+    }
+
+    #[test]
+    fn encodes_bpath_as_nfc_with_leading_slash() {
+        let decomposed = "Movies/\u{1100}\u{1161}";
+        let decoded = URL_SAFE_NO_PAD.decode(encode_bpath(decomposed)).unwrap();
+        assert_eq!(String::from_utf8(decoded).unwrap(), "/Movies/\u{AC00}");
+    }
+
+    #[test]
+    fn signed_url_appends_encoded_apikey() {
+        let media = MediaRef {
+            path: "/a b.mkv".to_string(),
+            source_id: 2,
+        };
+        let url = client("http://host").stream_url(&media);
+        assert!(url.starts_with("http://host/gds_dviewer/normal/stream?bpath="));
+        assert!(url.contains("&source_id=2&"));
+        assert!(url.ends_with("&apikey=k%26y"));
+    }
+
+    #[test]
+    fn search_request_maps_only_set_fields() {
+        let req: SearchRequest = serde_json::from_value(serde_json::json!({
+            "query": "abc",
+            "limit": 50,
+            "is_dir": true,
+            "source_id": 1,
+        }))
+        .unwrap();
+        let query = req.to_query();
+        assert_eq!(
+            query,
+            vec![
+                ("query", "abc".to_string()),
+                ("is_dir", "true".to_string()),
+                ("limit", "50".to_string()),
+                ("source_id", "1".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn list_response_accepts_every_list_key() {
+        for key in ["list", "data", "items"] {
+            let res: GdsListResponse = serde_json::from_value(serde_json::json!({
+                "ret": "success",
+                key: [{ "name": "a", "source_id": "3", "poster": "p.jpg" }],
+            }))
+            .unwrap();
+            let item = &res.list[0];
+            assert_eq!(item.source_id, Some(3));
+            assert_eq!(item.extra.get("poster").and_then(|v| v.as_str()), Some("p.jpg"));
+        }
+    }
+
+    #[test]
+    fn unsuccessful_ret_maps_to_server_error() {
+        let res: GdsListResponse = serde_json::from_value(serde_json::json!({ "ret": "fail", "msg": "nope" })).unwrap();
+        match res.into_success("search") {
+            Err(FlashplexError::Server { endpoint, message }) => {
+                assert_eq!(endpoint, "search");
+                assert_eq!(message, "nope");
+            }
+            other => panic!("unexpected {:?}", other),
+        }
+        let res: GdsDataResponse<VideoInfo> = serde_json::from_value(serde_json::json!({ "ret": "success" })).unwrap();
+        assert!(matches!(res.into_success("get_video_info"), Err(FlashplexError::Server { .. })));
+    }
+
+    #[test]
+    fn from_status_separates_auth_from_http() {
+        assert!(matches!(FlashplexError::from_status(401, "search"), FlashplexError::Auth { status: 401 }));
+        assert!(matches!(FlashplexError::from_status(403, "search"), FlashplexError::Auth { status: 403 }));
+        let err = FlashplexError::from_status(503, "search");
+        assert!(matches!(err, FlashplexError::Http { status: 503, .. }));
+        assert!(err.is_retryable());
+        assert!(!FlashplexError::from_status(404, "search").is_retryable());
+    }
+
+    #[test]
+    fn split_endpoint_decodes_and_strips_apikey() {
+        let (name, query) = split_endpoint("/search?query=a+b%26c&apikey=leak&limit=5").unwrap();
+        assert_eq!(name, "search");
+        assert_eq!(
+            query,
+            vec![
+                ("query".to_string(), "a b&c".to_string()),
+                ("limit".to_string(), "5".to_string()),
+            ]
+        );
+        assert!(split_endpoint("http://evil/x").is_err());
+        assert!(split_endpoint("../admin").is_err());
+        assert!(split_endpoint("?query=x").is_err());
+    }
+
+    #[test]
+    fn gds_get_sends_stored_key_once() {
+        let server = serve(vec![response("200 OK", &[], br#"{"ret":"success","list":[]}"#)]);
+        let value = get(&client(&server.base_url), "search?query=x&apikey=from-webview").unwrap();
+        assert_eq!(value["ret"], "success");
+        let request = &server.requests()[0];
+        let line = request.lines().next().unwrap();
+        assert!(line.starts_with("GET /gds_dviewer/normal/search?query=x&apikey=k%26y "), "{}", line);
+        assert!(!request.contains("from-webview"));
+    }
+
+    #[test]
+    fn http_failures_map_to_errors() {
+        let server = serve(vec![
+            response("401 Unauthorized", &[], b""),
+            response("500 Internal Server Error", &[], b""),
+            response("200 OK", &[], b"<html>"),
+        ]);
+        let client = client(&server.base_url);
+        assert!(matches!(get(&client, "search"), Err(FlashplexError::Auth { status: 401 })));
+        assert!(matches!(get(&client, "search"), Err(FlashplexError::Http { status: 500, .. })));
+        assert!(matches!(get(&client, "search"), Err(FlashplexError::ServerJson { .. })));
+    }
+}
@@ -0,0 +1,341 @@
+// Watch history and resume positions.
+//
+// Stored as a single JSON file in the app data dir (written via temp file + rename),
+// keyed by `source_id` + `bpath` so entries survive path re-encoding on the JS side.
+// The mpv event thread records progress; the UI only reads it back.
+use crate::error::FlashplexResult;
+use crate::gds::{encode_bpath, MediaRef};
+use crate::storage::write_atomic;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+const HISTORY_FILE: &str = "watch_history.json";
+const HISTORY_VERSION: u32 = 1;
+// Progress is kept in memory and flushed at most this often (plus on pause/end-file).
+const FLUSH_INTERVAL: Duration = Duration::from_secs(15);
+// Past this fraction of the duration an item counts as watched.
+const COMPLETED_RATIO: f64 = 0.95;
+// Positions below this aren't worth resuming from.
+const MIN_RESUME_SECS: f64 = 5.0;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HistoryEntry {
+    pub source_id: u32,
+    pub bpath: String,
+    pub path: String,
+    #[serde(default)]
+    pub title: String,
+    pub position: f64,
+    pub duration: f64,
+    pub completed: bool,
+    /// Unix seconds.
+    pub updated_at: i64,
+}
+
+impl HistoryEntry {
+    fn new(media: &MediaRef, title: &str) -> Self {
+        HistoryEntry {
+            source_id: media.source_id,
+            bpath: encode_bpath(&media.path),
+            path: media.path.clone(),
+            title: title.to_string(),
+            position: 0.0,
+            duration: 0.0,
+            completed: false,
+            updated_at: 0,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct HistoryFile {
+    version: u32,
+    entries: Vec<HistoryEntry>,
+}
+
+pub struct HistoryStore {
+    file: PathBuf,
+    entries: HashMap<String, HistoryEntry>,
+    dirty: bool,
+    last_flush: Instant,
+}
+
+fn history_key(source_id: u32, bpath: &str) -> String {
+    format!("{}:{}", source_id, bpath)
+}
+
+fn media_key(media: &MediaRef) -> String {
+    history_key(media.source_id, &encode_bpath(&media.path))
+}
+
+fn now_unix() -> i64 {
+    chrono::Utc::now().timestamp()
+}
+
+impl HistoryStore {
+    /// Loads `file`; a missing or unreadable file starts an empty history.
+    pub fn open(file: PathBuf) -> Self {
+        let entries = match std::fs::read_to_string(&file) {
+            Ok(text) => match serde_json::from_str::<HistoryFile>(&text) {
+                Ok(parsed) => parsed
+                    .entries
+                    .into_iter()
+                    .map(|e| (history_key(e.source_id, &e.bpath), e))
+                    .collect(),
+                Err(e) => {
+                    log_warn!("HISTORY", "Ignoring unreadable {}: {}", file.display(), e);
+                    HashMap::new()
+                }
+            },
+            Err(_) => HashMap::new(),
+        };
+        log_info!("HISTORY", "Loaded {} entries from {}", entries.len(), file.display());
+        HistoryStore {
+            file,
+            entries,
+            dirty: false,
+            last_flush: Instant::now(),
+        }
+    }
+
+    pub fn record_progress(&mut self, media: &MediaRef, title: &str, position: f64, duration: f64) {
+        let entry = self
+            .entries
+            .entry(media_key(media))
+            .or_insert_with(|| HistoryEntry::new(media, title));
+        if !title.is_empty() {
+            entry.title = title.to_string();
+        }
+        entry.position = position;
+        if duration > 0.0 {
+            entry.duration = duration;
+            // Playing a finished item again (from near the start) makes it resumable again.
+            entry.completed = position / duration >= COMPLETED_RATIO;
+        }
+        entry.updated_at = now_unix();
+        self.dirty = true;
+    }
+
+    pub fn set_watched(&mut self, media: &MediaRef, watched: bool) {
+        let entry = self
+            .entries
+            .entry(media_key(media))
+            .or_insert_with(|| HistoryEntry::new(media, ""));
+        entry.completed = watched;
+        if !watched {
+            entry.position = 0.0;
+        }
+        entry.updated_at = now_unix();
+        self.dirty = true;
+    }
+
+    pub fn get(&self, media: &MediaRef) -> Option<&HistoryEntry> {
+        self.entries.get(&media_key(media))
+    }
+
+    /// Position to resume from, or None when the item was finished or barely started.
+    pub fn resume_point(&self, media: &MediaRef) -> Option<f64> {
+        self.get(media)
+            .filter(|e| !e.completed && e.position >= MIN_RESUME_SECS)
+            .map(|e| e.position)
+    }
+
+    pub fn recent(&self, limit: usize) -> Vec<HistoryEntry> {
+        let mut list: Vec<HistoryEntry> = self.entries.values().cloned().collect();
+        list.sort_by_key(|e| std::cmp::Reverse(e.updated_at));
+        list.truncate(limit);
+        list
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+        self.dirty = true;
+    }
+
+    pub fn flush_if_due(&mut self) {
+        if self.dirty && self.last_flush.elapsed() >= FLUSH_INTERVAL {
+            self.flush_logged();
+        }
+    }
+
+    /// Flush for callers that can't surface an error (event thread).
+    pub fn flush_logged(&mut self) {
+        if let Err(e) = self.flush() {
+            log_warn!("HISTORY", "Write failed: {}", e);
+        }
+    }
+
+    pub fn flush(&mut self) -> FlashplexResult<()> {
+        if !self.dirty {
+            return Ok(());
+        }
+        let file = HistoryFile {
+            version: HISTORY_VERSION,
+            entries: self.entries.values().cloned().collect(),
+        };
+        write_atomic(&self.file, &serde_json::to_vec_pretty(&file)?)?;
+        self.dirty = false;
+        self.last_flush = Instant::now();
+        Ok(())
+    }
+}
+
+pub struct HistoryState(pub Arc<Mutex<HistoryStore>>);
+
+impl HistoryState {
+    pub fn open(app: &tauri::AppHandle) -> Self {
+        use tauri::Manager;
+        let dir = app
+            .path()
+            .app_data_dir()
+            .unwrap_or_else(|_| std::env::temp_dir().join("flashplex"));
+        HistoryState(Arc::new(Mutex::new(HistoryStore::open(dir.join(HISTORY_FILE)))))
+    }
+}
+
+// Commands
+
+#[tauri::command(rename_all = "snake_case")]
+pub fn history_list_recent(
+    state: tauri::State<'_, HistoryState>,
+    limit: Option<usize>,
+) -> FlashplexResult<Vec<HistoryEntry>> {
+    Ok(state.0.lock()?.recent(limit.unwrap_or(50)))
+}
+
+#[tauri::command(rename_all = "snake_case")]
+pub fn history_get_resume(state: tauri::State<'_, HistoryState>, media: MediaRef) -> FlashplexResult<Option<f64>> {
+    Ok(state.0.lock()?.resume_point(&media))
+}
+
+#[tauri::command(rename_all = "snake_case")]
+pub fn history_mark_watched(
+    state: tauri::State<'_, HistoryState>,
+    media: MediaRef,
+    watched: bool,
+) -> FlashplexResult<()> {
+    log_info!("HISTORY", "mark {} watched={}", media.path, watched);
+    let mut store = state.0.lock()?;
+    store.set_watched(&media, watched);
+    store.flush()
+}
+
+#[tauri::command(rename_all = "snake_case")]
+pub fn history_clear(state: tauri::State<'_, HistoryState>) -> FlashplexResult<()> {
+    log_info!("HISTORY", "clear");
+    let mut store = state.0.lock()?;
+    store.clear();
+    store.flush()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn media(path: &str) -> MediaRef {
+        MediaRef { path: path.to_string(), source_id: 3 }
+    }
+
+    fn scratch(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("flashplex-history-{}-{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        dir
+    }
+
+    fn store(name: &str) -> HistoryStore {
+        HistoryStore::open(scratch(name).join(HISTORY_FILE))
+    }
+
+    #[test]
+    fn key_is_source_id_and_bpath() {
+        let key = media_key(&media("/TV/show.mkv"));
+        assert_eq!(key, format!("3:{}", encode_bpath("/TV/show.mkv")));
+        // The leading slash the JS side sometimes drops doesn't split entries.
+        assert_eq!(media_key(&media("TV/show.mkv")), key);
+        assert_ne!(media_key(&MediaRef { source_id: 4, ..media("/TV/show.mkv") }), key);
+    }
+
+    #[test]
+    fn completed_past_the_ratio_and_reset_on_replay() {
+        let mut store = store("completed");
+        let item = media("/a.mkv");
+        store.record_progress(&item, "A", 94.0, 100.0);
+        assert!(!store.get(&item).unwrap().completed);
+        store.record_progress(&item, "A", 95.0, 100.0);
+        assert!(store.get(&item).unwrap().completed);
+        assert_eq!(store.resume_point(&item), None);
+
+        // Watched again from the start.
+        store.record_progress(&item, "A", 30.0, 100.0);
+        assert!(!store.get(&item).unwrap().completed);
+        assert_eq!(store.resume_point(&item), Some(30.0));
+
+        // Unknown duration leaves the flag alone.
+        store.set_watched(&item, true);
+        store.record_progress(&item, "", 10.0, 0.0);
+        let entry = store.get(&item).unwrap();
+        assert!(entry.completed);
+        assert_eq!((entry.title.as_str(), entry.duration), ("A", 100.0));
+    }
+
+    #[test]
+    fn short_positions_are_not_resumed() {
+        let mut store = store("min-resume");
+        let item = media("/a.mkv");
+        store.record_progress(&item, "A", MIN_RESUME_SECS - 0.5, 100.0);
+        assert_eq!(store.resume_point(&item), None);
+        store.record_progress(&item, "A", MIN_RESUME_SECS, 100.0);
+        assert_eq!(store.resume_point(&item), Some(MIN_RESUME_SECS));
+        assert_eq!(store.resume_point(&media("/b.mkv")), None);
+    }
+
+    #[test]
+    fn unwatching_resets_the_position() {
+        let mut store = store("unwatch");
+        let item = media("/a.mkv");
+        store.record_progress(&item, "A", 50.0, 100.0);
+        store.set_watched(&item, true);
+        assert_eq!(store.get(&item).unwrap().position, 50.0);
+        store.set_watched(&item, false);
+        let entry = store.get(&item).unwrap();
+        assert!(!entry.completed);
+        assert_eq!(entry.position, 0.0);
+        assert_eq!(store.resume_point(&item), None);
+    }
+
+    #[test]
+    fn recent_is_newest_first() {
+        let mut store = store("recent");
+        for (i, path) in ["/a.mkv", "/b.mkv", "/c.mkv"].iter().enumerate() {
+            store.record_progress(&media(path), path, 10.0, 100.0);
+            store.entries.get_mut(&media_key(&media(path))).unwrap().updated_at = [200, 300, 100][i];
+        }
+        let paths: Vec<String> = store.recent(10).into_iter().map(|e| e.path).collect();
+        assert_eq!(paths, ["/b.mkv", "/a.mkv", "/c.mkv"]);
+        assert_eq!(store.recent(1).len(), 1);
+    }
+
+    #[test]
+    fn flush_round_trips_through_the_file() {
+        let dir = scratch("round-trip");
+        let file = dir.join(HISTORY_FILE);
+        let mut store = HistoryStore::open(file.clone());
+        store.record_progress(&media("/TV/쇼 01.mkv"), "쇼", 42.5, 100.0);
+        store.set_watched(&media("/b.mkv"), true);
+        store.flush().unwrap();
+        assert!(!store.dirty);
+
+        let reopened = HistoryStore::open(file.clone());
+        let entry = reopened.get(&media("/TV/쇼 01.mkv")).unwrap();
+        assert_eq!((entry.title.as_str(), entry.position, entry.duration), ("쇼", 42.5, 100.0));
+        assert!(reopened.get(&media("/b.mkv")).unwrap().completed);
+        assert_eq!(reopened.recent(10).len(), 2);
+        // Only the file itself is left behind, no temp file.
+        let names: Vec<_> = std::fs::read_dir(&dir).unwrap().map(|e| e.unwrap().file_name()).collect();
+        assert_eq!(names, [HISTORY_FILE]);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
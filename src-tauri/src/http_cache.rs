@@ -0,0 +1,367 @@
+// Response cache for GDS API GETs.
+//
+// Bodies are kept in memory and, as one JSON file per request, in the app cache
+// dir so they survive a restart. Each endpoint has its own TTL (the table main.js
+// used to carry). A fresh entry is served as-is; for `STALE_WINDOW` past its TTL
+// it is still served while a background request refreshes it; after that the
+// request goes to the server, with If-None-Match / If-Modified-Since when the
+// server sent an ETag / Last-Modified, and a 304 just renews the entry. Memory
+// and disk each have a byte limit and drop least recently used entries first.
+use crate::error::FlashplexError;
+use crate::storage::write_atomic;
+use serde::{Deserialize, Serialize};
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{HashMap, HashSet};
+use std::hash::{Hash, Hasher};
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+const DEFAULT_TTL: Duration = Duration::from_secs(2 * 60);
+const STALE_WINDOW: Duration = Duration::from_secs(10 * 60);
+const MEMORY_LIMIT_BYTES: u64 = 16 * 1024 * 1024;
+const DISK_LIMIT_BYTES: u64 = 64 * 1024 * 1024;
+
+/// TTL for a cacheable endpoint; None means the endpoint is never cached.
+pub fn ttl_for(endpoint: &str) -> Option<Duration> {
+    match endpoint.trim_start_matches('/') {
+        "get_video_info" => Some(Duration::from_secs(60)),
+        "episode_meta" | "movie_genre" => Some(Duration::from_secs(5 * 60)),
+        "movie_preview" | "animation_preview" => Some(Duration::from_secs(90)),
+        "search" | "list" | "explorer/list" | "series_domestic" | "movie_virtual" | "animation_virtual" => Some(DEFAULT_TTL),
+        _ => None,
+    }
+}
+
+/// Same names as the `cacheMode` option of main.js `gdsFetch`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum CacheMode {
+    #[default]
+    Default,
+    /// Skip the cache entirely.
+    NoStore,
+    /// Go to the server, but store the answer.
+    Reload,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CachedResponse {
+    pub body: String,
+    /// Unix ms when the server last confirmed the body.
+    pub stored_at: u64,
+    pub etag: Option<String>,
+    pub last_modified: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Freshness {
+    Fresh,
+    /// Past the TTL but inside the stale-while-revalidate window.
+    Stale,
+    Expired,
+}
+
+impl CachedResponse {
+    pub fn new(body: String, etag: Option<String>, last_modified: Option<String>) -> Self {
+        CachedResponse {
+            body,
+            stored_at: now_ms(),
+            etag,
+            last_modified,
+        }
+    }
+
+    pub fn has_validators(&self) -> bool {
+        self.etag.is_some() || self.last_modified.is_some()
+    }
+
+    pub fn freshness(&self, ttl: Duration) -> Freshness {
+        let age = Duration::from_millis(now_ms().saturating_sub(self.stored_at));
+        if age <= ttl {
+            Freshness::Fresh
+        } else if age <= ttl + STALE_WINDOW {
+            Freshness::Stale
+        } else {
+            Freshness::Expired
+        }
+    }
+
+    fn size(&self) -> u64 {
+        (self.body.len()
+            + self.etag.as_ref().map_or(0, String::len)
+            + self.last_modified.as_ref().map_or(0, String::len)) as u64
+    }
+}
+
+// On-disk form; the key is kept so a hash collision reads as a miss.
+#[derive(Serialize, Deserialize)]
+struct DiskEntry {
+    key: String,
+    response: CachedResponse,
+}
+
+struct MemoryEntry {
+    response: CachedResponse,
+    last_used: u64,
+}
+
+struct DiskIndexEntry {
+    bytes: u64,
+    last_used: u64,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct CacheStats {
+    pub memory_entries: usize,
+    pub memory_bytes: u64,
+    pub memory_limit_bytes: u64,
+    pub disk_entries: usize,
+    pub disk_bytes: u64,
+    pub disk_limit_bytes: u64,
+    /// Served fresh from memory or disk.
+    pub hits: u64,
+    /// Served past the TTL while a refresh ran (or the server was unreachable).
+    pub stale_hits: u64,
+    /// 304 answers that renewed an entry.
+    pub revalidated: u64,
+    pub misses: u64,
+    pub evictions: u64,
+}
+
+pub struct ResponseCache {
+    dir: Option<PathBuf>,
+    memory: HashMap<String, MemoryEntry>,
+    memory_bytes: u64,
+    // File name -> size/recency, rebuilt from the directory on start.
+    disk: HashMap<String, DiskIndexEntry>,
+    // Keys with a background refresh in flight.
+    refreshing: HashSet<String>,
+    stats: CacheStats,
+}
+
+pub type SharedCache = Arc<Mutex<ResponseCache>>;
+
+impl ResponseCache {
+    pub fn new() -> Self {
+        ResponseCache {
+            dir: None,
+            memory: HashMap::new(),
+            memory_bytes: 0,
+            disk: HashMap::new(),
+            refreshing: HashSet::new(),
+            stats: CacheStats::default(),
+        }
+    }
+
+    /// Enables the disk layer and indexes what a previous run left there.
+    pub fn set_dir(&mut self, dir: PathBuf) {
+        if let Err(e) = std::fs::create_dir_all(&dir) {
+            log_warn!("CACHE", "Disk cache disabled, cannot create {}: {}", dir.display(), e);
+            return;
+        }
+        self.disk.clear();
+        if let Ok(entries) = std::fs::read_dir(&dir) {
+            for entry in entries.flatten() {
+                let name = entry.file_name().to_string_lossy().to_string();
+                let Ok(meta) = entry.metadata() else {
+                    continue;
+                };
+                if !meta.is_file() || !name.ends_with(".json") {
+                    continue;
+                }
+                let last_used = meta
+                    .modified()
+                    .ok()
+                    .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+                    .map_or(0, |d| d.as_millis() as u64);
+                self.disk.insert(name, DiskIndexEntry { bytes: meta.len(), last_used });
+            }
+        }
+        self.dir = Some(dir);
+        self.evict_disk();
+        log_info!("CACHE", "Disk cache: {} entries, {} bytes", self.disk.len(), self.disk_bytes());
+    }
+
+    pub fn get(&mut self, key: &str) -> Option<CachedResponse> {
+        let now = now_ms();
+        if let Some(entry) = self.memory.get_mut(key) {
+            entry.last_used = now;
+            return Some(entry.response.clone());
+        }
+        let name = file_name(key);
+        let path = self.dir.as_ref()?.join(&name);
+        let index = self.disk.get_mut(&name)?;
+        index.last_used = now;
+        let entry = std::fs::read(&path)
+            .ok()
+            .and_then(|bytes| serde_json::from_slice::<DiskEntry>(&bytes).ok())
+            .filter(|entry| entry.key == key);
+        let Some(entry) = entry else {
+            self.remove_file(&name);
+            return None;
+        };
+        self.insert_memory(key, entry.response.clone());
+        Some(entry.response)
+    }
+
+    pub fn put(&mut self, key: &str, response: CachedResponse) {
+        self.insert_memory(key, response.clone());
+        self.write_disk(key, response);
+    }
+
+    /// The server confirmed the cached body (304): restart its TTL.
+    pub fn renew(&mut self, key: &str) -> Option<CachedResponse> {
+        let mut response = self.get(key)?;
+        response.stored_at = now_ms();
+        self.put(key, response.clone());
+        self.stats.revalidated += 1;
+        Some(response)
+    }
+
+    /// Marks `key` as being refreshed; false if a refresh is already running.
+    pub fn begin_refresh(&mut self, key: &str) -> bool {
+        self.refreshing.insert(key.to_string())
+    }
+
+    pub fn end_refresh(&mut self, key: &str) {
+        self.refreshing.remove(key);
+    }
+
+    pub fn record_hit(&mut self, stale: bool) {
+        if stale {
+            self.stats.stale_hits += 1;
+        } else {
+            self.stats.hits += 1;
+        }
+    }
+
+    pub fn record_miss(&mut self) {
+        self.stats.misses += 1;
+    }
+
+    /// Drops every entry, in memory and on disk. Returns how many there were.
+    pub fn clear(&mut self) -> usize {
+        let count = self.memory.len().max(self.disk.len());
+        self.memory.clear();
+        self.memory_bytes = 0;
+        let names: Vec<String> = self.disk.keys().cloned().collect();
+        for name in names {
+            self.remove_file(&name);
+        }
+        self.stats = CacheStats::default();
+        count
+    }
+
+    pub fn stats(&self) -> CacheStats {
+        CacheStats {
+            memory_entries: self.memory.len(),
+            memory_bytes: self.memory_bytes,
+            memory_limit_bytes: MEMORY_LIMIT_BYTES,
+            disk_entries: self.disk.len(),
+            disk_bytes: self.disk_bytes(),
+            disk_limit_bytes: if self.dir.is_some() { DISK_LIMIT_BYTES } else { 0 },
+            ..self.stats.clone()
+        }
+    }
+
+    fn insert_memory(&mut self, key: &str, response: CachedResponse) {
+        let size = response.size();
+        if let Some(old) = self.memory.remove(key) {
+            self.memory_bytes -= old.response.size();
+        }
+        // A single body over the limit would just evict everything else.
+        if size > MEMORY_LIMIT_BYTES {
+            return;
+        }
+        self.memory_bytes += size;
+        self.memory.insert(key.to_string(), MemoryEntry { response, last_used: now_ms() });
+        while self.memory_bytes > MEMORY_LIMIT_BYTES {
+            let Some(oldest) = self
+                .memory
+                .iter()
+                .min_by_key(|(_, e)| e.last_used)
+                .map(|(k, _)| k.clone())
+            else {
+                break;
+            };
+            if let Some(entry) = self.memory.remove(&oldest) {
+                self.memory_bytes -= entry.response.size();
+                self.stats.evictions += 1;
+            }
+        }
+    }
+
+    fn write_disk(&mut self, key: &str, response: CachedResponse) {
+        let Some(dir) = self.dir.clone() else {
+            return;
+        };
+        let name = file_name(key);
+        let entry = DiskEntry {
+            key: key.to_string(),
+            response,
+        };
+        let result = serde_json::to_vec(&entry)
+            .map_err(FlashplexError::from)
+            .and_then(|bytes| write_atomic(&dir.join(&name), &bytes).map(|_| bytes.len() as u64));
+        match result {
+            Ok(bytes) => {
+                self.disk.insert(name, DiskIndexEntry { bytes, last_used: now_ms() });
+                self.evict_disk();
+            }
+            Err(e) => log_warn!("CACHE", "Could not write cache entry: {}", e),
+        }
+    }
+
+    fn evict_disk(&mut self) {
+        while self.disk_bytes() > DISK_LIMIT_BYTES {
+            let Some(oldest) = self
+                .disk
+                .iter()
+                .min_by_key(|(_, e)| e.last_used)
+                .map(|(k, _)| k.clone())
+            else {
+                break;
+            };
+            self.remove_file(&oldest);
+            self.stats.evictions += 1;
+        }
+    }
+
+    fn remove_file(&mut self, name: &str) {
+        self.disk.remove(name);
+        if let Some(dir) = &self.dir {
+            let _ = std::fs::remove_file(dir.join(name));
+        }
+    }
+
+    fn disk_bytes(&self) -> u64 {
+        self.disk.values().map(|e| e.bytes).sum()
+    }
+}
+
+/// Cache key for a GET: server, endpoint and the query sorted, without the apikey.
+pub fn cache_key(base_url: &str, endpoint: &str, query: &[(String, String)]) -> String {
+    let mut pairs: Vec<&(String, String)> = query.iter().filter(|(k, _)| k != "apikey").collect();
+    pairs.sort();
+    let mut key = format!("{}|{}", base_url, endpoint.trim_start_matches('/'));
+    for (k, v) in pairs {
+        key.push('|');
+        key.push_str(k);
+        key.push('=');
+        key.push_str(v);
+    }
+    key
+}
+
+fn file_name(key: &str) -> String {
+    let mut hasher = DefaultHasher::new();
+    key.hash(&mut hasher);
+    format!("{:016x}.json", hasher.finish())
+}
+
+fn now_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_millis() as u64)
+}
@@ -0,0 +1,313 @@
+// Local poster/thumbnail cache behind the `gdsimg://` protocol.
+//
+// The webview asks for `gdsimg://localhost/thumbnail?bpath=..&source_id=..&w=..`
+// (also `album_art` and `proxy_image?url=..`; Windows/Android spell it
+// `http://gdsimg.localhost/..`). Rust fetches the image through `GdsClient`, so
+// the API key never ends up in an <img src>, and keeps it on disk, one file per
+// source_id/bpath/width. A cached copy at least as wide as the request is served
+// instead of downloading the same image again, and once the directory passes
+// `DISK_LIMIT_BYTES` the least recently used files are removed.
+use crate::error::{FlashplexError, FlashplexResult};
+use crate::gds::GdsState;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::time::{SystemTime, UNIX_EPOCH};
+use tauri::http::{Request, Response, StatusCode};
+use tauri::Manager;
+
+pub const SCHEME: &str = "gdsimg";
+const DISK_LIMIT_BYTES: u64 = 256 * 1024 * 1024;
+const MAX_WIDTH: u32 = 4096;
+
+/// `gdsimg` URL of an image endpoint, in the spelling this platform's webview routes.
+pub fn url(kind: &str, query: &[(&str, String)]) -> String {
+    let origin = if cfg!(any(target_os = "windows", target_os = "android")) {
+        "http://gdsimg.localhost"
+    } else {
+        "gdsimg://localhost"
+    };
+    let query: Vec<String> = query
+        .iter()
+        .map(|(k, v)| format!("{}={}", k, urlencoding::encode(v)))
+        .collect();
+    format!("{}/{}?{}", origin, kind, query.join("&"))
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ImageRequest {
+    /// `width` 0 leaves the size to the server.
+    Thumbnail { bpath: String, source_id: u32, width: u32 },
+    AlbumArt { bpath: String, source_id: u32 },
+    Proxy { url: String },
+}
+
+impl ImageRequest {
+    /// Parses the path and query of a `gdsimg://` URI.
+    pub fn parse(path: &str, query: Option<&str>) -> FlashplexResult<Self> {
+        let params: HashMap<String, String> = query
+            .unwrap_or_default()
+            .split('&')
+            .filter(|pair| !pair.is_empty())
+            .map(|pair| {
+                let (k, v) = pair.split_once('=').unwrap_or((pair, ""));
+                let decode = |s: &str| urlencoding::decode(s).map(|d| d.into_owned()).unwrap_or_else(|_| s.to_string());
+                (decode(k), decode(v))
+            })
+            .collect();
+        let param = |name: &str| params.get(name).map(|v| v.trim()).filter(|v| !v.is_empty());
+        let bpath = || {
+            param("bpath")
+                .map(str::to_string)
+                .ok_or_else(|| FlashplexError::invalid_input("bpath is required"))
+        };
+        let source_id = param("source_id").and_then(|v| v.parse().ok()).unwrap_or(0);
+        match path.trim_matches('/') {
+            "thumbnail" => Ok(ImageRequest::Thumbnail {
+                bpath: bpath()?,
+                source_id,
+                width: param("w").and_then(|v| v.parse().ok()).unwrap_or(0).min(MAX_WIDTH),
+            }),
+            "album_art" => Ok(ImageRequest::AlbumArt { bpath: bpath()?, source_id }),
+            "proxy_image" => {
+                let url = param("url").ok_or_else(|| FlashplexError::invalid_input("url is required"))?;
+                if !(url.starts_with("http://") || url.starts_with("https://")) {
+                    return Err(FlashplexError::invalid_input("proxy_image url must be http(s)"));
+                }
+                Ok(ImageRequest::Proxy { url: url.to_string() })
+            }
+            other => Err(FlashplexError::invalid_input(format!("unknown image kind '{}'", other))),
+        }
+    }
+
+    fn endpoint(&self) -> &'static str {
+        match self {
+            ImageRequest::Thumbnail { .. } => "thumbnail",
+            ImageRequest::AlbumArt { .. } => "album_art",
+            ImageRequest::Proxy { .. } => "proxy_image",
+        }
+    }
+
+    fn to_query(&self) -> Vec<(String, String)> {
+        match self {
+            ImageRequest::Thumbnail { bpath, source_id, width } => {
+                let mut q = vec![("bpath".to_string(), bpath.clone()), ("source_id".to_string(), source_id.to_string())];
+                if *width > 0 {
+                    q.push(("w".to_string(), width.to_string()));
+                }
+                q
+            }
+            ImageRequest::AlbumArt { bpath, source_id } => {
+                vec![("bpath".to_string(), bpath.clone()), ("source_id".to_string(), source_id.to_string())]
+            }
+            ImageRequest::Proxy { url } => vec![("url".to_string(), url.clone())],
+        }
+    }
+
+    fn width(&self) -> u32 {
+        match self {
+            ImageRequest::Thumbnail { width, .. } => *width,
+            _ => 0,
+        }
+    }
+
+    // Every width of one image shares this stem; the file name adds `_<width>`.
+    fn stem(&self, base_url: &str) -> String {
+        let mut hasher = DefaultHasher::new();
+        base_url.hash(&mut hasher);
+        self.endpoint().hash(&mut hasher);
+        match self {
+            ImageRequest::Thumbnail { bpath, source_id, .. } | ImageRequest::AlbumArt { bpath, source_id } => {
+                source_id.hash(&mut hasher);
+                bpath.hash(&mut hasher);
+            }
+            ImageRequest::Proxy { url } => url.hash(&mut hasher),
+        }
+        format!("{:016x}", hasher.finish())
+    }
+}
+
+struct CachedFile {
+    bytes: u64,
+    last_used: u64,
+}
+
+pub struct ImageCache {
+    dir: PathBuf,
+    files: HashMap<String, CachedFile>,
+}
+
+impl ImageCache {
+    pub fn open(dir: PathBuf) -> Self {
+        let mut cache = ImageCache {
+            dir,
+            files: HashMap::new(),
+        };
+        if let Err(e) = std::fs::create_dir_all(&cache.dir) {
+            log_warn!("IMAGE", "Cannot create {}: {}", cache.dir.display(), e);
+        }
+        if let Ok(entries) = std::fs::read_dir(&cache.dir) {
+            for entry in entries.flatten() {
+                let name = entry.file_name().to_string_lossy().to_string();
+                let Some(meta) = entry.metadata().ok().filter(|m| m.is_file()) else {
+                    continue;
+                };
+                if parse_file_name(&name).is_none() {
+                    continue;
+                }
+                let last_used = meta.modified().ok().map_or(0, unix_ms);
+                cache.files.insert(name, CachedFile { bytes: meta.len(), last_used });
+            }
+        }
+        cache.evict();
+        cache
+    }
+
+    /// The narrowest cached copy of `stem` that is at least `width` wide (exactly
+    /// the server default when `width` is 0).
+    pub fn get(&mut self, stem: &str, width: u32) -> Option<Vec<u8>> {
+        let name = self
+            .files
+            .keys()
+            .filter_map(|name| parse_file_name(name).filter(|(s, _)| *s == stem).map(|(_, w)| (w, name)))
+            .filter(|(w, _)| if width == 0 { *w == 0 } else { *w >= width })
+            .min_by_key(|(w, _)| *w)
+            .map(|(_, name)| name.clone())?;
+        let path = self.dir.join(&name);
+        let Ok(bytes) = std::fs::read(&path) else {
+            self.files.remove(&name);
+            return None;
+        };
+        let now = SystemTime::now();
+        if let Some(file) = self.files.get_mut(&name) {
+            file.last_used = unix_ms(now);
+        }
+        // The mtime carries recency across restarts.
+        if let Ok(file) = std::fs::File::options().write(true).open(&path) {
+            let _ = file.set_modified(now);
+        }
+        Some(bytes)
+    }
+
+    pub fn put(&mut self, stem: &str, width: u32, bytes: &[u8]) {
+        let name = format!("{}_{}.img", stem, width);
+        if let Err(e) = crate::storage::write_atomic(&self.dir.join(&name), bytes) {
+            log_warn!("IMAGE", "Could not cache image: {}", e);
+            return;
+        }
+        self.files.insert(
+            name,
+            CachedFile {
+                bytes: bytes.len() as u64,
+                last_used: unix_ms(SystemTime::now()),
+            },
+        );
+        self.evict();
+    }
+
+    fn evict(&mut self) {
+        let mut total: u64 = self.files.values().map(|f| f.bytes).sum();
+        while total > DISK_LIMIT_BYTES {
+            let Some(oldest) = self
+                .files
+                .iter()
+                .min_by_key(|(_, f)| f.last_used)
+                .map(|(name, _)| name.clone())
+            else {
+                break;
+            };
+            if let Some(file) = self.files.remove(&oldest) {
+                total -= file.bytes;
+            }
+            let _ = std::fs::remove_file(self.dir.join(&oldest));
+        }
+    }
+}
+
+// "<stem>_<width>.img" -> (stem, width)
+fn parse_file_name(name: &str) -> Option<(&str, u32)> {
+    let (stem, width) = name.strip_suffix(".img")?.rsplit_once('_')?;
+    Some((stem, width.parse().ok()?))
+}
+
+fn unix_ms(time: SystemTime) -> u64 {
+    time.duration_since(UNIX_EPOCH).map_or(0, |d| d.as_millis() as u64)
+}
+
+// Images get a guessed type when served from disk; browsers sniff anyway.
+fn sniff_content_type(bytes: &[u8]) -> &'static str {
+    match bytes {
+        [0xFF, 0xD8, 0xFF, ..] => "image/jpeg",
+        [0x89, b'P', b'N', b'G', ..] => "image/png",
+        [b'G', b'I', b'F', ..] => "image/gif",
+        [b'R', b'I', b'F', b'F', _, _, _, _, b'W', b'E', b'B', b'P', ..] => "image/webp",
+        _ => "application/octet-stream",
+    }
+}
+
+pub struct ImageCacheState(pub Arc<Mutex<ImageCache>>);
+
+impl ImageCacheState {
+    pub fn open(app: &tauri::AppHandle) -> Self {
+        let dir = app
+            .path()
+            .app_cache_dir()
+            .unwrap_or_else(|_| std::env::temp_dir().join("flashplex").join("cache"))
+            .join("images");
+        ImageCacheState(Arc::new(Mutex::new(ImageCache::open(dir))))
+    }
+}
+
+/// Handler for the `gdsimg` URI scheme.
+pub async fn respond(app: &tauri::AppHandle, request: &Request<Vec<u8>>) -> Response<Vec<u8>> {
+    match serve(app, request).await {
+        Ok((bytes, content_type)) => Response::builder()
+            .status(StatusCode::OK)
+            .header("Content-Type", content_type)
+            .header("Cache-Control", "max-age=86400")
+            .header("Access-Control-Allow-Origin", "*")
+            .body(bytes)
+            .unwrap_or_default(),
+        Err(e) => {
+            log_debug!("IMAGE", "{} failed: {}", request.uri().path(), e);
+            let status = match &e {
+                FlashplexError::InvalidInput { .. } => 400,
+                FlashplexError::Auth { status } | FlashplexError::Http { status, .. } => *status,
+                FlashplexError::ServerNotConfigured => 503,
+                FlashplexError::Network { .. } => 502,
+                _ => 500,
+            };
+            Response::builder()
+                .status(status)
+                .header("Content-Type", "text/plain")
+                .body(e.to_string().into_bytes())
+                .unwrap_or_default()
+        }
+    }
+}
+
+async fn serve(app: &tauri::AppHandle, request: &Request<Vec<u8>>) -> FlashplexResult<(Vec<u8>, String)> {
+    let image = ImageRequest::parse(request.uri().path(), request.uri().query())?;
+    let client = app
+        .try_state::<GdsState>()
+        .ok_or(FlashplexError::ServerNotConfigured)?
+        .client()?;
+    let cache = app
+        .try_state::<ImageCacheState>()
+        .ok_or_else(|| FlashplexError::internal("image cache not initialized"))?;
+    let stem = image.stem(client.base_url());
+    let cached = cache.0.lock()?.get(&stem, image.width());
+    if let Some(bytes) = cached {
+        let content_type = sniff_content_type(&bytes).to_string();
+        return Ok((bytes, content_type));
+    }
+    let (bytes, content_type) = client.get_bytes(image.endpoint(), &image.to_query()).await?;
+    let content_type = content_type.unwrap_or_else(|| sniff_content_type(&bytes).to_string());
+    // Error pages served with 200 aren't worth keeping.
+    if content_type.starts_with("image/") {
+        cache.0.lock()?.put(&stem, image.width(), &bytes);
+    }
+    Ok((bytes, content_type))
+}
@@ -0,0 +1,193 @@
+// Structured app log.
+//
+// Every line carries a level and a target (INVOKE, RESIZE, QUALITY, SEARCH, ...).
+// Lines are mirrored to stdout and, once `init` ran, appended to rotating files in
+// the app log dir. Debug lines are kept only while `debug_logs` (the UI's
+// flashplex_debug_logs toggle) is on. `apikey=` values are masked before anything
+// is written.
+use crate::credentials::redact;
+use crate::error::{FlashplexError, FlashplexResult};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::fs::{File, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+
+// Crate-wide via `#[macro_use] mod logging;` in lib.rs: `log_info!("TARGET", "fmt", args..)`.
+macro_rules! log_error {
+    ($target:expr, $($arg:tt)+) => {
+        $crate::logging::write($crate::logging::Level::Error, $target, format_args!($($arg)+))
+    };
+}
+
+macro_rules! log_warn {
+    ($target:expr, $($arg:tt)+) => {
+        $crate::logging::write($crate::logging::Level::Warn, $target, format_args!($($arg)+))
+    };
+}
+
+macro_rules! log_info {
+    ($target:expr, $($arg:tt)+) => {
+        $crate::logging::write($crate::logging::Level::Info, $target, format_args!($($arg)+))
+    };
+}
+
+macro_rules! log_debug {
+    ($target:expr, $($arg:tt)+) => {
+        $crate::logging::write($crate::logging::Level::Debug, $target, format_args!($($arg)+))
+    };
+}
+
+const LOG_FILE: &str = "flashplex.log";
+const MAX_FILE_BYTES: u64 = 2 * 1024 * 1024;
+// flashplex.log plus flashplex.1.log .. flashplex.4.log
+const KEEP_FILES: usize = 5;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Level {
+    Error,
+    Warn,
+    Info,
+    Debug,
+}
+
+impl fmt::Display for Level {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Level::Error => "ERROR",
+            Level::Warn => "WARN",
+            Level::Info => "INFO",
+            Level::Debug => "DEBUG",
+        })
+    }
+}
+
+struct Sink {
+    dir: PathBuf,
+    file: Option<File>,
+    size: u64,
+}
+
+static SINK: Mutex<Option<Sink>> = Mutex::new(None);
+static DEBUG: AtomicBool = AtomicBool::new(false);
+
+fn file_path(dir: &Path, index: usize) -> PathBuf {
+    if index == 0 {
+        dir.join(LOG_FILE)
+    } else {
+        dir.join(format!("flashplex.{}.log", index))
+    }
+}
+
+impl Sink {
+    fn open(&mut self) {
+        let path = file_path(&self.dir, 0);
+        self.file = OpenOptions::new().create(true).append(true).open(&path).ok();
+        self.size = std::fs::metadata(&path).map(|m| m.len()).unwrap_or(0);
+    }
+
+    fn rotate(&mut self) {
+        self.file = None;
+        let _ = std::fs::remove_file(file_path(&self.dir, KEEP_FILES - 1));
+        for index in (0..KEEP_FILES - 1).rev() {
+            let _ = std::fs::rename(file_path(&self.dir, index), file_path(&self.dir, index + 1));
+        }
+        self.open();
+    }
+
+    fn append(&mut self, line: &str) {
+        if self.size >= MAX_FILE_BYTES {
+            self.rotate();
+        }
+        if let Some(file) = self.file.as_mut() {
+            if writeln!(file, "{}", line).is_ok() {
+                self.size += line.len() as u64 + 1;
+            }
+        }
+    }
+}
+
+/// Starts writing to `dir`. Lines logged before this only went to stdout.
+pub fn init(dir: PathBuf, debug: bool) {
+    if let Err(e) = std::fs::create_dir_all(&dir) {
+        println!("[LOG] Cannot create {}: {}", dir.display(), e);
+        return;
+    }
+    set_debug(debug);
+    let mut sink = Sink { dir, file: None, size: 0 };
+    sink.open();
+    let path = file_path(&sink.dir, 0);
+    if let Ok(mut lock) = SINK.lock() {
+        *lock = Some(sink);
+    }
+    write(Level::Info, "LOG", format_args!("Logging to {} (debug={})", path.display(), debug));
+}
+
+pub fn set_debug(enabled: bool) {
+    DEBUG.store(enabled, Ordering::Relaxed);
+}
+
+pub fn debug_enabled() -> bool {
+    DEBUG.load(Ordering::Relaxed)
+}
+
+pub fn write(level: Level, target: &str, args: fmt::Arguments) {
+    if level == Level::Debug && !debug_enabled() {
+        return;
+    }
+    let message = redact(&args.to_string());
+    println!("[{}] {}", target, message);
+    let line = format!(
+        "{} {:<5} [{}] {}",
+        chrono::Local::now().format("%Y-%m-%d %H:%M:%S%.3f"),
+        level,
+        target,
+        message
+    );
+    if let Ok(mut lock) = SINK.lock() {
+        if let Some(sink) = lock.as_mut() {
+            sink.append(&line);
+        }
+    }
+}
+
+/// Concatenates the rotated files (oldest first) into one report in the log dir.
+pub fn export(header: &str) -> FlashplexResult<PathBuf> {
+    let dir = SINK
+        .lock()?
+        .as_ref()
+        .map(|sink| sink.dir.clone())
+        .ok_or_else(|| FlashplexError::internal("logging not initialized"))?;
+    let mut report = String::from(header);
+    for index in (0..KEEP_FILES).rev() {
+        let path = file_path(&dir, index);
+        if let Ok(text) = std::fs::read_to_string(&path) {
+            report.push_str(&format!("\n===== {} =====\n", path.display()));
+            report.push_str(&text);
+        }
+    }
+    let out = dir.join(format!("flashplex-logs-{}.txt", chrono::Local::now().format("%Y%m%d-%H%M%S")));
+    std::fs::write(&out, report)
+        .map_err(|e| FlashplexError::internal(format!("Failed to write {}: {}", out.display(), e)))?;
+    Ok(out)
+}
+
+/// Writes the recent log files into one report and returns its path.
+#[tauri::command(rename_all = "snake_case")]
+pub fn export_logs(app: tauri::AppHandle) -> FlashplexResult<String> {
+    use tauri::Manager;
+    let header = format!(
+        "GDS Mobile Player {}\nOS: {} ({})\nDebug logs: {}\nExported: {}\n",
+        app.package_info().version,
+        std::env::consts::OS,
+        std::env::consts::ARCH,
+        debug_enabled(),
+        chrono::Local::now().to_rfc3339()
+    );
+    let path = export(&header)?;
+    log_info!("LOG", "Exported logs to {}", path.display());
+    Ok(path.display().to_string())
+}
@@ -0,0 +1,274 @@
+// Loopback proxy for media the webview or ExoPlayer loads by URL (web <video>/<audio>,
+// <track>, previews, Android playback).
+//
+// `http://127.0.0.1:<port>/<token>/<endpoint>?<query>` is forwarded to the same
+// gds_dviewer endpoint of the active server with the API key added here, so signed
+// URLs never reach the webview. Range requests pass through untouched, which keeps
+// seeking working. The random token keeps other local processes from using the key.
+use crate::error::{FlashplexError, FlashplexResult};
+use crate::gds::{split_endpoint, GdsClient};
+use chacha20poly1305::aead::rand_core::RngCore;
+use chacha20poly1305::aead::OsRng;
+use std::io::{Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tauri_plugin_http::reqwest;
+
+const MAX_HEAD_BYTES: usize = 16 * 1024;
+// A client that connects and never finishes its request head is dropped after this.
+const READ_TIMEOUT: Duration = Duration::from_secs(10);
+// Media elements keep a few connections open each; beyond this new ones get a 503.
+const MAX_CONNECTIONS: usize = 32;
+// Upstream headers a media element needs to play and seek.
+const FORWARDED_HEADERS: [reqwest::header::HeaderName; 6] = [
+    reqwest::header::CONTENT_TYPE,
+    reqwest::header::CONTENT_LENGTH,
+    reqwest::header::CONTENT_RANGE,
+    reqwest::header::ACCEPT_RANGES,
+    reqwest::header::ETAG,
+    reqwest::header::LAST_MODIFIED,
+];
+
+pub type ActiveClient = Arc<Mutex<Option<GdsClient>>>;
+
+pub struct MediaProxy {
+    base_url: String,
+}
+
+impl MediaProxy {
+    /// Binds a random loopback port and serves every connection on its own thread,
+    /// at most `MAX_CONNECTIONS` at a time.
+    pub fn start(active: ActiveClient) -> FlashplexResult<Self> {
+        let listener = TcpListener::bind("127.0.0.1:0")
+            .map_err(|e| FlashplexError::internal(format!("Media proxy not started: {}", e)))?;
+        let addr = listener
+            .local_addr()
+            .map_err(|e| FlashplexError::internal(format!("Media proxy not started: {}", e)))?;
+        let mut bytes = [0u8; 16];
+        OsRng.fill_bytes(&mut bytes);
+        let token: String = bytes.iter().map(|b| format!("{:02x}", b)).collect();
+        let base_url = format!("http://{}/{}", addr, token);
+
+        std::thread::Builder::new()
+            .name("media-proxy".to_string())
+            .spawn(move || {
+                let open = Arc::new(AtomicUsize::new(0));
+                for stream in listener.incoming().flatten() {
+                    let Some(slot) = ConnectionSlot::claim(&open) else {
+                        log_warn!("MEDIA", "{} connections open; refusing another", MAX_CONNECTIONS);
+                        refuse(stream);
+                        continue;
+                    };
+                    let active = active.clone();
+                    let token = token.clone();
+                    let _ = std::thread::Builder::new().name("media-proxy-conn".to_string()).spawn(move || {
+                        let _slot = slot;
+                        handle(stream, &token, &active)
+                    });
+                }
+            })
+            .map_err(|e| FlashplexError::internal(format!("Media proxy not started: {}", e)))?;
+        log_info!("MEDIA", "Media proxy listening on {}", addr);
+        Ok(MediaProxy { base_url })
+    }
+
+    /// `http://127.0.0.1:<port>/<token>`; append `/<endpoint>?<query>`.
+    pub fn base_url(&self) -> &str {
+        &self.base_url
+    }
+
+    pub fn url(&self, name: &str, query: &[(&str, String)]) -> String {
+        let query: Vec<String> = query
+            .iter()
+            .map(|(k, v)| format!("{}={}", k, urlencoding::encode(v)))
+            .collect();
+        format!("{}/{}?{}", self.base_url, name, query.join("&"))
+    }
+}
+
+/// Answers 503 without waiting for the request (that would stall the accept loop).
+fn refuse(mut stream: TcpStream) {
+    let _ = stream.write_all(b"HTTP/1.1 503 Service Unavailable\r\nContent-Length: 0\r\nConnection: close\r\n\r\n");
+    // Closing with unread input resets the connection before the client sees the
+    // 503, so whatever of the request already arrived is read first.
+    if stream.set_nonblocking(true).is_ok() {
+        let mut buf = [0u8; 4096];
+        while matches!(stream.read(&mut buf), Ok(n) if n > 0) {}
+    }
+}
+
+/// Counts an open connection until dropped.
+struct ConnectionSlot(Arc<AtomicUsize>);
+
+impl ConnectionSlot {
+    fn claim(open: &Arc<AtomicUsize>) -> Option<Self> {
+        open.fetch_update(Ordering::SeqCst, Ordering::SeqCst, |n| (n < MAX_CONNECTIONS).then_some(n + 1))
+            .ok()
+            .map(|_| ConnectionSlot(open.clone()))
+    }
+}
+
+impl Drop for ConnectionSlot {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+struct ProxyRequest {
+    head_only: bool,
+    target: String,
+    range: Option<String>,
+}
+
+fn read_request(stream: &mut TcpStream) -> Option<ProxyRequest> {
+    let mut head = Vec::new();
+    let mut byte = [0u8; 1];
+    while !head.ends_with(b"\r\n\r\n") {
+        if head.len() >= MAX_HEAD_BYTES || stream.read(&mut byte).ok()? == 0 {
+            return None;
+        }
+        head.push(byte[0]);
+    }
+    let head = String::from_utf8_lossy(&head).into_owned();
+    let mut lines = head.split("\r\n");
+    let mut request_line = lines.next()?.split_whitespace();
+    let method = request_line.next()?;
+    let target = request_line.next()?.to_string();
+    let range = lines
+        .filter_map(|line| line.split_once(':'))
+        .find(|(name, _)| name.trim().eq_ignore_ascii_case("range"))
+        .map(|(_, value)| value.trim().to_string());
+    Some(ProxyRequest {
+        head_only: method.eq_ignore_ascii_case("HEAD"),
+        target,
+        range,
+    })
+}
+
+fn handle(mut stream: TcpStream, token: &str, active: &ActiveClient) {
+    if let Err(e) = stream.set_read_timeout(Some(READ_TIMEOUT)) {
+        log_debug!("MEDIA", "Proxy connection dropped: {}", e);
+        return;
+    }
+    let Some(request) = read_request(&mut stream) else {
+        return;
+    };
+    if let Err(e) = tauri::async_runtime::block_on(forward(&mut stream, token, active, &request)) {
+        log_debug!("MEDIA", "Proxy request failed: {}", e);
+        let status = match &e {
+            FlashplexError::InvalidInput { .. } => "400 Bad Request",
+            FlashplexError::ServerNotConfigured => "503 Service Unavailable",
+            _ => "502 Bad Gateway",
+        };
+        let _ = write!(stream, "HTTP/1.1 {}\r\nContent-Length: 0\r\nConnection: close\r\n\r\n", status);
+    }
+}
+
+// Errors before the status line is written are answered by `handle`; later ones just
+// close the connection.
+async fn forward(stream: &mut TcpStream, token: &str, active: &ActiveClient, request: &ProxyRequest) -> FlashplexResult<()> {
+    let endpoint = request
+        .target
+        .strip_prefix('/')
+        .and_then(|rest| rest.strip_prefix(token))
+        .and_then(|rest| rest.strip_prefix('/'))
+        .ok_or_else(|| FlashplexError::invalid_input("unknown media proxy path"))?;
+    let (name, query) = split_endpoint(endpoint)?;
+    let client = active.lock()?.clone().ok_or(FlashplexError::ServerNotConfigured)?;
+    let mut response = client.open_media(&name, &query, request.range.as_deref()).await?;
+
+    let status = response.status();
+    let mut head = format!(
+        "HTTP/1.1 {} {}\r\n",
+        status.as_u16(),
+        status.canonical_reason().unwrap_or("")
+    );
+    for name in FORWARDED_HEADERS {
+        if let Some(value) = response.headers().get(&name).and_then(|v| v.to_str().ok()) {
+            head.push_str(&format!("{}: {}\r\n", name, value));
+        }
+    }
+    head.push_str("Access-Control-Allow-Origin: *\r\nConnection: close\r\n\r\n");
+    let io_err = |e: std::io::Error| FlashplexError::internal(format!("Media proxy write: {}", e));
+    stream.write_all(head.as_bytes()).map_err(io_err)?;
+    if request.head_only {
+        return Ok(());
+    }
+    while let Some(chunk) = response.chunk().await? {
+        // The player closes the connection when it seeks; that ends this transfer.
+        stream.write_all(&chunk).map_err(io_err)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::http_cache::ResponseCache;
+    use crate::test_http::{response, serve};
+
+    fn get(url: &str, range: &str) -> (String, Vec<u8>) {
+        let rest = url.strip_prefix("http://").unwrap();
+        let (addr, path) = rest.split_once('/').unwrap();
+        let mut stream = TcpStream::connect(addr).unwrap();
+        write!(stream, "GET /{} HTTP/1.1\r\nHost: {}\r\nRange: {}\r\n\r\n", path, addr, range).unwrap();
+        let mut out = Vec::new();
+        stream.read_to_end(&mut out).unwrap();
+        let split = out.windows(4).position(|w| w == b"\r\n\r\n").unwrap();
+        (String::from_utf8_lossy(&out[..split]).into_owned(), out[split + 4..].to_vec())
+    }
+
+    #[test]
+    fn forwards_range_and_adds_key() {
+        let server = serve(vec![response(
+            "206 Partial Content",
+            &[("Content-Range", "bytes 2-5/10"), ("Content-Type", "video/mp4")],
+            b"2345",
+        )]);
+        let cache = Arc::new(Mutex::new(ResponseCache::new()));
+        let client = GdsClient::new(reqwest::Client::new(), cache, &server.base_url, "secret");
+        let proxy = MediaProxy::start(Arc::new(Mutex::new(Some(client)))).unwrap();
+        assert!(!proxy.base_url().contains("secret"));
+
+        let (head, body) = get(&format!("{}/stream?bpath=YQ&source_id=1", proxy.base_url()), "bytes=2-5");
+        assert!(head.starts_with("HTTP/1.1 206"), "{}", head);
+        assert!(head.contains("content-range: bytes 2-5/10"), "{}", head);
+        assert_eq!(body, b"2345");
+
+        let upstream = &server.requests()[0];
+        assert!(upstream.starts_with("GET /gds_dviewer/normal/stream?bpath=YQ&source_id=1&apikey=secret "), "{}", upstream);
+        assert!(upstream.to_ascii_lowercase().contains("range: bytes=2-5"));
+    }
+
+    #[test]
+    fn rejects_wrong_token() {
+        let proxy = MediaProxy::start(Arc::new(Mutex::new(None))).unwrap();
+        let (token_base, _) = proxy.base_url().rsplit_once('/').unwrap();
+        let (head, _) = get(&format!("{}/not-the-token/stream", token_base), "bytes=0-");
+        assert!(head.starts_with("HTTP/1.1 400"), "{}", head);
+    }
+
+    #[test]
+    fn refuses_connections_over_the_limit() {
+        let proxy = MediaProxy::start(Arc::new(Mutex::new(None))).unwrap();
+        let addr = proxy.base_url().strip_prefix("http://").unwrap().split_once('/').unwrap().0;
+        // Idle clients that never send a request hold their slot.
+        let idle: Vec<TcpStream> = (0..MAX_CONNECTIONS).map(|_| TcpStream::connect(addr).unwrap()).collect();
+        let mut refused = TcpStream::connect(addr).unwrap();
+        let mut head = String::new();
+        refused.read_to_string(&mut head).unwrap();
+        assert!(head.starts_with("HTTP/1.1 503"), "{}", head);
+
+        // Closed clients free their slot.
+        drop(idle);
+        // A wrong token gets a 400 once it is served at all.
+        let url = format!("http://{}/not-the-token/stream", addr);
+        let served = (0..50).any(|_| {
+            std::thread::sleep(Duration::from_millis(20));
+            get(&url, "bytes=0-").0.starts_with("HTTP/1.1 400")
+        });
+        assert!(served);
+    }
+}
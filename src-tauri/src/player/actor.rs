@@ -0,0 +1,442 @@
+// Player actor.
+//
+// The player is owned by one thread. Commands reach it as `PlayerCommand`s over
+// a channel and get their result back on a one-shot channel, so a slow mpv init
+// (waiting on the main thread, trying several render paths) no longer holds a
+// lock that every other command queues behind. Callers await the reply with a
+// timeout, so a command never blocks the main thread (which mpv init itself
+// needs) or a runtime worker. A panic while handling a command is caught; the
+// player it happened in is dropped and the thread keeps serving.
+//
+// The thread owns every player instance, keyed by player id ("main" unless a
+// command names another one, e.g. a muted hover preview). The main player can be
+// created ahead of the first launch (`warm`) and parked instead of destroyed on
+// close, so the next title skips view creation and the render path fallbacks.
+//
+// Creating a player runs on a thread of its own and reports back with
+// `Created`; launches of a player that is still initializing wait for it, while
+// every other command is served meanwhile (and sees the player as not active).
+use super::diagnostics::{first_frame_cancelled, first_frame_pending};
+use super::{create_backend, LoadRequest, PlayerBackend};
+use crate::error::{FlashplexError, FlashplexResult};
+use std::collections::BTreeMap;
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::time::{Duration, Instant};
+use tokio::sync::oneshot;
+
+const CALL_TIMEOUT: Duration = Duration::from_secs(5);
+// Covers every render path candidate failing slowly.
+pub(super) const INIT_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// The player commands address when they don't pass a `player_id`.
+pub const MAIN_PLAYER_ID: &str = "main";
+const MAX_ID_LEN: usize = 32;
+
+type Reply<T> = oneshot::Sender<FlashplexResult<T>>;
+/// Runs against the addressed player (None when it doesn't exist).
+type Call = Box<dyn FnOnce(Option<&dyn PlayerBackend>) + Send>;
+/// Applied to a freshly created player before the first load.
+type Setup = Box<dyn FnOnce(&dyn PlayerBackend) + Send>;
+
+type Players = BTreeMap<String, Box<dyn PlayerBackend>>;
+
+enum PlayerCommand {
+    /// Creates player `id` if it doesn't exist (unless `max_players` are already
+    /// running), then loads `request`.
+    Launch {
+        app: tauri::AppHandle,
+        id: String,
+        max_players: usize,
+        setup: Setup,
+        request: Box<LoadRequest>,
+        /// When the launch was requested; start of the first-frame timing.
+        started: Instant,
+        /// Where the init thread reports the created player.
+        done: Sender<PlayerCommand>,
+        reply: Reply<()>,
+    },
+    /// Creates the main player hidden and idle if it doesn't exist yet.
+    Warm {
+        app: tauri::AppHandle,
+        setup: Setup,
+        done: Sender<PlayerCommand>,
+        reply: Reply<bool>,
+    },
+    /// Sent by an init thread once player `id` is created (or failed to be).
+    Created {
+        id: String,
+        created: FlashplexResult<Box<dyn PlayerBackend>>,
+    },
+    /// `keep_alive` parks the main player instead of destroying it.
+    Close {
+        id: String,
+        keep_alive: bool,
+        reply: Reply<()>,
+    },
+    Call {
+        id: String,
+        name: &'static str,
+        call: Call,
+    },
+    List {
+        reply: Reply<Vec<String>>,
+    },
+}
+
+impl PlayerCommand {
+    fn name(&self) -> &'static str {
+        match self {
+            PlayerCommand::Launch { .. } => "launch",
+            PlayerCommand::Warm { .. } => "warm",
+            PlayerCommand::Created { .. } => "created",
+            PlayerCommand::Close { .. } => "close",
+            PlayerCommand::Call { name, .. } => name,
+            PlayerCommand::List { .. } => "list",
+        }
+    }
+
+    // The player a panic while handling this command is blamed on.
+    fn target(&self) -> Option<&str> {
+        match self {
+            PlayerCommand::Launch { id, .. }
+            | PlayerCommand::Created { id, .. }
+            | PlayerCommand::Close { id, .. }
+            | PlayerCommand::Call { id, .. } => Some(id),
+            PlayerCommand::Warm { .. } => Some(MAIN_PLAYER_ID),
+            PlayerCommand::List { .. } => None,
+        }
+    }
+}
+
+pub struct PlayerState {
+    tx: Sender<PlayerCommand>,
+}
+
+impl PlayerState {
+    pub fn new() -> Self {
+        let (tx, rx) = mpsc::channel();
+        let spawned = std::thread::Builder::new()
+            .name("player".to_string())
+            .spawn(move || run(rx));
+        if let Err(e) = spawned {
+            // Every command then fails with a closed channel instead of hanging.
+            log_error!("PLAYER", "Player thread not started: {}", e);
+        }
+        PlayerState { tx }
+    }
+
+    async fn send<T>(&self, name: &'static str, timeout: Duration, command: impl FnOnce(Reply<T>) -> PlayerCommand) -> FlashplexResult<T> {
+        let (reply, rx) = oneshot::channel();
+        self.tx
+            .send(command(reply))
+            .map_err(|_| FlashplexError::channel_closed("player"))?;
+        match tokio::time::timeout(timeout, rx).await {
+            Ok(Ok(result)) => result,
+            Err(_) => Err(FlashplexError::PlayerBusy { command: name.to_string() }),
+            // The handler panicked before answering.
+            Ok(Err(_)) => Err(FlashplexError::internal(format!("player command '{}' failed", name))),
+        }
+    }
+
+    /// Creates player `id` on first use (running `setup` on it) and loads `request`.
+    /// At most `max_players` instances exist at a time.
+    pub async fn launch(
+        &self,
+        app: tauri::AppHandle,
+        id: Option<String>,
+        max_players: usize,
+        request: LoadRequest,
+        setup: impl FnOnce(&dyn PlayerBackend) + Send + 'static,
+    ) -> FlashplexResult<()> {
+        let id = resolve_id(id)?;
+        let started = Instant::now();
+        let done = self.tx.clone();
+        self.send("launch", INIT_TIMEOUT, |reply| PlayerCommand::Launch {
+            app,
+            id,
+            max_players,
+            setup: Box::new(setup),
+            request: Box::new(request),
+            started,
+            done,
+            reply,
+        })
+        .await
+    }
+
+    /// Initializes the main player without loading anything; true if it was created.
+    /// Blocks for the whole init, so never call it from the main thread or a runtime worker.
+    pub fn warm(
+        &self,
+        app: tauri::AppHandle,
+        setup: impl FnOnce(&dyn PlayerBackend) + Send + 'static,
+    ) -> FlashplexResult<bool> {
+        let done = self.tx.clone();
+        tauri::async_runtime::block_on(self.send("warm", INIT_TIMEOUT, |reply| PlayerCommand::Warm {
+            app,
+            setup: Box::new(setup),
+            done,
+            reply,
+        }))
+    }
+
+    pub async fn close(&self, id: Option<String>, keep_alive: bool) -> FlashplexResult<()> {
+        let id = resolve_id(id)?;
+        self.send("close", CALL_TIMEOUT, |reply| PlayerCommand::Close { id, keep_alive, reply })
+            .await
+    }
+
+    /// Ids of the running players.
+    pub async fn list(&self) -> FlashplexResult<Vec<String>> {
+        self.send("list", CALL_TIMEOUT, |reply| PlayerCommand::List { reply }).await
+    }
+
+    /// Runs `f` on the player thread whether or not player `id` exists.
+    pub async fn query<R: Send + 'static>(
+        &self,
+        id: Option<String>,
+        name: &'static str,
+        f: impl FnOnce(Option<&dyn PlayerBackend>) -> R + Send + 'static,
+    ) -> FlashplexResult<R> {
+        let id = resolve_id(id)?;
+        self.send(name, CALL_TIMEOUT, |reply| PlayerCommand::Call {
+            id,
+            name,
+            call: Box::new(move |player| {
+                let _ = reply.send(Ok(f(player)));
+            }),
+        })
+        .await
+    }
+
+    /// Runs `f` against player `id`; PlayerNotActive if it doesn't exist.
+    pub async fn with<R: Send + 'static>(
+        &self,
+        id: Option<String>,
+        name: &'static str,
+        f: impl FnOnce(&dyn PlayerBackend) -> FlashplexResult<R> + Send + 'static,
+    ) -> FlashplexResult<R> {
+        self.query(id, name, |player| f(player.ok_or(FlashplexError::PlayerNotActive)?))
+            .await?
+    }
+
+    /// Like `with`, but a missing player is not an error (the call is skipped).
+    pub async fn if_active(
+        &self,
+        id: Option<String>,
+        name: &'static str,
+        f: impl FnOnce(&dyn PlayerBackend) -> FlashplexResult<()> + Send + 'static,
+    ) -> FlashplexResult<()> {
+        self.query(id, name, |player| player.map_or(Ok(()), f)).await?
+    }
+}
+
+/// `player_id` as sent by the webview; None or blank is the main player.
+fn resolve_id(id: Option<String>) -> FlashplexResult<String> {
+    let id = id.map(|id| id.trim().to_string()).filter(|id| !id.is_empty());
+    let Some(id) = id else {
+        return Ok(MAIN_PLAYER_ID.to_string());
+    };
+    let valid = id.len() <= MAX_ID_LEN && id.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+    if !valid {
+        return Err(FlashplexError::invalid_input(format!("invalid player_id '{}'", id)));
+    }
+    Ok(id)
+}
+
+fn run(rx: Receiver<PlayerCommand>) {
+    let mut actor = Actor::default();
+    for command in rx {
+        let name = command.name();
+        let target = command.target().map(str::to_string);
+        let handled = panic::catch_unwind(AssertUnwindSafe(|| actor.handle(command)));
+        if handled.is_err() {
+            log_error!("PLAYER", "Panic while handling '{}'; dropping player {:?}", name, target);
+            // Its drop could panic again, so it is leaked rather than closed.
+            if let Some(broken) = target.and_then(|id| actor.players.remove(&id)) {
+                std::mem::forget(broken);
+            }
+        }
+    }
+    log_info!("PLAYER", "Player thread stopped");
+}
+
+/// A player being created on its init thread.
+struct Pending {
+    waiting: Vec<Waiter>,
+    /// Set by a close that arrived during init; applied once the player exists.
+    close: Option<bool>,
+}
+
+/// A command answered once its player is created.
+enum Waiter {
+    Launch {
+        app: tauri::AppHandle,
+        request: Box<LoadRequest>,
+        started: Instant,
+        reply: Reply<()>,
+    },
+    Warm {
+        reply: Reply<bool>,
+    },
+}
+
+#[derive(Default)]
+struct Actor {
+    players: Players,
+    pending: BTreeMap<String, Pending>,
+}
+
+impl Actor {
+    fn handle(&mut self, command: PlayerCommand) {
+        match command {
+            PlayerCommand::Launch { app, id, max_players, setup, request, started, done, reply } => {
+                if let Some(active) = self.players.get(&id) {
+                    let _ = reply.send(load(&app, &id, active.as_ref(), &request, started, false));
+                } else if let Some(pending) = self.pending.get_mut(&id) {
+                    // Launching again supersedes a close sent during init.
+                    pending.close = None;
+                    pending.waiting.push(Waiter::Launch { app, request, started, reply });
+                } else if self.players.len() + self.pending.len() >= max_players {
+                    let _ = reply.send(Err(FlashplexError::PlayerLimit { max: max_players }));
+                } else {
+                    let waiter = Waiter::Launch { app: app.clone(), request, started, reply };
+                    self.start_init(app, id, setup, false, done, waiter);
+                }
+            }
+            PlayerCommand::Warm { app, setup, done, reply } => {
+                if self.players.contains_key(MAIN_PLAYER_ID) || self.pending.contains_key(MAIN_PLAYER_ID) {
+                    let _ = reply.send(Ok(false));
+                } else {
+                    self.start_init(app, MAIN_PLAYER_ID.to_string(), setup, true, done, Waiter::Warm { reply });
+                }
+            }
+            PlayerCommand::Created { id, created } => self.created(id, created),
+            PlayerCommand::Close { id, keep_alive, reply } => {
+                let result = match self.pending.get_mut(&id) {
+                    Some(pending) => {
+                        pending.close = Some(keep_alive);
+                        Ok(())
+                    }
+                    None => self.close(&id, keep_alive),
+                };
+                let _ = reply.send(result);
+            }
+            PlayerCommand::Call { id, call, .. } => call(self.players.get(&id).map(|p| p.as_ref())),
+            PlayerCommand::List { reply } => {
+                let _ = reply.send(Ok(self.players.keys().cloned().collect()));
+            }
+        }
+    }
+
+    fn start_init(&mut self, app: tauri::AppHandle, id: String, setup: Setup, park: bool, done: Sender<PlayerCommand>, waiter: Waiter) {
+        let thread_id = id.clone();
+        let spawned = std::thread::Builder::new()
+            .name(format!("player-init-{}", id))
+            .spawn(move || {
+                let created = panic::catch_unwind(AssertUnwindSafe(|| init(&app, &thread_id, setup, park)))
+                    .unwrap_or_else(|_| Err(FlashplexError::internal(format!("player '{}' init panicked", thread_id))));
+                let _ = done.send(PlayerCommand::Created { id: thread_id, created });
+            });
+        match spawned {
+            Ok(_) => {
+                self.pending.insert(id, Pending { waiting: vec![waiter], close: None });
+            }
+            Err(e) => waiter.fail(FlashplexError::internal(format!("player init thread not started: {}", e))),
+        }
+    }
+
+    fn created(&mut self, id: String, created: FlashplexResult<Box<dyn PlayerBackend>>) {
+        let Some(pending) = self.pending.remove(&id) else {
+            return;
+        };
+        let player = match created {
+            Ok(player) => self.players.entry(id.clone()).or_insert(player),
+            Err(e) => {
+                for waiter in pending.waiting {
+                    waiter.fail(e.clone());
+                }
+                return;
+            }
+        };
+        for waiter in pending.waiting {
+            match waiter {
+                Waiter::Warm { reply } => {
+                    let _ = reply.send(Ok(true));
+                }
+                // Closed before it was ready: nothing to load.
+                Waiter::Launch { reply, .. } if pending.close.is_some() => {
+                    let _ = reply.send(Ok(()));
+                }
+                Waiter::Launch { app, request, started, reply } => {
+                    let _ = reply.send(load(&app, &id, player.as_ref(), &request, started, true));
+                }
+            }
+        }
+        if let Some(keep_alive) = pending.close {
+            if let Err(e) = self.close(&id, keep_alive) {
+                log_warn!("PLAYER", "Player '{}' not closed after init: {}", id, e);
+            }
+        }
+    }
+
+    fn close(&mut self, id: &str, keep_alive: bool) -> FlashplexResult<()> {
+        match self.players.get(id) {
+            Some(active) if keep_alive && id == MAIN_PLAYER_ID => active.park(),
+            Some(_) => {
+                log_info!("PLAYER", "Closing player '{}'", id);
+                if let Some(active) = self.players.remove(id) {
+                    active.close();
+                }
+                Ok(())
+            }
+            None => Ok(()),
+        }
+    }
+}
+
+impl Waiter {
+    fn fail(self, error: FlashplexError) {
+        match self {
+            Waiter::Launch { reply, .. } => {
+                let _ = reply.send(Err(error));
+            }
+            Waiter::Warm { reply } => {
+                let _ = reply.send(Err(error));
+            }
+        }
+    }
+}
+
+/// Runs on the init thread; `park` hides the new player until its first load.
+fn init(app: &tauri::AppHandle, id: &str, setup: Setup, park: bool) -> FlashplexResult<Box<dyn PlayerBackend>> {
+    log_debug!("PLAYER", "Initializing MPV for player '{}'...", id);
+    let started = Instant::now();
+    let created = create_backend(app, id)?;
+    setup(created.as_ref());
+    if park {
+        // Idle already; parking just hides the surface until the first load.
+        if let Err(e) = created.park() {
+            log_warn!("PLAYER", "Pre-warmed player not hidden: {}", e);
+        }
+        log_info!("PLAYER", "Player '{}' pre-warmed in {} ms", id, started.elapsed().as_millis());
+    }
+    Ok(created)
+}
+
+fn load(
+    app: &tauri::AppHandle,
+    id: &str,
+    player: &dyn PlayerBackend,
+    request: &LoadRequest,
+    started: Instant,
+    cold: bool,
+) -> FlashplexResult<()> {
+    first_frame_pending(app, id, &request.title, started, cold);
+    let loaded = player.load(request);
+    if loaded.is_err() {
+        first_frame_cancelled(app, id);
+    }
+    loaded
+}
@@ -0,0 +1,330 @@
+// Player diagnostics for bug reports.
+//
+// libmpv's own log (requested via `mpv_request_log_messages` on the event client)
+// is forwarded into the app log and the last lines are kept here, so a failed
+// gpu-next/metal init can be read back after the fact without a terminal.
+// `player_diagnostics` bundles that tail with the render path the player was
+// created with, recent start-to-first-frame times and the live mpv state into one
+// document for bug reports.
+use super::PlayerState;
+use crate::error::FlashplexResult;
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+
+const MPV_LOG_CAPACITY: usize = 500;
+const FIRST_FRAME_CAPACITY: usize = 20;
+pub const FIRST_FRAME_EVENT: &str = "player-first-frame";
+/// mpv log levels accepted by the `mpv_log_level` setting, most to least severe.
+pub const MPV_LOG_LEVELS: [&str; 8] = ["no", "fatal", "error", "warn", "info", "v", "debug", "trace"];
+
+pub fn normalize_mpv_log_level(level: &str) -> &'static str {
+    let level = level.trim().to_ascii_lowercase();
+    MPV_LOG_LEVELS.iter().copied().find(|l| *l == level).unwrap_or("info")
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct MpvLogLine {
+    pub time: String,
+    /// mpv's level name ("error", "warn", "v", ...).
+    pub level: String,
+    /// mpv module, e.g. "vo/gpu-next" or "cplayer".
+    pub prefix: String,
+    pub text: String,
+}
+
+#[derive(Default)]
+pub struct MpvLogBuffer {
+    lines: VecDeque<MpvLogLine>,
+    /// Level requested from the current (or last) player.
+    level: String,
+}
+
+impl MpvLogBuffer {
+    pub fn push(&mut self, line: MpvLogLine) {
+        if self.lines.len() >= MPV_LOG_CAPACITY {
+            self.lines.pop_front();
+        }
+        self.lines.push_back(line);
+    }
+
+    pub fn set_level(&mut self, level: &str) {
+        self.level = level.to_string();
+    }
+
+    pub fn tail(&self, limit: usize) -> Vec<MpvLogLine> {
+        let skip = self.lines.len().saturating_sub(limit);
+        self.lines.iter().skip(skip).cloned().collect()
+    }
+}
+
+/// Outlives individual players so the log of a crashed/closed one stays readable.
+pub struct PlayerLogState(pub Arc<Mutex<MpvLogBuffer>>);
+
+impl PlayerLogState {
+    pub fn new() -> Self {
+        PlayerLogState(Arc::new(Mutex::new(MpvLogBuffer::default())))
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct MpvLogReport {
+    pub level: String,
+    pub capacity: usize,
+    pub lines: Vec<MpvLogLine>,
+}
+
+/// One render profile tried while creating the player.
+#[derive(Debug, Clone, Serialize)]
+pub struct InitAttempt {
+    /// Render policy candidate, e.g. "layer-metal" or "linux-window".
+    pub profile: String,
+    /// "NSView" / "Layer" on macOS, None where mpv owns its window.
+    pub wid_kind: Option<String>,
+    /// Why the profile was rejected; None for the one that initialized.
+    pub error: Option<String>,
+}
+
+impl InitAttempt {
+    pub fn new(profile: &str, wid_kind: Option<&str>, error: Option<String>) -> Self {
+        Self {
+            profile: profile.to_string(),
+            wid_kind: wid_kind.map(str::to_string),
+            error,
+        }
+    }
+}
+
+/// How the last player was created, kept after it closes (or failed to start).
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct RenderInit {
+    pub time: String,
+    /// Profile that initialized; None when every attempt failed.
+    pub render_path: Option<String>,
+    pub wid_kind: Option<String>,
+    /// In the order they were tried, including the selected one.
+    pub attempts: Vec<InitAttempt>,
+    /// Platform specifics: policy source, VK_ICD_FILENAMES, the MoltenVK library.
+    pub environment: BTreeMap<String, Option<String>>,
+}
+
+impl RenderInit {
+    pub fn new(attempts: Vec<InitAttempt>, environment: BTreeMap<String, Option<String>>) -> Self {
+        let selected = attempts.iter().find(|a| a.error.is_none());
+        Self {
+            time: chrono::Local::now().to_rfc3339(),
+            render_path: selected.map(|a| a.profile.clone()),
+            wid_kind: selected.and_then(|a| a.wid_kind.clone()),
+            attempts,
+            environment,
+        }
+    }
+}
+
+pub struct RenderInitState(pub Arc<Mutex<Option<RenderInit>>>);
+
+impl RenderInitState {
+    pub fn new() -> Self {
+        RenderInitState(Arc::new(Mutex::new(None)))
+    }
+}
+
+/// Stores how the player was created; called by the platform `create` on success and failure.
+pub fn record_render_init(app: &tauri::AppHandle, init: RenderInit) {
+    use tauri::Manager;
+    match &init.render_path {
+        Some(path) => log_info!("INVOKE", "Render path: {} ({} attempt(s))", path, init.attempts.len()),
+        None => log_error!("INVOKE", "No render path initialized ({} attempt(s))", init.attempts.len()),
+    }
+    if let Some(state) = app.try_state::<RenderInitState>() {
+        if let Ok(mut last) = state.0.lock() {
+            *last = Some(init);
+        }
+    }
+}
+
+/// Time from a `launch_mpv_player` call to the first frame of that file.
+#[derive(Debug, Clone, Serialize)]
+pub struct FirstFrameTiming {
+    pub time: String,
+    pub player_id: String,
+    pub title: String,
+    pub ms: u64,
+    /// The player had to be created for this launch (not pre-warmed or kept alive).
+    pub cold: bool,
+}
+
+struct PendingStart {
+    started: Instant,
+    title: String,
+    cold: bool,
+}
+
+#[derive(Default)]
+pub struct FirstFrameTracker {
+    // Per player id: the launch still waiting for its first frame.
+    pending: HashMap<String, PendingStart>,
+    recent: VecDeque<FirstFrameTiming>,
+}
+
+impl FirstFrameTracker {
+    pub fn recent(&self) -> Vec<FirstFrameTiming> {
+        self.recent.iter().cloned().collect()
+    }
+}
+
+pub struct FirstFrameState(pub Arc<Mutex<FirstFrameTracker>>);
+
+impl FirstFrameState {
+    pub fn new() -> Self {
+        FirstFrameState(Arc::new(Mutex::new(FirstFrameTracker::default())))
+    }
+}
+
+fn with_first_frame<R>(app: &tauri::AppHandle, f: impl FnOnce(&mut FirstFrameTracker) -> R) -> Option<R> {
+    use tauri::Manager;
+    let state = app.try_state::<FirstFrameState>()?;
+    let mut tracker = state.0.lock().ok()?;
+    Some(f(&mut tracker))
+}
+
+/// Starts timing a load on `player_id`; called right before the file is handed to mpv.
+pub fn first_frame_pending(app: &tauri::AppHandle, player_id: &str, title: &str, started: Instant, cold: bool) {
+    with_first_frame(app, |tracker| {
+        let pending = PendingStart {
+            started,
+            title: title.to_string(),
+            cold,
+        };
+        tracker.pending.insert(player_id.to_string(), pending);
+    });
+}
+
+/// The load on `player_id` failed before showing anything.
+pub fn first_frame_cancelled(app: &tauri::AppHandle, player_id: &str) {
+    with_first_frame(app, |tracker| tracker.pending.remove(player_id));
+}
+
+/// mpv (re)started playback on `player_id`; completes a pending timing and emits
+/// `player-first-frame`. Later restarts (seeks) find nothing pending.
+pub fn first_frame_presented(app: &tauri::AppHandle, player_id: &str) {
+    use tauri::Emitter;
+    let timing = with_first_frame(app, |tracker| {
+        let pending = tracker.pending.remove(player_id)?;
+        let timing = FirstFrameTiming {
+            time: chrono::Local::now().to_rfc3339(),
+            player_id: player_id.to_string(),
+            title: pending.title,
+            ms: pending.started.elapsed().as_millis() as u64,
+            cold: pending.cold,
+        };
+        if tracker.recent.len() >= FIRST_FRAME_CAPACITY {
+            tracker.recent.pop_front();
+        }
+        tracker.recent.push_back(timing.clone());
+        Some(timing)
+    })
+    .flatten();
+    if let Some(timing) = timing {
+        log_info!(
+            "PLAYER",
+            "First frame on '{}' after {} ms ({})",
+            timing.player_id,
+            timing.ms,
+            if timing.cold { "cold start" } else { "warm player" }
+        );
+        let _ = app.emit(FIRST_FRAME_EVENT, timing);
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct CacheDiagnostics {
+    pub paused_for_cache: Option<bool>,
+    /// 0-100 while mpv is buffering.
+    pub buffering_percent: Option<i64>,
+    /// Seconds of media buffered ahead of the playback position.
+    pub duration: Option<f64>,
+    pub speed_bytes: Option<i64>,
+}
+
+/// Live mpv state; fields mpv can't answer (nothing loaded, property missing) are None.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct PlaybackDiagnostics {
+    pub mpv_version: Option<String>,
+    pub ffmpeg_version: Option<String>,
+    pub current_vo: Option<String>,
+    pub gpu_api: Option<String>,
+    pub gpu_context: Option<String>,
+    pub hwdec: Option<String>,
+    pub hwdec_current: Option<String>,
+    pub video_codec: Option<String>,
+    pub video_format: Option<String>,
+    pub pixel_format: Option<String>,
+    pub width: Option<i64>,
+    pub height: Option<i64>,
+    pub fps: Option<f64>,
+    pub frame_drops: Option<i64>,
+    pub decoder_frame_drops: Option<i64>,
+    pub cache: CacheDiagnostics,
+    pub window_scale: Option<f64>,
+    pub osd_width: Option<i64>,
+    pub osd_height: Option<i64>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PlayerDiagnosticsReport {
+    pub generated_at: String,
+    pub app_version: String,
+    pub os: &'static str,
+    pub arch: &'static str,
+    pub player_active: bool,
+    pub render: Option<RenderInit>,
+    /// Most recent last.
+    pub first_frame: Vec<FirstFrameTiming>,
+    pub playback: Option<PlaybackDiagnostics>,
+    pub mpv_log: MpvLogReport,
+}
+
+// Commands
+
+/// Last `limit` (default: all buffered) libmpv log lines.
+#[tauri::command(rename_all = "snake_case")]
+pub fn get_player_diagnostics(
+    state: tauri::State<'_, PlayerLogState>,
+    limit: Option<usize>,
+) -> FlashplexResult<MpvLogReport> {
+    let buffer = state.0.lock()?;
+    Ok(MpvLogReport {
+        level: buffer.level.clone(),
+        capacity: MPV_LOG_CAPACITY,
+        lines: buffer.tail(limit.unwrap_or(MPV_LOG_CAPACITY)),
+    })
+}
+
+/// Everything a bug report needs in one JSON document; works without an open player.
+#[tauri::command(rename_all = "snake_case")]
+pub async fn player_diagnostics(
+    app: tauri::AppHandle,
+    player: tauri::State<'_, PlayerState>,
+    logs: tauri::State<'_, PlayerLogState>,
+    render: tauri::State<'_, RenderInitState>,
+    first_frame: tauri::State<'_, FirstFrameState>,
+    log_limit: Option<usize>,
+    player_id: Option<String>,
+) -> FlashplexResult<PlayerDiagnosticsReport> {
+    use tauri::Manager;
+    let playback = player.query(player_id, "player_diagnostics", |player| player.map(|p| p.diagnostics())).await?;
+    let mpv_log = get_player_diagnostics(logs, Some(log_limit.unwrap_or(100)))?;
+    Ok(PlayerDiagnosticsReport {
+        generated_at: chrono::Local::now().to_rfc3339(),
+        app_version: app.package_info().version.to_string(),
+        os: std::env::consts::OS,
+        arch: std::env::consts::ARCH,
+        player_active: playback.is_some(),
+        render: render.0.lock()?.clone(),
+        first_frame: first_frame.0.lock()?.recent(),
+        playback,
+        mpv_log,
+    })
+}
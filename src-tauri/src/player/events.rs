@@ -0,0 +1,465 @@
+// Push-based mpv event stream.
+//
+// A dedicated mpv client handle is created next to the player's own handle so the
+// event thread never needs the player state lock. Property changes and playback
+// events are forwarded to the webview as typed Tauri events, tagged with the id of
+// the player they came from; libmpv's log messages go to the app log and the
+// diagnostics buffer.
+use super::diagnostics::{
+    first_frame_cancelled, first_frame_presented, normalize_mpv_log_level, MpvLogLine, PlayerLogState,
+};
+use super::queue::{QueueItem, SharedQueue};
+use crate::credentials::redact;
+use crate::error::{FlashplexError, FlashplexResult};
+use crate::gds::MediaRef;
+use crate::history::{HistoryState, HistoryStore};
+use crate::logging::{self, Level};
+use crate::settings::SettingsState;
+use libmpv2::Mpv;
+use libmpv2_sys as sys;
+use serde::Serialize;
+use std::ffi::{CStr, CString};
+use std::os::raw::{c_char, c_int, c_void};
+use tauri::{Emitter, Manager};
+
+// reply_userdata ids for observed properties
+const OBS_TIME_POS: u64 = 1;
+const OBS_PAUSE: u64 = 2;
+const OBS_DURATION: u64 = 3;
+const OBS_TRACK_LIST: u64 = 4;
+const OBS_PAUSED_FOR_CACHE: u64 = 5;
+const OBS_CACHE_BUFFERING: u64 = 6;
+const OBS_CACHE_DURATION: u64 = 7;
+const OBS_PLAYLIST_POS: u64 = 8;
+const OBS_PLAYLIST_COUNT: u64 = 9;
+
+// time-pos fires every frame; the UI only needs a few updates per second.
+const TIME_POS_MIN_DELTA: f64 = 0.25;
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "type", rename_all = "kebab-case")]
+pub enum PlayerEvent {
+    TimePos { position: f64 },
+    Pause { paused: bool },
+    Duration { duration: f64 },
+    TrackListChanged,
+    EndFile { reason: String, error: Option<String> },
+    FileLoaded,
+    Seek,
+    PlaybackRestart,
+    Buffering {
+        paused_for_cache: bool,
+        buffering_percent: i64,
+        cache_duration: f64,
+    },
+    Playlist { pos: i64, count: i64 },
+    /// `script-message` / `script-message-to flashplex-events` from an mpv script
+    /// (e.g. an OSC button): first argument as `name`, the rest as `args`.
+    ScriptMessage { name: String, args: Vec<String> },
+}
+
+impl PlayerEvent {
+    pub fn event_name(&self) -> &'static str {
+        match self {
+            PlayerEvent::TimePos { .. } => "mpv-time-pos",
+            PlayerEvent::Pause { .. } => "mpv-pause",
+            PlayerEvent::Duration { .. } => "mpv-duration",
+            PlayerEvent::TrackListChanged => "mpv-track-list",
+            PlayerEvent::EndFile { .. } => "mpv-end-file",
+            PlayerEvent::FileLoaded => "mpv-file-loaded",
+            PlayerEvent::Seek => "mpv-seek",
+            PlayerEvent::PlaybackRestart => "mpv-playback-restart",
+            PlayerEvent::Buffering { .. } => "mpv-buffering",
+            PlayerEvent::Playlist { .. } => "mpv-playlist",
+            PlayerEvent::ScriptMessage { .. } => "mpv-script-message",
+        }
+    }
+}
+
+// Event payload: the PlayerEvent fields plus `player_id`.
+#[derive(Clone, Serialize)]
+struct TaggedEvent<'a> {
+    player_id: &'a str,
+    #[serde(flatten)]
+    event: &'a PlayerEvent,
+}
+
+struct EventHandle(*mut sys::mpv_handle);
+
+// mpv client handles may be used from any thread; this one is only touched by the event thread.
+unsafe impl Send for EventHandle {}
+
+#[derive(Default)]
+struct BufferingState {
+    paused_for_cache: bool,
+    buffering_percent: i64,
+    cache_duration: f64,
+}
+
+impl BufferingState {
+    fn to_event(&self) -> PlayerEvent {
+        PlayerEvent::Buffering {
+            paused_for_cache: self.paused_for_cache,
+            buffering_percent: self.buffering_percent,
+            cache_duration: self.cache_duration,
+        }
+    }
+}
+
+#[derive(Default)]
+struct PlaylistState {
+    pos: i64,
+    count: i64,
+}
+
+impl PlaylistState {
+    fn to_event(&self) -> PlayerEvent {
+        PlayerEvent::Playlist { pos: self.pos, count: self.count }
+    }
+}
+
+// The file progress is attributed to in the watch history (see history.rs).
+#[derive(Default)]
+struct NowPlaying {
+    media: Option<MediaRef>,
+    title: String,
+    duration: f64,
+}
+
+impl NowPlaying {
+    fn start(&mut self, item: Option<QueueItem>) {
+        *self = NowPlaying::default();
+        if let Some(item) = item {
+            if let Some(path) = item.path.filter(|p| !p.is_empty()) {
+                self.media = Some(MediaRef { path, source_id: item.source_id.unwrap_or(0) });
+                self.title = item.title;
+            }
+        }
+    }
+
+    fn record(&self, app: &tauri::AppHandle, position: f64) {
+        if let Some(ref media) = self.media {
+            with_history(app, |store| {
+                store.record_progress(media, &self.title, position, self.duration);
+                store.flush_if_due();
+            });
+        }
+    }
+
+    fn flush(&self, app: &tauri::AppHandle) {
+        if self.media.is_some() {
+            with_history(app, |store| store.flush_logged());
+        }
+    }
+
+    fn finish(&mut self, app: &tauri::AppHandle, eof: bool) {
+        if let Some(media) = self.media.take() {
+            with_history(app, |store| {
+                if eof {
+                    store.set_watched(&media, true);
+                }
+                store.flush_logged();
+            });
+        }
+    }
+}
+
+fn with_history(app: &tauri::AppHandle, f: impl FnOnce(&mut HistoryStore)) {
+    if let Some(history) = app.try_state::<HistoryState>() {
+        if let Ok(mut store) = history.0.lock() {
+            f(&mut store);
+        }
+    }
+}
+
+/// Starts the event thread for `mpv`. It exits by itself on MPV_EVENT_SHUTDOWN (player `quit`).
+pub fn spawn_event_thread(app: tauri::AppHandle, player_id: &str, mpv: &Mpv, queue: SharedQueue) -> FlashplexResult<()> {
+    let name = CString::new("flashplex-events").unwrap();
+    let handle = unsafe { sys::mpv_create_client(mpv.ctx.as_ptr(), name.as_ptr()) };
+    if handle.is_null() {
+        return Err(FlashplexError::MpvInit {
+            profile: "event-client".to_string(),
+            code: None,
+            message: "mpv_create_client returned null".to_string(),
+        });
+    }
+
+    let observed: [(u64, &str, sys::mpv_format); 9] = [
+        (OBS_TIME_POS, "time-pos", sys::mpv_format_MPV_FORMAT_DOUBLE),
+        (OBS_PAUSE, "pause", sys::mpv_format_MPV_FORMAT_FLAG),
+        (OBS_DURATION, "duration", sys::mpv_format_MPV_FORMAT_DOUBLE),
+        (OBS_TRACK_LIST, "track-list", sys::mpv_format_MPV_FORMAT_NONE),
+        (OBS_PAUSED_FOR_CACHE, "paused-for-cache", sys::mpv_format_MPV_FORMAT_FLAG),
+        (OBS_CACHE_BUFFERING, "cache-buffering-state", sys::mpv_format_MPV_FORMAT_INT64),
+        (OBS_CACHE_DURATION, "demuxer-cache-duration", sys::mpv_format_MPV_FORMAT_DOUBLE),
+        (OBS_PLAYLIST_POS, "playlist-pos", sys::mpv_format_MPV_FORMAT_INT64),
+        (OBS_PLAYLIST_COUNT, "playlist-count", sys::mpv_format_MPV_FORMAT_INT64),
+    ];
+    for (id, prop, format) in observed {
+        let c_prop = CString::new(prop).unwrap();
+        let rc = unsafe { sys::mpv_observe_property(handle, id, c_prop.as_ptr(), format) };
+        if rc < 0 {
+            log_warn!("EVENTS", "observe_property({}) failed: {}", prop, rc);
+        }
+    }
+
+    let log_level = app
+        .try_state::<SettingsState>()
+        .and_then(|s| s.snapshot().ok())
+        .map(|s| normalize_mpv_log_level(&s.mpv_log_level))
+        .unwrap_or("info");
+    let c_level = CString::new(log_level).unwrap();
+    let rc = unsafe { sys::mpv_request_log_messages(handle, c_level.as_ptr()) };
+    if rc < 0 {
+        log_warn!("EVENTS", "request_log_messages({}) failed: {}", log_level, mpv_error_string(rc));
+    } else if let Some(logs) = app.try_state::<PlayerLogState>() {
+        if let Ok(mut buffer) = logs.0.lock() {
+            buffer.set_level(log_level);
+        }
+    }
+
+    let handle = EventHandle(handle);
+    let player_id = player_id.to_string();
+    std::thread::Builder::new()
+        .name(format!("mpv-events-{}", player_id))
+        .spawn(move || {
+            let handle = handle;
+            run_event_loop(&app, &player_id, handle.0, &queue);
+            unsafe { sys::mpv_destroy(handle.0) };
+            log_info!("EVENTS", "Event thread of player '{}' stopped", player_id);
+        })
+        .map_err(|e| FlashplexError::internal(format!("Failed to spawn mpv event thread: {}", e)))?;
+    Ok(())
+}
+
+fn run_event_loop(app: &tauri::AppHandle, player_id: &str, handle: *mut sys::mpv_handle, queue: &SharedQueue) {
+    let mut last_time_pos = f64::NEG_INFINITY;
+    let mut buffering = BufferingState::default();
+    let mut playlist = PlaylistState::default();
+    let mut now_playing = NowPlaying::default();
+
+    loop {
+        let event = unsafe { &*sys::mpv_wait_event(handle, -1.0) };
+        let out = match event.event_id {
+            sys::mpv_event_id_MPV_EVENT_SHUTDOWN => {
+                now_playing.finish(app, false);
+                break;
+            }
+            sys::mpv_event_id_MPV_EVENT_PROPERTY_CHANGE => {
+                let prop = unsafe { &*(event.data as *const sys::mpv_event_property) };
+                property_event(event.reply_userdata, prop, &mut last_time_pos, &mut buffering, &mut playlist)
+            }
+            sys::mpv_event_id_MPV_EVENT_END_FILE => {
+                let end = unsafe { &*(event.data as *const sys::mpv_event_end_file) };
+                Some(PlayerEvent::EndFile {
+                    reason: end_file_reason(end.reason).to_string(),
+                    error: if end.error < 0 { Some(mpv_error_string(end.error)) } else { None },
+                })
+            }
+            sys::mpv_event_id_MPV_EVENT_FILE_LOADED => {
+                now_playing.start(on_file_loaded(handle, queue));
+                Some(PlayerEvent::FileLoaded)
+            }
+            sys::mpv_event_id_MPV_EVENT_SEEK => {
+                // Always let the next time-pos through after a seek.
+                last_time_pos = f64::NEG_INFINITY;
+                Some(PlayerEvent::Seek)
+            }
+            sys::mpv_event_id_MPV_EVENT_PLAYBACK_RESTART => Some(PlayerEvent::PlaybackRestart),
+            sys::mpv_event_id_MPV_EVENT_LOG_MESSAGE => {
+                let msg = unsafe { &*(event.data as *const sys::mpv_event_log_message) };
+                on_log_message(app, msg);
+                None
+            }
+            sys::mpv_event_id_MPV_EVENT_CLIENT_MESSAGE => {
+                let msg = unsafe { &*(event.data as *const sys::mpv_event_client_message) };
+                script_message(msg)
+            }
+            _ => None,
+        };
+
+        match out {
+            Some(PlayerEvent::TimePos { position }) => now_playing.record(app, position),
+            Some(PlayerEvent::Duration { duration }) => now_playing.duration = duration,
+            Some(PlayerEvent::Pause { paused: true }) => now_playing.flush(app),
+            Some(PlayerEvent::EndFile { ref reason, .. }) => {
+                if reason == "error" {
+                    first_frame_cancelled(app, player_id);
+                }
+                now_playing.finish(app, reason == "eof")
+            }
+            Some(PlayerEvent::PlaybackRestart) => first_frame_presented(app, player_id),
+            _ => {}
+        }
+
+        if let Some(ev) = out {
+            let _ = app.emit(ev.event_name(), TaggedEvent { player_id, event: &ev });
+        }
+    }
+}
+
+fn property_event(
+    id: u64,
+    prop: &sys::mpv_event_property,
+    last_time_pos: &mut f64,
+    buffering: &mut BufferingState,
+    playlist: &mut PlaylistState,
+) -> Option<PlayerEvent> {
+    // Unavailable properties (e.g. duration before load) arrive with format NONE.
+    let has_data = prop.format != sys::mpv_format_MPV_FORMAT_NONE && !prop.data.is_null();
+    match id {
+        OBS_TIME_POS if has_data => {
+            let position = unsafe { read_double(prop.data) };
+            if (position - *last_time_pos).abs() < TIME_POS_MIN_DELTA {
+                return None;
+            }
+            *last_time_pos = position;
+            Some(PlayerEvent::TimePos { position })
+        }
+        OBS_PAUSE if has_data => Some(PlayerEvent::Pause { paused: unsafe { read_flag(prop.data) } }),
+        OBS_DURATION => Some(PlayerEvent::Duration {
+            duration: if has_data { unsafe { read_double(prop.data) } } else { 0.0 },
+        }),
+        OBS_TRACK_LIST => Some(PlayerEvent::TrackListChanged),
+        OBS_PAUSED_FOR_CACHE => {
+            buffering.paused_for_cache = has_data && unsafe { read_flag(prop.data) };
+            Some(buffering.to_event())
+        }
+        OBS_CACHE_BUFFERING => {
+            buffering.buffering_percent = if has_data { unsafe { read_int64(prop.data) } } else { 0 };
+            Some(buffering.to_event())
+        }
+        OBS_CACHE_DURATION => {
+            buffering.cache_duration = if has_data { unsafe { read_double(prop.data) } } else { 0.0 };
+            Some(buffering.to_event())
+        }
+        OBS_PLAYLIST_POS => {
+            playlist.pos = if has_data { unsafe { read_int64(prop.data) } } else { -1 };
+            Some(playlist.to_event())
+        }
+        OBS_PLAYLIST_COUNT => {
+            playlist.count = if has_data { unsafe { read_int64(prop.data) } } else { 0 };
+            Some(playlist.to_event())
+        }
+        _ => None,
+    }
+}
+
+// Looks up the queue entry of the file that just loaded and adds its external
+// subtitle (see queue.rs).
+fn on_file_loaded(handle: *mut sys::mpv_handle, queue: &SharedQueue) -> Option<QueueItem> {
+    let path = get_string_property(handle, "path")?;
+    let (item, subtitle) = match queue.lock() {
+        Ok(meta) => (meta.items.get(&path).cloned(), meta.subtitle_for(&path)),
+        Err(_) => (None, None),
+    };
+    if let Some(sub) = subtitle {
+        let rc = client_command(handle, &["sub-add", &sub, "select"]);
+        if rc < 0 {
+            log_warn!("EVENTS", "sub-add failed: {}", mpv_error_string(rc));
+        } else {
+            log_info!("LIB", "Added primary subtitle: {}", redact(&sub));
+        }
+    }
+    item
+}
+
+fn on_log_message(app: &tauri::AppHandle, msg: &sys::mpv_event_log_message) {
+    let text = unsafe { c_str(msg.text) };
+    let text = text.trim_end();
+    if text.is_empty() {
+        return;
+    }
+    let prefix = unsafe { c_str(msg.prefix) };
+    let level = match msg.log_level {
+        sys::mpv_log_level_MPV_LOG_LEVEL_FATAL | sys::mpv_log_level_MPV_LOG_LEVEL_ERROR => Level::Error,
+        sys::mpv_log_level_MPV_LOG_LEVEL_WARN => Level::Warn,
+        sys::mpv_log_level_MPV_LOG_LEVEL_INFO => Level::Info,
+        _ => Level::Debug,
+    };
+    logging::write(level, "MPV", format_args!("[{}] {}", prefix, text));
+    if let Some(logs) = app.try_state::<PlayerLogState>() {
+        if let Ok(mut buffer) = logs.0.lock() {
+            buffer.push(MpvLogLine {
+                time: chrono::Local::now().format("%H:%M:%S%.3f").to_string(),
+                level: unsafe { c_str(msg.level) },
+                prefix,
+                text: text.to_string(),
+            });
+        }
+    }
+}
+
+fn script_message(msg: &sys::mpv_event_client_message) -> Option<PlayerEvent> {
+    let count = usize::try_from(msg.num_args).unwrap_or(0);
+    if count == 0 || msg.args.is_null() {
+        return None;
+    }
+    let mut args: Vec<String> = (0..count).map(|i| unsafe { c_str(*msg.args.add(i)) }).collect();
+    let name = args.remove(0);
+    Some(PlayerEvent::ScriptMessage { name, args })
+}
+
+unsafe fn c_str(ptr: *const c_char) -> String {
+    if ptr.is_null() {
+        String::new()
+    } else {
+        CStr::from_ptr(ptr).to_string_lossy().into_owned()
+    }
+}
+
+fn get_string_property(handle: *mut sys::mpv_handle, name: &str) -> Option<String> {
+    let c_name = CString::new(name).ok()?;
+    unsafe {
+        let ptr = sys::mpv_get_property_string(handle, c_name.as_ptr());
+        if ptr.is_null() {
+            return None;
+        }
+        let value = CStr::from_ptr(ptr).to_string_lossy().into_owned();
+        sys::mpv_free(ptr as *mut c_void);
+        Some(value)
+    }
+}
+
+fn client_command(handle: *mut sys::mpv_handle, args: &[&str]) -> c_int {
+    let c_args: Vec<CString> = match args.iter().map(|a| CString::new(*a)).collect() {
+        Ok(v) => v,
+        Err(_) => return -1,
+    };
+    let mut ptrs: Vec<*const c_char> = c_args.iter().map(|a| a.as_ptr()).collect();
+    ptrs.push(std::ptr::null());
+    unsafe { sys::mpv_command(handle, ptrs.as_mut_ptr()) }
+}
+
+unsafe fn read_double(data: *mut c_void) -> f64 {
+    *(data as *const f64)
+}
+
+unsafe fn read_flag(data: *mut c_void) -> bool {
+    *(data as *const c_int) != 0
+}
+
+unsafe fn read_int64(data: *mut c_void) -> i64 {
+    *(data as *const i64)
+}
+
+fn end_file_reason(reason: sys::mpv_end_file_reason) -> &'static str {
+    match reason {
+        sys::mpv_end_file_reason_MPV_END_FILE_REASON_EOF => "eof",
+        sys::mpv_end_file_reason_MPV_END_FILE_REASON_STOP => "stop",
+        sys::mpv_end_file_reason_MPV_END_FILE_REASON_QUIT => "quit",
+        sys::mpv_end_file_reason_MPV_END_FILE_REASON_ERROR => "error",
+        sys::mpv_end_file_reason_MPV_END_FILE_REASON_REDIRECT => "redirect",
+        _ => "unknown",
+    }
+}
+
+fn mpv_error_string(code: c_int) -> String {
+    unsafe {
+        let ptr = sys::mpv_error_string(code);
+        if ptr.is_null() {
+            return code.to_string();
+        }
+        CStr::from_ptr(ptr).to_string_lossy().into_owned()
+    }
+}
@@ -0,0 +1,52 @@
+// Linux player: libmpv in its own top-level window.
+//
+// The vo/hwdec come from the render policy (see render_policy.rs).
+//
+// Set FLASHPLEX_HEADLESS=1 to run without video/audio output (CI, remote dev boxes);
+// commands and events still work because playback is driven by mpv's core.
+use super::mpv::{init_with_policy, MpvHost, MpvPlayer};
+use super::render_policy::{self, PolicySource, RenderPathPolicy, WidKind};
+use crate::error::{FlashplexError, FlashplexResult};
+use libmpv2::Mpv;
+
+pub struct LinuxHost;
+
+impl MpvHost for LinuxHost {
+    fn refresh_layout(&self, _app: &tauri::AppHandle, _mpv: &Mpv) -> FlashplexResult<()> {
+        // mpv owns its window here and follows its own size.
+        Ok(())
+    }
+
+    fn set_visible(&self, _app: &tauri::AppHandle, _visible: bool) {
+        // Without force-window, mpv closes its window once playback stops and
+        // reopens it for the next file.
+    }
+
+    fn destroy(self) {}
+}
+
+fn headless() -> bool {
+    matches!(
+        std::env::var("FLASHPLEX_HEADLESS").as_deref(),
+        Ok("1") | Ok("true") | Ok("yes")
+    )
+}
+
+pub fn create(app: &tauri::AppHandle, player_id: &str) -> FlashplexResult<MpvPlayer<LinuxHost>> {
+    let (policy, source) = if headless() {
+        (RenderPathPolicy::headless(), PolicySource::Environment)
+    } else {
+        render_policy::load(app)
+    };
+    let environment = [("FLASHPLEX_HEADLESS".to_string(), std::env::var("FLASHPLEX_HEADLESS").ok())]
+        .into_iter()
+        .collect();
+    // mpv opens its own window here; there is no view to embed into.
+    let (mpv, profile) = init_with_policy(app, &policy, source, |kind| match kind {
+        WidKind::Window => Ok(None),
+        _ => Err(FlashplexError::unsupported(&format!("{} wid", kind.label()))),
+    }, environment)?;
+
+    log_info!("INVOKE", "MPV initialized ({}).", profile.name);
+    Ok(MpvPlayer::new(app, player_id, mpv, LinuxHost))
+}
@@ -0,0 +1,297 @@
+// macOS player: libmpv rendering into an NSView / CAMetalLayer placed under the webview.
+use super::actor::INIT_TIMEOUT;
+use super::mpv::{init_with_policy, MpvHost, MpvPlayer};
+use super::render_policy::{self, WidKind};
+use crate::error::{FlashplexError, FlashplexResult};
+use cocoa::base::id;
+use cocoa::foundation::NSRect;
+use libmpv2::Mpv;
+use objc::{class, msg_send, sel, sel_impl};
+use std::collections::BTreeMap;
+use std::io::Write;
+use std::sync::mpsc::RecvTimeoutError;
+
+pub struct MacHost {
+    app: tauri::AppHandle, // destroy() runs on the player thread; AppKit calls go to the main thread
+    container_view: usize, // Store container to remove on close
+    using_layer_wid: bool, // true: CAMetalLayer wid, false: NSView wid
+}
+
+pub fn create(app: &tauri::AppHandle, player_id: &str) -> FlashplexResult<MpvPlayer<MacHost>> {
+    let (tx_wid, rx_wid) = std::sync::mpsc::channel::<FlashplexResult<(usize, usize, usize)>>();
+    let app_handle_for_wid = app.clone();
+    
+    // 1. Get WID/NSView on main thread
+    app.run_on_main_thread(move || {
+        let res: FlashplexResult<(usize, usize, usize)> = (|| {
+            use tauri::Manager;
+            let window = app_handle_for_wid
+                .get_webview_window("main")
+                .ok_or_else(|| FlashplexError::window_not_found("main"))?;
+            let ns_window = window.ns_window()? as id;
+            
+            // Set window background to BLACK
+            unsafe {
+                let _: () = msg_send![ns_window, setOpaque: 1i8]; 
+                let black_color: id = msg_send![class!(NSColor), blackColor];
+                let _: () = msg_send![ns_window, setBackgroundColor: black_color];
+            }
+            
+            // Main content view
+            let content_view: id = unsafe { msg_send![ns_window, contentView] };
+            
+            // Create embedding NSView for mpv.
+            // Keep CAMetalLayer* as the primary WID candidate.
+            let (layer_wid_ptr, mpv_container_ptr): (usize, usize) = unsafe {
+                // Ensure parent has a layer backing
+                let _: () = msg_send![content_view, setWantsLayer: 1i8];
+                let _: () = msg_send![content_view, layer]; // Ensure root layer exists
+
+                // Create explicit CAMetalLayer
+                let layer: id = msg_send![class!(CAMetalLayer), layer];
+
+                // Create a container view for MPV
+                let mpv_container: id = msg_send![class!(NSView), alloc];
+                let mpv_container: id = msg_send![mpv_container, init];
+                
+                // Host the explicit CAMetalLayer
+                let _: () = msg_send![mpv_container, setWantsLayer: 1i8];
+                let _: () = msg_send![mpv_container, setLayer: layer];
+                
+                // Add container to window content view
+                // Position BELOW everything (at the bottom) to allow WebView (on top) to capture drag events
+                let _: () = msg_send![content_view, addSubview: mpv_container positioned: -1isize relativeTo: std::ptr::null_mut::<std::ffi::c_void>()];
+                
+                // [FIX] Use Old-School Autoresizing Mask (More reliable for fullscreen transitions)
+                // This allows the OS to handle resizing automatically as the parent view grows
+                let _: () = msg_send![mpv_container, setTranslatesAutoresizingMaskIntoConstraints: 1i8];
+                let _: () = msg_send![mpv_container, setAutoresizingMask: 18usize];
+                
+                // Set initial frame to match parent bounds
+                let bounds: NSRect = msg_send![content_view, bounds];
+                let _: () = msg_send![mpv_container, setFrame: bounds];
+                let _: () = msg_send![layer, setFrame: bounds];
+                let _: () = msg_send![layer, setAutoresizingMask: 18usize];
+                
+                // Visual properties (moved from original layer setup)
+                let ns_black: id = msg_send![class!(NSColor), blackColor];
+                let black_cg: id = msg_send![ns_black, CGColor];
+                let _: () = msg_send![layer, setBackgroundColor: black_cg];
+
+                log_debug!("MPV", "MPV container created. LAYER ptr: {:p}", layer);
+                
+                (layer as usize, mpv_container as usize)
+            };
+            
+            // Return both candidates:
+            //   1) CAMetalLayer* for Layer WID path
+            //   2) NSView*      for NSView WID path
+            Ok((layer_wid_ptr, mpv_container_ptr, mpv_container_ptr))
+        })();
+        let _ = tx_wid.send(res);
+    })?;
+
+    log_info!("INVOKE", "Waiting for WID pointers...");
+    let (layer_wid_raw, nsview_wid_raw, container_view_ptr) = match rx_wid.recv_timeout(INIT_TIMEOUT) {
+        Ok(result) => result?,
+        // The main thread is blocked (e.g. by a caller waiting on this player).
+        Err(RecvTimeoutError::Timeout) => {
+            return Err(FlashplexError::MainThread { message: "WID handshake timed out".to_string() })
+        }
+        Err(RecvTimeoutError::Disconnected) => return Err(FlashplexError::channel_closed("WID")),
+    };
+
+    let (policy, source) = render_policy::load(app);
+
+    // --- Dynamic MoltenVK ICD Detection ---
+    let m1_lib = "/opt/homebrew/lib/libMoltenVK.dylib";
+    let intel_lib = "/usr/local/lib/libMoltenVK.dylib";
+    let lib_candidates = if std::env::consts::ARCH == "x86_64" {
+        [intel_lib, m1_lib]
+    } else {
+        [m1_lib, intel_lib]
+    };
+
+    // Only policies with a vulkan/moltenvk candidate touch MoltenVK at all.
+    let moltenvk_lib = if policy.needs_vulkan() {
+        // Pick an existing library path, preferring the current CPU architecture.
+        let actual_lib = lib_candidates
+            .iter()
+            .copied()
+            .find(|path| std::path::Path::new(path).exists())
+            .unwrap_or_else(|| {
+                log_warn!(
+                    "MPV",
+                    "libMoltenVK.dylib not found in standard Homebrew paths for arch {}.",
+                    std::env::consts::ARCH
+                );
+                lib_candidates[0]
+            });
+
+        // Create a temporary ICD JSON content
+        let icd_json = serde_json::json!({
+            "file_format_version": "1.0.0",
+            "ICD": {
+                "library_path": actual_lib,
+                "api_version": "1.2"
+            }
+        });
+
+        // Write to a temporary file in the app's executable directory or /tmp
+        let icd_temp_path = "/tmp/moltenvk_icd_auto.json";
+        if let Ok(mut file) = std::fs::File::create(icd_temp_path) {
+            let _ = file.write_all(icd_json.to_string().as_bytes());
+        }
+        std::env::set_var("VK_ICD_FILENAMES", icd_temp_path);
+        log_info!("INVOKE", "Using Dynamic ICD: {} -> {}", icd_temp_path, actual_lib);
+        Some(actual_lib)
+    } else {
+        std::env::remove_var("VK_ICD_FILENAMES");
+        log_info!("INVOKE", "No Vulkan candidate: VK_ICD_FILENAMES cleared");
+        None
+    };
+    // --------------------------------------
+
+    let environment: BTreeMap<String, Option<String>> = [
+        ("VK_ICD_FILENAMES", std::env::var("VK_ICD_FILENAMES").ok()),
+        ("MPV_WID_EXPERIMENT", std::env::var("MPV_WID_EXPERIMENT").ok()),
+        ("MPV_LAYER_VO_LEGACY", std::env::var("MPV_LAYER_VO_LEGACY").ok()),
+        ("moltenvk_lib", moltenvk_lib.map(str::to_string)),
+        (
+            "moltenvk_lib_found",
+            moltenvk_lib.map(|lib| std::path::Path::new(lib).exists().to_string()),
+        ),
+    ]
+    .into_iter()
+    .map(|(k, v)| (k.to_string(), v))
+    .collect();
+
+    let (mpv, profile) = init_with_policy(
+        app,
+        &policy,
+        source,
+        |kind| match kind {
+            WidKind::Layer => Ok(Some(layer_wid_raw as i64)),
+            WidKind::Nsview => Ok(Some(nsview_wid_raw as i64)),
+            // mpv would open a second window next to ours.
+            WidKind::Window => Err(FlashplexError::unsupported("Window wid")),
+        },
+        environment,
+    )?;
+
+    log_info!("INVOKE", "MPV initialized ({}, {} wid).", profile.name, profile.wid.label());
+    Ok(MpvPlayer::new(
+        app,
+        player_id,
+        mpv,
+        MacHost {
+            app: app.clone(),
+            container_view: container_view_ptr,
+            using_layer_wid: profile.wid == WidKind::Layer,
+        },
+    ))
+}
+
+impl MpvHost for MacHost {
+    fn refresh_layout(&self, app: &tauri::AppHandle, mpv: &Mpv) -> FlashplexResult<()> {
+        use tauri::Manager;
+
+        let container_view_addr = self.container_view;
+        let using_layer_wid = self.using_layer_wid;
+        let app_handle = app.clone();
+
+        let _ = app.run_on_main_thread(move || {
+            let window = match app_handle.get_webview_window("main") {
+                Some(w) => w,
+                None => return,
+            };
+
+            let ns_window_ptr = match window.ns_window() {
+                Ok(ptr) => ptr as id,
+                Err(_) => return,
+            };
+
+            unsafe {
+                let container_ptr = container_view_addr as id;
+                let content_view: id = msg_send![ns_window_ptr, contentView];
+                let bounds: NSRect = msg_send![content_view, bounds];
+                let scale: f64 = msg_send![ns_window_ptr, backingScaleFactor];
+
+                // Force Match Parent Bounds
+                let _: () = msg_send![container_ptr, setTranslatesAutoresizingMaskIntoConstraints: 1i8];
+                let _: () = msg_send![container_ptr, setAutoresizingMask: 18usize];
+                let _: () = msg_send![container_ptr, setFrame: bounds];
+
+                if using_layer_wid {
+                    // Keep CAMetalLayer bounds aligned with container bounds
+                    // when mpv renders against layer pointer (`wid`).
+                    let layer: id = msg_send![container_ptr, layer];
+                    if !layer.is_null() {
+                        let _: () = msg_send![layer, setFrame: bounds];
+                        let _: () = msg_send![layer, setContentsScale: scale];
+                        let supports_drawable_size: bool = msg_send![layer, respondsToSelector: sel!(setDrawableSize:)];
+                        if supports_drawable_size {
+                            let drawable_size = cocoa::foundation::NSSize::new(
+                                bounds.size.width * scale,
+                                bounds.size.height * scale,
+                            );
+                            let _: () = msg_send![layer, setDrawableSize: drawable_size];
+                        }
+                        let _: () = msg_send![layer, setNeedsDisplay];
+                    }
+                } else {
+                    // NSView-wid path: force layout/display updates on container view.
+                    let _: () = msg_send![container_ptr, setNeedsLayout: 1i8];
+                    let _: () = msg_send![container_ptr, setNeedsDisplay: 1i8];
+                }
+
+                let _: () = msg_send![container_ptr, layoutSubtreeIfNeeded];
+
+                log_debug!(
+                    "RESIZE",
+                    "Container/{} -> {}x{} (scale: {}, drawable: {}x{})",
+                    if using_layer_wid { "LAYER" } else { "NSVIEW" },
+                    bounds.size.width,
+                    bounds.size.height,
+                    scale,
+                    bounds.size.width * scale,
+                    bounds.size.height * scale
+                );
+            }
+        });
+
+        // Trigger mpv VO refresh after host view resize.
+        // Some Layer-wid paths keep rendering at the initial size until a VO-side update occurs.
+        let zoom = mpv.get_property::<f64>("video-zoom").unwrap_or(0.0);
+        let _ = mpv.set_property("video-zoom", zoom + 0.0001f64);
+        let _ = mpv.set_property("video-zoom", zoom);
+        let _ = mpv.command("seek", &["+0", "relative"]);
+        let osd_w = mpv.get_property::<i64>("osd-width").unwrap_or(-1);
+        let osd_h = mpv.get_property::<i64>("osd-height").unwrap_or(-1);
+        let out_dw = mpv.get_property::<i64>("video-out-params/dw").unwrap_or(-1);
+        let out_dh = mpv.get_property::<i64>("video-out-params/dh").unwrap_or(-1);
+        log_debug!(
+            "RESIZE",
+            "MPV reconfigure poke sent (osd={}x{}, out={}x{})",
+            osd_w, osd_h, out_dw, out_dh
+        );
+        Ok(())
+    }
+
+    fn set_visible(&self, app: &tauri::AppHandle, visible: bool) {
+        let container_view_addr = self.container_view;
+        let _ = app.run_on_main_thread(move || unsafe {
+            let container_ptr = container_view_addr as id;
+            let _: () = msg_send![container_ptr, setHidden: if visible { 0i8 } else { 1i8 }];
+        });
+    }
+
+    fn destroy(self) {
+        // Remove the container view from superview (Prevent layer leak)
+        let container_view_addr = self.container_view;
+        let _ = self.app.run_on_main_thread(move || unsafe {
+            let container_ptr = container_view_addr as id;
+            let _: () = msg_send![container_ptr, removeFromSuperview];
+        });
+    }
+}
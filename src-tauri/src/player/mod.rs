@@ -0,0 +1,183 @@
+// Platform-neutral player interface.
+//
+// Commands in lib.rs only talk to `PlayerBackend`; each platform provides the
+// concrete player (libmpv embedded in the NSView on macOS, libmpv with its own
+// window or headless on Linux).
+use crate::error::FlashplexResult;
+use serde::{Deserialize, Serialize};
+
+mod actor;
+pub mod diagnostics;
+#[cfg(any(target_os = "macos", target_os = "linux"))]
+mod events;
+#[cfg(target_os = "linux")]
+mod linux;
+#[cfg(target_os = "macos")]
+mod macos;
+#[cfg(any(target_os = "macos", target_os = "linux"))]
+mod mpv;
+#[cfg(any(target_os = "macos", target_os = "linux"))]
+mod mpv_config;
+pub mod quality;
+pub mod queue;
+#[cfg_attr(not(any(target_os = "macos", target_os = "linux")), allow(dead_code))]
+pub mod render_policy;
+
+pub use actor::PlayerState;
+use quality::{QualityApplyReport, QualityProfile};
+use queue::{QueueItem, QueueSnapshot, RepeatMode};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LoadRequest {
+    pub title: String,
+    pub url: String,
+    pub subtitle_url: Option<String>,
+    pub start_pos: Option<f64>,
+    pub start_paused: bool,
+    /// GDS path/source of the file; playback of items without one isn't recorded in history.
+    pub path: Option<String>,
+    pub source_id: Option<u32>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SubtitleTrack {
+    pub id: i64,
+    pub lang: String,
+    pub title: String,
+    pub selected: bool,
+    pub external: bool,
+    pub index: i64,
+}
+
+/// One entry of mpv's `track-list`, any type. Fields mpv doesn't know for a track are None.
+#[derive(Debug, Clone, Serialize)]
+pub struct MediaTrack {
+    pub id: i64,
+    /// "video", "audio" or "sub".
+    #[serde(rename = "type")]
+    pub kind: String,
+    pub lang: String,
+    pub title: String,
+    pub codec: String,
+    pub selected: bool,
+    pub external: bool,
+    pub default: bool,
+    pub forced: bool,
+    pub channels: Option<i64>,
+    pub sample_rate: Option<i64>,
+    pub width: Option<i64>,
+    pub height: Option<i64>,
+    pub fps: Option<f64>,
+    pub index: i64,
+}
+
+/// Preferred languages as mpv `alang` / `slang` lists (e.g. "kor,ko,jpn").
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct TrackPreferences {
+    #[serde(default)]
+    pub alang: Option<String>,
+    #[serde(default)]
+    pub slang: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+pub struct SubtitleStyle {
+    pub scale: Option<f64>,
+    pub pos: Option<i64>,
+}
+
+/// Field names match the JSON `get_mpv_state` has always returned.
+#[derive(Debug, Clone, Serialize)]
+pub struct PlayerSnapshot {
+    pub position: f64,
+    pub duration: f64,
+    pub pause: bool,
+    pub hwdec: String,
+    pub sid: i64,
+    pub volume: i64,
+    pub osd_width: i64,
+    pub osd_height: i64,
+    pub out_width: i64,
+    pub out_height: i64,
+}
+
+impl Default for PlayerSnapshot {
+    fn default() -> Self {
+        Self {
+            position: 0.0,
+            duration: 0.0,
+            pause: true,
+            hwdec: "no".to_string(),
+            sid: -1,
+            volume: 100,
+            osd_width: -1,
+            osd_height: -1,
+            out_width: -1,
+            out_height: -1,
+        }
+    }
+}
+
+pub trait PlayerBackend: Send {
+    fn load(&self, req: &LoadRequest) -> FlashplexResult<()>;
+    fn add_subtitle(&self, url: &str, title: Option<&str>) -> FlashplexResult<()>;
+    fn reload_subtitles(&self) -> FlashplexResult<()>;
+    fn set_pause(&self, pause: bool) -> FlashplexResult<()>;
+    fn seek(&self, seconds: f64) -> FlashplexResult<()>;
+    fn set_volume(&self, volume: i64) -> FlashplexResult<()>;
+    fn subtitle_tracks(&self) -> Vec<SubtitleTrack>;
+    fn tracks(&self) -> Vec<MediaTrack>;
+    /// Negative `sid` disables subtitles.
+    fn set_subtitle_track(&self, sid: i64) -> FlashplexResult<()>;
+    /// Negative ids disable the track type, like `set_subtitle_track`.
+    fn set_audio_track(&self, aid: i64) -> FlashplexResult<()>;
+    fn set_video_track(&self, vid: i64) -> FlashplexResult<()>;
+    /// Applies to every file loaded afterwards.
+    fn set_track_preferences(&self, prefs: &TrackPreferences) -> FlashplexResult<()>;
+    fn set_subtitle_style(&self, style: SubtitleStyle) -> FlashplexResult<()>;
+    /// Sets every property of the resolved profile; rejected ones are reported, not fatal.
+    fn set_quality_profile(&self, profile: &QualityProfile) -> QualityApplyReport;
+    fn set_fullscreen(&self, fullscreen: bool) -> FlashplexResult<()>;
+    fn snapshot(&self) -> PlayerSnapshot;
+    /// Render/decoder/cache state for `player_diagnostics`.
+    fn diagnostics(&self) -> diagnostics::PlaybackDiagnostics;
+    /// Re-fit the video surface after the host window changed size.
+    fn refresh_layout(&self, app: &tauri::AppHandle) -> FlashplexResult<()>;
+    /// Stops playback and hides the video surface but keeps the instance
+    /// initialized; the next load shows it again.
+    fn park(&self) -> FlashplexResult<()>;
+    /// `script-message-to <target>`, or `script-message` to every script without a target.
+    fn script_message(&self, target: Option<&str>, args: &[String]) -> FlashplexResult<()>;
+
+    // Play queue (see queue.rs). Indices are playlist positions.
+    fn enqueue(&self, items: &[QueueItem]) -> FlashplexResult<()>;
+    fn insert_next(&self, item: &QueueItem) -> FlashplexResult<()>;
+    fn remove_from_queue(&self, index: i64) -> FlashplexResult<()>;
+    /// Moves the entry at `from` so that it ends up at index `to`.
+    fn move_in_queue(&self, from: i64, to: i64) -> FlashplexResult<()>;
+    fn play_next(&self) -> FlashplexResult<()>;
+    fn play_previous(&self) -> FlashplexResult<()>;
+    fn set_shuffle(&self, shuffle: bool) -> FlashplexResult<()>;
+    fn set_repeat(&self, mode: RepeatMode) -> FlashplexResult<()>;
+    fn queue(&self) -> QueueSnapshot;
+
+    fn close(self: Box<Self>);
+}
+
+/// Creates player `id` for the current platform. Each instance gets its own
+/// video surface; `id` tags the events it emits.
+pub fn create_backend(app: &tauri::AppHandle, id: &str) -> FlashplexResult<Box<dyn PlayerBackend>> {
+    #[cfg(target_os = "macos")]
+    {
+        Ok(Box::new(macos::create(app, id)?))
+    }
+    #[cfg(target_os = "linux")]
+    {
+        Ok(Box::new(linux::create(app, id)?))
+    }
+    #[cfg(not(any(target_os = "macos", target_os = "linux")))]
+    {
+        let _ = (app, id);
+        Err(crate::error::FlashplexError::unsupported("Embedded player"))
+    }
+}
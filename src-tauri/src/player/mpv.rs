@@ -0,0 +1,558 @@
+// libmpv-backed `PlayerBackend`, shared by every platform that links libmpv.
+//
+// Only the video surface differs between platforms; that part lives behind
+// `MpvHost` (NSView/CAMetalLayer on macOS, mpv's own window on Linux).
+use super::diagnostics::{record_render_init, CacheDiagnostics, InitAttempt, PlaybackDiagnostics, RenderInit};
+use super::mpv_config::MpvConfig;
+use super::render_policy::{self, PolicySource, RenderPathPolicy, RenderProfile, WidKind};
+use super::queue::{QueueEntry, QueueItem, QueueSnapshot, RepeatMode, SharedQueue};
+use super::quality::{PropertyValue, QualityApplyReport, QualityProfile, RejectedProperty};
+use super::{
+    LoadRequest, MediaTrack, PlayerBackend, PlayerSnapshot, SubtitleStyle, SubtitleTrack,
+    TrackPreferences,
+};
+use crate::credentials::redact;
+use crate::error::{FlashplexError, FlashplexResult};
+use libmpv2::Mpv;
+use std::collections::BTreeMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+pub trait MpvHost: Send {
+    fn refresh_layout(&self, app: &tauri::AppHandle, mpv: &Mpv) -> FlashplexResult<()>;
+    /// Shows or hides the video surface without releasing it (see `PlayerBackend::park`).
+    fn set_visible(&self, app: &tauri::AppHandle, visible: bool);
+    /// Called after mpv was told to quit; release the video surface.
+    fn destroy(self);
+}
+
+pub struct MpvPlayer<H: MpvHost> {
+    app: tauri::AppHandle,
+    mpv: Mpv,
+    host: H,
+    queue: SharedQueue,
+    // Stopped with the surface hidden, waiting for the next file.
+    parked: AtomicBool,
+}
+
+// libmpv handles are thread-safe; the host only stores raw view pointers.
+unsafe impl<H: MpvHost> Send for MpvPlayer<H> {}
+
+impl<H: MpvHost> MpvPlayer<H> {
+    pub fn new(app: &tauri::AppHandle, player_id: &str, mpv: Mpv, host: H) -> Self {
+        let queue = SharedQueue::default();
+        if let Err(e) = super::events::spawn_event_thread(app.clone(), player_id, &mpv, queue.clone()) {
+            log_warn!("MPV", "mpv event stream unavailable: {}", e);
+        }
+        Self {
+            app: app.clone(),
+            mpv,
+            host,
+            queue,
+            parked: AtomicBool::new(false),
+        }
+    }
+
+    fn command(&self, name: &str, args: &[&str]) -> FlashplexResult<()> {
+        self.mpv
+            .command(name, args)
+            .map_err(|e| FlashplexError::mpv_command(name, e))
+    }
+
+    // `mode` is a loadfile flag (replace / append / append-play).
+    // Queued subtitles are added by the event thread once the file is loaded.
+    fn loadfile(&self, item: &QueueItem, mode: &str, start_paused: bool) -> FlashplexResult<()> {
+        if self.parked.swap(false, Ordering::SeqCst) {
+            self.host.set_visible(&self.app, true);
+        }
+        let mut options: Vec<String> = Vec::new();
+        if let Some(pos) = item.start_pos {
+            if pos > 0.2 {
+                options.push(format!("start={:.3}", pos));
+            }
+        }
+        if start_paused {
+            options.push("pause=yes".to_string());
+        }
+        let options = options.join(",");
+        let mut args: Vec<&str> = vec![item.url.as_str(), mode];
+        if !options.is_empty() {
+            args.push(options.as_str());
+        }
+        self.command("loadfile", &args)?;
+        self.queue.lock()?.items.insert(item.url.clone(), item.clone());
+        Ok(())
+    }
+
+    // "no" for negative ids, like the subtitle selection.
+    fn select_track(&self, property: &str, id: i64) -> FlashplexResult<()> {
+        let result = if id < 0 {
+            self.mpv.set_property(property, "no")
+        } else {
+            self.mpv.set_property(property, id)
+        };
+        result.map_err(|e| FlashplexError::mpv_command(&format!("set {}", property), e))
+    }
+
+    fn playlist_pos(&self) -> i64 {
+        self.mpv.get_property::<i64>("playlist-pos").unwrap_or(-1)
+    }
+
+    fn playlist_count(&self) -> i64 {
+        self.mpv.get_property::<i64>("playlist-count").unwrap_or(0)
+    }
+}
+
+/// Options every libmpv player starts with, independent of the render path.
+fn apply_behavior_options(init: &libmpv2::MpvInitializer) {
+    let _ = init.set_option("keepaspect-window", "no");
+    let _ = init.set_option("input-default-bindings", "no");
+    let _ = init.set_option("input-vo-keyboard", "no");
+    let _ = init.set_option("osc", "no");
+    // mpv's log reaches the app log through the event client (see events.rs).
+    let _ = init.set_option("terminal", "no");
+}
+
+fn apply_render_profile(init: &libmpv2::MpvInitializer, profile: &RenderProfile) -> libmpv2::Result<()> {
+    init.set_option("vo", profile.vo.as_str())?;
+    if let Some(api) = &profile.gpu_api {
+        init.set_option("gpu-api", api.as_str())?;
+    }
+    if let Some(context) = &profile.gpu_context {
+        init.set_option("gpu-context", context.as_str())?;
+    }
+    for (key, value) in &profile.options {
+        init.set_option(key.as_str(), value.as_str())?;
+    }
+    if let Some(hwdec) = &profile.hwdec {
+        if let Err(e) = init.set_option("hwdec", hwdec.as_str()) {
+            log_warn!("MPV", "Init hwdec={}: {}", hwdec, e);
+        }
+    }
+    log_info!(
+        "INVOKE",
+        "MPV render profile {}: vo={} gpu-api={} gpu-context={}",
+        profile.name,
+        profile.vo,
+        profile.gpu_api.as_deref().unwrap_or("auto"),
+        profile.gpu_context.as_deref().unwrap_or("auto")
+    );
+    Ok(())
+}
+
+/// Initializes libmpv with the first policy candidate that works.
+///
+/// `wid_for` maps a candidate's wid kind to the handle mpv should draw into
+/// (None: mpv's own window); an error skips the candidate. Every attempt ends
+/// up in `player_diagnostics`, together with `environment`.
+pub fn init_with_policy(
+    app: &tauri::AppHandle,
+    policy: &RenderPathPolicy,
+    source: PolicySource,
+    wid_for: impl Fn(WidKind) -> FlashplexResult<Option<i64>>,
+    mut environment: BTreeMap<String, Option<String>>,
+) -> FlashplexResult<(Mpv, RenderProfile)> {
+    let last_good = if policy.remember_last_good { render_policy::last_good(app) } else { None };
+    environment.insert("render_policy".to_string(), Some(source.as_str().to_string()));
+    environment.insert("render_path_last_good".to_string(), last_good.clone());
+    let config = MpvConfig::resolve(app);
+    config.describe(&mut environment);
+
+    let mut attempts = Vec::new();
+    let mut selected = None;
+    let mut last_error = None;
+    for profile in policy.ordered(last_good.as_deref()) {
+        let kind = profile.wid.label();
+        let result = wid_for(profile.wid).and_then(|wid| {
+            log_info!("INVOKE", "Initializing MPV ({}, {} wid)", profile.name, kind);
+            Mpv::with_initializer(|init| {
+                config.apply(&init)?;
+                if let Some(wid) = wid {
+                    init.set_option("wid", wid)?;
+                }
+                apply_render_profile(&init, profile)?;
+                apply_behavior_options(&init);
+                Ok(())
+            })
+            .map_err(|e| {
+                log_error!("MPV", "MPV init failed ({}, {} wid): {}", profile.name, kind, e);
+                FlashplexError::mpv_init(&profile.name, e)
+            })
+        });
+        match result {
+            Ok(mpv) => {
+                attempts.push(InitAttempt::new(&profile.name, Some(kind), None));
+                selected = Some((mpv, profile.clone()));
+                break;
+            }
+            Err(e) => {
+                attempts.push(InitAttempt::new(&profile.name, Some(kind), Some(e.to_string())));
+                last_error = Some(e);
+            }
+        }
+    }
+
+    record_render_init(app, RenderInit::new(attempts, environment));
+    match selected {
+        Some((mpv, profile)) => {
+            if policy.remember_last_good && last_good.as_deref() != Some(profile.name.as_str()) {
+                render_policy::remember(app, &profile.name);
+            }
+            Ok((mpv, profile))
+        }
+        None => Err(last_error.unwrap_or_else(|| FlashplexError::invalid_input("render policy: no candidates"))),
+    }
+}
+
+pub fn apply_quality_profile(mpv: &Mpv, profile: &QualityProfile) -> QualityApplyReport {
+    let mut applied = 0;
+    let mut rejected = Vec::new();
+    for (name, value) in &profile.properties {
+        let result = match value {
+            PropertyValue::Bool(v) => mpv.set_property(name, *v),
+            PropertyValue::Int(v) => mpv.set_property(name, *v),
+            PropertyValue::Float(v) => mpv.set_property(name, *v),
+            PropertyValue::Text(v) => mpv.set_property(name, v.as_str()),
+        };
+        match result {
+            Ok(()) => applied += 1,
+            Err(e) => {
+                log_warn!("QUALITY", "{}: mpv rejected {}={}: {}", profile.name, name, value, e);
+                rejected.push(RejectedProperty {
+                    name: name.clone(),
+                    value: value.clone(),
+                    error: e.to_string(),
+                });
+            }
+        }
+    }
+
+    log_info!(
+        "QUALITY",
+        "Applied profile: {} ({} set, {} rejected)",
+        profile.chain.join(" > "),
+        applied,
+        rejected.len()
+    );
+    QualityApplyReport {
+        profile: profile.name.clone(),
+        applied,
+        rejected,
+    }
+}
+
+impl<H: MpvHost> PlayerBackend for MpvPlayer<H> {
+    fn load(&self, req: &LoadRequest) -> FlashplexResult<()> {
+        // `replace` resets mpv's playlist, so drop the metadata of the old queue too.
+        self.queue.lock()?.items.clear();
+        let item = QueueItem {
+            url: req.url.clone(),
+            title: req.title.clone(),
+            subtitle_url: req.subtitle_url.clone(),
+            start_pos: req.start_pos,
+            path: req.path.clone(),
+            source_id: req.source_id,
+        };
+        self.loadfile(&item, "replace", req.start_paused)
+    }
+
+    fn add_subtitle(&self, url: &str, title: Option<&str>) -> FlashplexResult<()> {
+        let args: &[&str] = if let Some(t) = title {
+            &[url, "auto", t]
+        } else {
+            &[url, "auto"]
+        };
+        self.command("sub-add", args)?;
+        log_info!("LIB", "Added track: {}", redact(url));
+        Ok(())
+    }
+
+    fn reload_subtitles(&self) -> FlashplexResult<()> {
+        // Re-scan tracks by toggling or just let frontend re-fetch
+        let _ = self.mpv.command("sub-reload", &[]);
+        Ok(())
+    }
+
+    fn set_pause(&self, pause: bool) -> FlashplexResult<()> {
+        self.mpv
+            .set_property("pause", pause)
+            .map_err(|e| FlashplexError::mpv_command("set pause", e))
+    }
+
+    fn seek(&self, seconds: f64) -> FlashplexResult<()> {
+        let _ = self.mpv.command("seek", &[&seconds.to_string(), "absolute"]);
+        Ok(())
+    }
+
+    fn set_volume(&self, volume: i64) -> FlashplexResult<()> {
+        self.mpv
+            .set_property("volume", volume)
+            .map_err(|e| FlashplexError::mpv_command("set volume", e))
+    }
+
+    fn subtitle_tracks(&self) -> Vec<SubtitleTrack> {
+        self.tracks()
+            .into_iter()
+            .filter(|t| t.kind == "sub")
+            .map(|t| SubtitleTrack {
+                id: t.id,
+                lang: t.lang,
+                title: t.title,
+                selected: t.selected,
+                external: t.external,
+                index: t.index, // useful for debug
+            })
+            .collect()
+    }
+
+    fn tracks(&self) -> Vec<MediaTrack> {
+        let count = self.mpv.get_property::<i64>("track-list/count").unwrap_or(0);
+        let mut tracks = Vec::new();
+
+        for i in 0..count {
+            let prop = |name: &str| format!("track-list/{}/{}", i, name);
+            let string = |name: &str| self.mpv.get_property::<String>(&prop(name)).unwrap_or_default();
+            let flag = |name: &str| self.mpv.get_property::<bool>(&prop(name)).unwrap_or(false);
+            let int = |name: &str| self.mpv.get_property::<i64>(&prop(name)).ok();
+            tracks.push(MediaTrack {
+                id: int("id").unwrap_or(0),
+                kind: string("type"),
+                lang: string("lang"),
+                title: string("title"),
+                codec: string("codec"),
+                selected: flag("selected"),
+                external: flag("external"),
+                default: flag("default"),
+                forced: flag("forced"),
+                channels: int("demux-channel-count"),
+                sample_rate: int("demux-samplerate"),
+                width: int("demux-w"),
+                height: int("demux-h"),
+                fps: self.mpv.get_property::<f64>(&prop("demux-fps")).ok(),
+                index: i,
+            });
+        }
+        tracks
+    }
+
+    fn set_subtitle_track(&self, sid: i64) -> FlashplexResult<()> {
+        // MPV 'sid' property: integer ID (1-based), or 'no' / 'auto'.
+        let _ = self.select_track("sid", sid);
+        Ok(())
+    }
+
+    fn set_audio_track(&self, aid: i64) -> FlashplexResult<()> {
+        self.select_track("aid", aid)
+    }
+
+    fn set_video_track(&self, vid: i64) -> FlashplexResult<()> {
+        self.select_track("vid", vid)
+    }
+
+    fn set_track_preferences(&self, prefs: &TrackPreferences) -> FlashplexResult<()> {
+        // mpv picks tracks from alang/slang each time a file is loaded; empty = no preference.
+        for (property, value) in [("alang", &prefs.alang), ("slang", &prefs.slang)] {
+            self.mpv
+                .set_property(property, value.as_deref().unwrap_or(""))
+                .map_err(|e| FlashplexError::mpv_command(&format!("set {}", property), e))?;
+        }
+        log_info!("TRACKS", "Preferences alang={:?} slang={:?}", prefs.alang, prefs.slang);
+        Ok(())
+    }
+
+    fn set_subtitle_style(&self, style: SubtitleStyle) -> FlashplexResult<()> {
+        if let Some(s) = style.scale {
+            let _ = self.mpv.set_property("sub-scale", s);
+        }
+        if let Some(p) = style.pos {
+            let _ = self.mpv.set_property("sub-pos", p);
+        }
+        Ok(())
+    }
+
+    fn set_quality_profile(&self, profile: &QualityProfile) -> QualityApplyReport {
+        apply_quality_profile(&self.mpv, profile)
+    }
+
+    fn set_fullscreen(&self, fullscreen: bool) -> FlashplexResult<()> {
+        let _ = self.mpv.set_property("fullscreen", fullscreen);
+        Ok(())
+    }
+
+    fn snapshot(&self) -> PlayerSnapshot {
+        let mpv = &self.mpv;
+        PlayerSnapshot {
+            position: mpv.get_property::<f64>("time-pos").unwrap_or(0.0),
+            duration: mpv.get_property::<f64>("duration").unwrap_or(0.0),
+            pause: mpv.get_property::<bool>("pause").unwrap_or(false),
+            hwdec: mpv.get_property::<String>("hwdec-current").unwrap_or_else(|_| "no".to_string()),
+            sid: mpv.get_property::<i64>("sid").unwrap_or(-1),
+            volume: mpv.get_property::<i64>("volume").unwrap_or(100),
+            osd_width: mpv.get_property::<i64>("osd-width").unwrap_or(-1),
+            osd_height: mpv.get_property::<i64>("osd-height").unwrap_or(-1),
+            out_width: mpv.get_property::<i64>("video-out-params/dw").unwrap_or(-1),
+            out_height: mpv.get_property::<i64>("video-out-params/dh").unwrap_or(-1),
+        }
+    }
+
+    fn diagnostics(&self) -> PlaybackDiagnostics {
+        let mpv = &self.mpv;
+        let string = |name: &str| mpv.get_property::<String>(name).ok().filter(|v| !v.is_empty());
+        let int = |name: &str| mpv.get_property::<i64>(name).ok();
+        let float = |name: &str| mpv.get_property::<f64>(name).ok();
+        PlaybackDiagnostics {
+            mpv_version: string("mpv-version"),
+            ffmpeg_version: string("ffmpeg-version"),
+            current_vo: string("current-vo"),
+            gpu_api: string("gpu-api"),
+            gpu_context: string("gpu-context"),
+            hwdec: string("hwdec"),
+            hwdec_current: string("hwdec-current"),
+            video_codec: string("video-codec"),
+            video_format: string("video-format"),
+            pixel_format: string("video-params/pixelformat"),
+            width: int("video-params/w"),
+            height: int("video-params/h"),
+            fps: float("container-fps"),
+            frame_drops: int("frame-drop-count"),
+            decoder_frame_drops: int("decoder-frame-drop-count"),
+            cache: CacheDiagnostics {
+                paused_for_cache: mpv.get_property::<bool>("paused-for-cache").ok(),
+                buffering_percent: int("cache-buffering-state"),
+                duration: float("demuxer-cache-duration"),
+                speed_bytes: int("cache-speed"),
+            },
+            window_scale: float("display-hidpi-scale"),
+            osd_width: int("osd-width"),
+            osd_height: int("osd-height"),
+        }
+    }
+
+    fn refresh_layout(&self, app: &tauri::AppHandle) -> FlashplexResult<()> {
+        self.host.refresh_layout(app, &self.mpv)
+    }
+
+    fn park(&self) -> FlashplexResult<()> {
+        self.queue.lock()?.items.clear();
+        self.command("stop", &[])?;
+        if !self.parked.swap(true, Ordering::SeqCst) {
+            self.host.set_visible(&self.app, false);
+        }
+        log_info!("EMBEDDED", "Player parked");
+        Ok(())
+    }
+
+    fn script_message(&self, target: Option<&str>, args: &[String]) -> FlashplexResult<()> {
+        let mut argv: Vec<&str> = target.into_iter().collect();
+        argv.extend(args.iter().map(String::as_str));
+        let name = if target.is_some() { "script-message-to" } else { "script-message" };
+        self.command(name, &argv)
+    }
+
+    fn enqueue(&self, items: &[QueueItem]) -> FlashplexResult<()> {
+        for item in items {
+            // append-play starts playback when the playlist was idle (e.g. after the last entry ended).
+            self.loadfile(item, "append-play", false)?;
+        }
+        Ok(())
+    }
+
+    fn insert_next(&self, item: &QueueItem) -> FlashplexResult<()> {
+        let pos = self.playlist_pos();
+        self.loadfile(item, "append-play", false)?;
+        if pos >= 0 {
+            let last = self.playlist_count() - 1;
+            if last > pos + 1 {
+                self.command("playlist-move", &[&last.to_string(), &(pos + 1).to_string()])?;
+            }
+        }
+        Ok(())
+    }
+
+    fn remove_from_queue(&self, index: i64) -> FlashplexResult<()> {
+        self.command("playlist-remove", &[&index.to_string()])
+    }
+
+    fn move_in_queue(&self, from: i64, to: i64) -> FlashplexResult<()> {
+        if from == to {
+            return Ok(());
+        }
+        // playlist-move inserts *before* the target entry.
+        let target = if to > from { to + 1 } else { to };
+        self.command("playlist-move", &[&from.to_string(), &target.to_string()])
+    }
+
+    fn play_next(&self) -> FlashplexResult<()> {
+        self.command("playlist-next", &[])
+    }
+
+    fn play_previous(&self) -> FlashplexResult<()> {
+        self.command("playlist-prev", &[])
+    }
+
+    fn set_shuffle(&self, shuffle: bool) -> FlashplexResult<()> {
+        self.command(if shuffle { "playlist-shuffle" } else { "playlist-unshuffle" }, &[])?;
+        self.queue.lock()?.shuffle = shuffle;
+        log_info!("QUEUE", "shuffle={}", shuffle);
+        Ok(())
+    }
+
+    fn set_repeat(&self, mode: RepeatMode) -> FlashplexResult<()> {
+        let (loop_file, loop_playlist) = match mode {
+            RepeatMode::Off => ("no", "no"),
+            RepeatMode::One => ("inf", "no"),
+            RepeatMode::All => ("no", "inf"),
+        };
+        self.mpv
+            .set_property("loop-file", loop_file)
+            .map_err(|e| FlashplexError::mpv_command("set loop-file", e))?;
+        self.mpv
+            .set_property("loop-playlist", loop_playlist)
+            .map_err(|e| FlashplexError::mpv_command("set loop-playlist", e))?;
+        self.queue.lock()?.repeat = mode;
+        log_info!("QUEUE", "repeat={:?}", mode);
+        Ok(())
+    }
+
+    fn queue(&self) -> QueueSnapshot {
+        let current = self.playlist_pos();
+        let count = self.playlist_count();
+        let mut meta = match self.queue.lock() {
+            Ok(meta) => meta,
+            Err(_) => return QueueSnapshot::default(),
+        };
+
+        let mut entries = Vec::new();
+        for i in 0..count {
+            let url = self.mpv.get_property::<String>(&format!("playlist/{}/filename", i)).unwrap_or_default();
+            let item = meta.items.get(&url).cloned().unwrap_or_else(|| QueueItem {
+                title: self.mpv.get_property::<String>(&format!("playlist/{}/title", i)).unwrap_or_default(),
+                url: url.clone(),
+                subtitle_url: None,
+                start_pos: None,
+                path: None,
+                source_id: None,
+            });
+            entries.push(QueueEntry { index: i, current: i == current, item });
+        }
+
+        // Forget metadata of entries that are no longer in mpv's playlist.
+        meta.items.retain(|url, _| entries.iter().any(|e| &e.item.url == url));
+
+        QueueSnapshot {
+            entries,
+            current,
+            shuffle: meta.shuffle,
+            repeat: meta.repeat,
+        }
+    }
+
+    fn close(self: Box<Self>) {
+        let MpvPlayer { mpv, host, .. } = *self;
+        // 1. Explicitly quit to ensure that core shuts down
+        let _ = mpv.command("quit", &["0"]);
+        // 2. Release the host surface (e.g. remove the container view)
+        host.destroy();
+        drop(mpv);
+        log_info!("EMBEDDED", "Player closed and view removed");
+    }
+}
@@ -0,0 +1,175 @@
+// mpv configuration shipped with the app.
+//
+// The bundle carries `resources/mpv_config` (mpv.conf, fonts/, scripts/) and
+// `resources/gds_osc.lua`. mpv reads one config dir, so the player gets a small
+// generated one in the app cache dir whose mpv.conf includes the bundled mpv.conf
+// and then `mpv.conf` from the app config dir, in that order: user settings win.
+// Fonts come straight from the bundle. Scripts are never auto-loaded; only the
+// names listed in the `mpv_scripts` setting are, looked up in `scripts/` of the
+// app config dir first and then in the bundle.
+use crate::error::FlashplexResult;
+use crate::settings::SettingsState;
+use crate::storage::write_atomic;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use tauri::Manager;
+
+const RUNTIME_DIR: &str = "mpv_runtime";
+const USER_CONF: &str = "mpv.conf";
+const USER_SCRIPTS_DIR: &str = "scripts";
+// Lua and JavaScript files, or a directory with main.lua / main.js.
+const SCRIPT_EXTENSIONS: [&str; 2] = ["lua", "js"];
+
+#[derive(Debug, Default)]
+pub struct MpvConfig {
+    /// Generated config dir; None when nothing could be written (mpv then reads no config).
+    pub config_dir: Option<PathBuf>,
+    pub fonts_dir: Option<PathBuf>,
+    pub scripts: Vec<PathBuf>,
+}
+
+impl MpvConfig {
+    /// Resolves the bundle and user files for the next player.
+    pub fn resolve(app: &tauri::AppHandle) -> Self {
+        let bundle = bundle_dir(app);
+        let user_dir = app.path().app_config_dir().ok();
+        let runtime_dir = app
+            .path()
+            .app_cache_dir()
+            .unwrap_or_else(|_| std::env::temp_dir().join("flashplex").join("cache"))
+            .join(RUNTIME_DIR);
+        let bundled_conf = bundle.as_ref().map(|dir| dir.join("mpv_config").join("mpv.conf"));
+        let user_conf = user_dir.as_ref().map(|dir| dir.join(USER_CONF));
+        let config_dir = match write_runtime_conf(&runtime_dir, bundled_conf.as_deref(), user_conf.as_deref()) {
+            Ok(()) => Some(runtime_dir),
+            Err(e) => {
+                log_warn!("MPV", "mpv config not written to {}: {}", runtime_dir.display(), e);
+                None
+            }
+        };
+        let fonts_dir = bundle
+            .as_ref()
+            .map(|dir| dir.join("mpv_config").join("fonts"))
+            .filter(|dir| dir.is_dir());
+
+        let allowlist = app
+            .try_state::<SettingsState>()
+            .and_then(|s| s.snapshot().ok())
+            .map(|s| s.mpv_scripts)
+            .unwrap_or_default();
+        let mut search = Vec::new();
+        if let Some(dir) = &user_dir {
+            search.push(dir.join(USER_SCRIPTS_DIR));
+        }
+        if let Some(dir) = &bundle {
+            search.push(dir.clone());
+            search.push(dir.join("mpv_config").join("scripts"));
+        }
+        let scripts = allowlist
+            .iter()
+            .filter_map(|name| {
+                let found = find_script(&search, name);
+                if found.is_none() {
+                    log_warn!("MPV", "Script '{}' is allowed but not installed", name);
+                }
+                found
+            })
+            .collect();
+
+        MpvConfig {
+            config_dir,
+            fonts_dir,
+            scripts,
+        }
+    }
+
+    /// Sets the options before `mpv_initialize`. Options this mpv doesn't know are
+    /// logged and skipped; the player still starts.
+    pub fn apply(&self, init: &libmpv2::MpvInitializer) -> libmpv2::Result<()> {
+        match &self.config_dir {
+            Some(dir) => {
+                init.set_option("config-dir", dir.to_string_lossy().as_ref())?;
+                init.set_option("config", "yes")?;
+            }
+            None => init.set_option("config", "no")?,
+        }
+        // Only the allowlisted scripts below, not everything under scripts/.
+        init.set_option("load-scripts", "no")?;
+        if let Some(dir) = &self.fonts_dir {
+            let dir = dir.to_string_lossy();
+            for option in ["sub-fonts-dir", "osd-fonts-dir"] {
+                if let Err(e) = init.set_option(option, dir.as_ref()) {
+                    log_warn!("MPV", "Init {}: {}", option, e);
+                }
+            }
+        }
+        for script in &self.scripts {
+            if let Err(e) = init.set_option("scripts-append", script.to_string_lossy().as_ref()) {
+                log_warn!("MPV", "Script {} not loaded: {}", script.display(), e);
+            }
+        }
+        Ok(())
+    }
+
+    /// Paths for `player_diagnostics`.
+    pub fn describe(&self, environment: &mut BTreeMap<String, Option<String>>) {
+        let path = |p: &Option<PathBuf>| p.as_ref().map(|p| p.display().to_string());
+        environment.insert("mpv_config_dir".to_string(), path(&self.config_dir));
+        environment.insert("mpv_fonts_dir".to_string(), path(&self.fonts_dir));
+        let scripts = self
+            .scripts
+            .iter()
+            .map(|p| p.display().to_string())
+            .collect::<Vec<_>>()
+            .join(", ");
+        environment.insert("mpv_scripts".to_string(), Some(scripts));
+    }
+}
+
+// `resources/` of the app bundle; in a dev build the crate's own copy when the
+// bundle wasn't laid out. gds_osc.lua sits next to mpv_config/ and is found on its
+// own, so either one is enough.
+fn bundle_dir(app: &tauri::AppHandle) -> Option<PathBuf> {
+    let has_files = |dir: &Path| dir.join("mpv_config").is_dir() || dir.join("gds_osc.lua").is_file();
+    let bundled = app.path().resource_dir().ok().map(|dir| dir.join("resources"));
+    #[cfg(debug_assertions)]
+    let bundled = bundled
+        .filter(|dir| has_files(dir))
+        .or_else(|| Some(Path::new(env!("CARGO_MANIFEST_DIR")).join("resources")));
+    let Some(dir) = bundled.filter(|dir| has_files(dir)) else {
+        log_warn!("MPV", "Bundled mpv resources not found");
+        return None;
+    };
+    if !dir.join("mpv_config").is_dir() {
+        log_warn!("MPV", "Bundled mpv_config not found in {}", dir.display());
+    }
+    Some(dir)
+}
+
+fn write_runtime_conf(dir: &Path, bundled: Option<&Path>, user: Option<&Path>) -> FlashplexResult<()> {
+    let mut conf = String::from("# Generated at player start. Edit mpv.conf in the app config dir instead.\n");
+    for file in [bundled, user].into_iter().flatten().filter(|f| f.is_file()) {
+        // %<bytes>%<value> is mpv's quoting for values with any characters in them.
+        let path = file.to_string_lossy();
+        conf.push_str(&format!("include=%{}%{}\n", path.len(), path));
+    }
+    write_atomic(&dir.join("mpv.conf"), conf.as_bytes())
+}
+
+// First `<dir>/<name>.lua|.js` or `<dir>/<name>/` in search order. Names are plain
+// file names, so the allowlist can't point outside the script dirs.
+fn find_script(search: &[PathBuf], name: &str) -> Option<PathBuf> {
+    let valid = !name.is_empty() && name.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | '.'));
+    if !valid || name.starts_with('.') {
+        return None;
+    }
+    search.iter().find_map(|dir| {
+        SCRIPT_EXTENSIONS
+            .iter()
+            .map(|ext| dir.join(format!("{}.{}", name, ext)))
+            .chain(std::iter::once(dir.join(name)))
+            .find(|path| {
+                path.is_file() || SCRIPT_EXTENSIONS.iter().any(|ext| path.join(format!("main.{}", ext)).is_file())
+            })
+    })
+}
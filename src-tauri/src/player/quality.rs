@@ -0,0 +1,508 @@
+// Quality profiles: named sets of mpv properties.
+//
+// "balanced", "quality" and "smooth" are built in. Users add or override
+// profiles in quality_profiles.toml (or .yaml / .yml) in the app config dir;
+// the file is re-read when it changes on disk. A profile can inherit another
+// one and its own properties win:
+//
+//   [profiles.anime]
+//   inherits = "quality"
+//   description = "Stronger deband for flat colors"
+//   properties = { deband-iterations = 4, deband-grain = 0 }
+use crate::error::{FlashplexError, FlashplexResult};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::SystemTime;
+
+pub const QUALITY_PROFILE_CHANGED_EVENT: &str = "quality-profile-changed";
+pub const DEFAULT_QUALITY_PROFILE: &str = "balanced";
+const PROFILE_FILES: [&str; 3] = ["quality_profiles.toml", "quality_profiles.yaml", "quality_profiles.yml"];
+const MAX_INHERITANCE_DEPTH: usize = 8;
+
+/// An mpv property value as written in the profile file.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum PropertyValue {
+    Bool(bool),
+    Int(i64),
+    Float(f64),
+    Text(String),
+}
+
+impl fmt::Display for PropertyValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PropertyValue::Bool(v) => write!(f, "{}", if *v { "yes" } else { "no" }),
+            PropertyValue::Int(v) => write!(f, "{}", v),
+            PropertyValue::Float(v) => write!(f, "{}", v),
+            PropertyValue::Text(v) => write!(f, "{}", v),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ProfileDefinition {
+    #[serde(default)]
+    pub inherits: Option<String>,
+    #[serde(default)]
+    pub description: Option<String>,
+    #[serde(default)]
+    pub properties: BTreeMap<String, PropertyValue>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct ProfileFile {
+    #[serde(default)]
+    profiles: BTreeMap<String, ProfileDefinition>,
+}
+
+/// A profile with its inheritance chain flattened.
+#[derive(Debug, Clone, Serialize)]
+pub struct QualityProfile {
+    pub name: String,
+    pub description: Option<String>,
+    /// Ancestors first, ending with `name`.
+    pub chain: Vec<String>,
+    pub properties: BTreeMap<String, PropertyValue>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RejectedProperty {
+    pub name: String,
+    pub value: PropertyValue,
+    pub error: String,
+}
+
+/// Result of applying a profile; also the `quality-profile-changed` payload.
+#[derive(Debug, Clone, Serialize)]
+pub struct QualityApplyReport {
+    pub profile: String,
+    /// Properties mpv accepted; 0 when no player is open.
+    pub applied: usize,
+    pub rejected: Vec<RejectedProperty>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct QualityProfileInfo {
+    pub name: String,
+    pub description: Option<String>,
+    pub inherits: Option<String>,
+    pub builtin: bool,
+    /// Resolved properties, empty when `error` is set.
+    pub properties: BTreeMap<String, PropertyValue>,
+    /// Broken inheritance (unknown parent, cycle).
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct QualityProfileList {
+    pub active: String,
+    pub file: Option<String>,
+    /// Parse error of the user file; its last good profiles stay in use.
+    pub file_error: Option<String>,
+    pub profiles: Vec<QualityProfileInfo>,
+}
+
+pub fn normalize_quality_profile(profile: &str) -> String {
+    let profile = profile.trim();
+    if profile.is_empty() {
+        DEFAULT_QUALITY_PROFILE.to_string()
+    } else {
+        profile.to_string()
+    }
+}
+
+fn builtin_profiles() -> BTreeMap<String, ProfileDefinition> {
+    fn profile(inherits: Option<&str>, description: &str, properties: &[(&str, PropertyValue)]) -> ProfileDefinition {
+        ProfileDefinition {
+            inherits: inherits.map(str::to_string),
+            description: Some(description.to_string()),
+            properties: properties.iter().map(|(k, v)| (k.to_string(), v.clone())).collect(),
+        }
+    }
+    use PropertyValue::{Bool, Int, Text};
+    let text = |v: &str| Text(v.to_string());
+    BTreeMap::from([
+        (
+            "balanced".to_string(),
+            profile(
+                None,
+                "기본 추천",
+                &[
+                    ("deband", Bool(true)),
+                    ("deband-iterations", Int(2)),
+                    ("dscale", text("mitchell")),
+                    ("scale", text("ewa_lanczossharp")),
+                    ("cscale", text("spline36")),
+                    ("sigmoid-upscaling", Bool(true)),
+                    ("interpolation", Bool(false)),
+                    ("video-sync", text("audio")),
+                ],
+            ),
+        ),
+        (
+            "quality".to_string(),
+            profile(
+                Some("balanced"),
+                "고화질(부하 증가)",
+                &[
+                    ("deband-iterations", Int(3)),
+                    ("interpolation", Bool(true)),
+                    ("video-sync", text("display-resample")),
+                    ("tscale", text("oversample")),
+                ],
+            ),
+        ),
+        (
+            "smooth".to_string(),
+            profile(
+                Some("balanced"),
+                "부드러움 우선",
+                &[
+                    ("scale", text("bilinear")),
+                    ("cscale", text("bilinear")),
+                    ("deband", Bool(false)),
+                    ("interpolation", Bool(true)),
+                    ("video-sync", text("display-resample")),
+                    ("tscale", text("oversample")),
+                ],
+            ),
+        ),
+    ])
+}
+
+/// Parses a profile file; the format follows the extension (toml, yaml/yml).
+pub fn parse_profiles(path: &Path, text: &str) -> FlashplexResult<BTreeMap<String, ProfileDefinition>> {
+    let name = path.file_name().and_then(|n| n.to_str()).unwrap_or("quality profiles");
+    let file: ProfileFile = match path.extension().and_then(|e| e.to_str()) {
+        Some("toml") => toml::from_str(text).map_err(|e| FlashplexError::invalid_input(format!("{}: {}", name, e)))?,
+        Some("yaml") | Some("yml") => {
+            serde_yaml::from_str(text).map_err(|e| FlashplexError::invalid_input(format!("{}: {}", name, e)))?
+        }
+        _ => return Err(FlashplexError::invalid_input(format!("{}: unsupported format", name))),
+    };
+    if let Some(empty) = file.profiles.keys().find(|k| k.trim().is_empty()) {
+        return Err(FlashplexError::invalid_input(format!("{}: invalid profile name '{}'", name, empty)));
+    }
+    Ok(file.profiles)
+}
+
+pub struct QualityProfiles {
+    dir: PathBuf,
+    builtin: BTreeMap<String, ProfileDefinition>,
+    user: BTreeMap<String, ProfileDefinition>,
+    /// File and mtime `user` was read from.
+    loaded: Option<(PathBuf, Option<SystemTime>)>,
+    file_error: Option<String>,
+}
+
+impl QualityProfiles {
+    pub fn open(dir: PathBuf) -> Self {
+        let mut profiles = QualityProfiles {
+            dir,
+            builtin: builtin_profiles(),
+            user: BTreeMap::new(),
+            loaded: None,
+            file_error: None,
+        };
+        profiles.refresh();
+        profiles
+    }
+
+    /// Re-reads the user file when it appeared, changed or went away.
+    pub fn refresh(&mut self) {
+        let current = PROFILE_FILES.iter().map(|f| self.dir.join(f)).find(|p| p.exists()).map(|p| {
+            let modified = std::fs::metadata(&p).and_then(|m| m.modified()).ok();
+            (p, modified)
+        });
+        if current == self.loaded {
+            return;
+        }
+        self.loaded = current.clone();
+        let Some((path, _)) = current else {
+            self.user.clear();
+            self.file_error = None;
+            return;
+        };
+        let parsed = std::fs::read_to_string(&path)
+            .map_err(|e| FlashplexError::internal(format!("Failed to read {}: {}", path.display(), e)))
+            .and_then(|text| parse_profiles(&path, &text));
+        match parsed {
+            Ok(user) => {
+                log_info!("QUALITY", "Loaded {} user profile(s) from {}", user.len(), path.display());
+                self.user = user;
+                self.file_error = None;
+            }
+            Err(e) => {
+                log_warn!("QUALITY", "Keeping previous user profiles: {}", e);
+                self.file_error = Some(e.to_string());
+            }
+        }
+    }
+
+    // User profiles shadow built-ins of the same name.
+    fn definition(&self, name: &str) -> Option<&ProfileDefinition> {
+        self.user.get(name).or_else(|| self.builtin.get(name))
+    }
+
+    pub fn resolve(&self, name: &str) -> FlashplexResult<QualityProfile> {
+        let mut chain: Vec<String> = Vec::new();
+        let mut current = Some(name.to_string());
+        while let Some(profile) = current {
+            if chain.contains(&profile) {
+                return Err(FlashplexError::invalid_input(format!(
+                    "quality profile '{}': inheritance cycle at '{}'",
+                    name, profile
+                )));
+            }
+            if chain.len() >= MAX_INHERITANCE_DEPTH {
+                return Err(FlashplexError::invalid_input(format!(
+                    "quality profile '{}': inherits more than {} levels",
+                    name, MAX_INHERITANCE_DEPTH
+                )));
+            }
+            let definition = self.definition(&profile).ok_or_else(|| {
+                FlashplexError::invalid_input(format!("unknown quality profile '{}'", profile))
+            })?;
+            current = definition.inherits.clone();
+            chain.push(profile);
+        }
+        chain.reverse();
+
+        let mut properties = BTreeMap::new();
+        for profile in &chain {
+            if let Some(definition) = self.definition(profile) {
+                properties.extend(definition.properties.clone());
+            }
+        }
+        Ok(QualityProfile {
+            name: name.to_string(),
+            description: self.definition(name).and_then(|d| d.description.clone()),
+            chain,
+            properties,
+        })
+    }
+
+    pub fn list(&self, active: &str) -> QualityProfileList {
+        let mut names: Vec<&String> = self.builtin.keys().collect();
+        names.extend(self.user.keys().filter(|k| !self.builtin.contains_key(*k)));
+        let profiles = names
+            .into_iter()
+            .map(|name| {
+                let definition = self.definition(name).cloned().unwrap_or_default();
+                let (properties, error) = match self.resolve(name) {
+                    Ok(resolved) => (resolved.properties, None),
+                    Err(e) => (BTreeMap::new(), Some(e.to_string())),
+                };
+                QualityProfileInfo {
+                    name: name.clone(),
+                    description: definition.description,
+                    inherits: definition.inherits,
+                    builtin: !self.user.contains_key(name),
+                    properties,
+                    error,
+                }
+            })
+            .collect();
+        QualityProfileList {
+            active: active.to_string(),
+            file: self.loaded.as_ref().map(|(p, _)| p.display().to_string()),
+            file_error: self.file_error.clone(),
+            profiles,
+        }
+    }
+}
+
+pub struct QualityState(pub Arc<Mutex<QualityProfiles>>);
+
+impl QualityState {
+    pub fn open(app: &tauri::AppHandle) -> Self {
+        use tauri::Manager;
+        let dir = app
+            .path()
+            .app_config_dir()
+            .unwrap_or_else(|_| std::env::temp_dir().join("flashplex"));
+        QualityState(Arc::new(Mutex::new(QualityProfiles::open(dir))))
+    }
+
+    pub fn resolve(&self, name: &str) -> FlashplexResult<QualityProfile> {
+        let mut profiles = self.0.lock()?;
+        profiles.refresh();
+        profiles.resolve(name)
+    }
+
+    /// Like `resolve`, but a missing or broken profile falls back to the default.
+    pub fn resolve_or_default(&self, name: &str) -> FlashplexResult<QualityProfile> {
+        self.resolve(name).or_else(|e| {
+            log_warn!("QUALITY", "{}; using {}", e, DEFAULT_QUALITY_PROFILE);
+            self.resolve(DEFAULT_QUALITY_PROFILE)
+        })
+    }
+}
+
+// Commands
+
+#[tauri::command(rename_all = "snake_case")]
+pub fn list_quality_profiles(
+    state: tauri::State<'_, QualityState>,
+    settings: tauri::State<'_, crate::settings::SettingsState>,
+) -> FlashplexResult<QualityProfileList> {
+    let active = settings.snapshot()?.quality_profile;
+    let mut profiles = state.0.lock()?;
+    profiles.refresh();
+    Ok(profiles.list(&active))
+}
+
+/// Applies `profile` to the open player (if any; `player_id` defaults to the main one) and emits `quality-profile-changed`.
+/// Unknown profiles are an error instead of silently becoming "balanced".
+#[tauri::command(rename_all = "snake_case")]
+pub async fn set_quality_profile(
+    app: tauri::AppHandle,
+    player: tauri::State<'_, super::PlayerState>,
+    state: tauri::State<'_, QualityState>,
+    profile: String,
+    player_id: Option<String>,
+) -> FlashplexResult<QualityApplyReport> {
+    use tauri::Emitter;
+    let resolved = state.resolve(&normalize_quality_profile(&profile))?;
+    let report = player.query(player_id, "set_quality_profile", move |player| match player {
+        Some(player) => player.set_quality_profile(&resolved),
+        None => QualityApplyReport {
+            profile: resolved.name.clone(),
+            applied: 0,
+            rejected: Vec::new(),
+        },
+    }).await?;
+    let _ = app.emit(QUALITY_PROFILE_CHANGED_EVENT, report.clone());
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    const ANIME_TOML: &str = r#"
+[profiles.anime]
+inherits = "quality"
+description = "Stronger deband"
+properties = { deband-iterations = 4, deband-grain = 0, hdr-compute-peak = "auto" }
+"#;
+
+    fn scratch(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("flashplex-quality-{}-{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn with_user(text: &str) -> QualityProfiles {
+        // No profile file there: only the built-ins plus `text`.
+        let mut profiles = QualityProfiles::open(PathBuf::from("/nonexistent/flashplex"));
+        profiles.user = parse_profiles(Path::new("quality_profiles.toml"), text).unwrap();
+        profiles
+    }
+
+    fn err_message(result: FlashplexResult<QualityProfile>) -> String {
+        result.unwrap_err().to_string()
+    }
+
+    #[test]
+    fn parses_toml_and_yaml() {
+        let toml = parse_profiles(Path::new("quality_profiles.toml"), ANIME_TOML).unwrap();
+        let yaml = parse_profiles(
+            Path::new("quality_profiles.yml"),
+            "profiles:\n  anime:\n    inherits: quality\n    description: Stronger deband\n    properties:\n      deband-iterations: 4\n      deband-grain: 0\n      hdr-compute-peak: auto\n",
+        )
+        .unwrap();
+        for parsed in [&toml, &yaml] {
+            let anime = &parsed["anime"];
+            assert_eq!(anime.inherits.as_deref(), Some("quality"));
+            assert_eq!(anime.properties["deband-iterations"], PropertyValue::Int(4));
+            assert_eq!(anime.properties["hdr-compute-peak"], PropertyValue::Text("auto".to_string()));
+        }
+
+        let err = parse_profiles(Path::new("quality_profiles.json"), "{}").unwrap_err();
+        assert!(err.to_string().contains("unsupported format"), "{}", err);
+        let err = parse_profiles(Path::new("quality_profiles.toml"), "[profiles.\" \"]\n").unwrap_err();
+        assert!(err.to_string().contains("invalid profile name"), "{}", err);
+    }
+
+    #[test]
+    fn inherited_properties_are_overridden_by_the_child() {
+        let anime = with_user(ANIME_TOML).resolve("anime").unwrap();
+        assert_eq!(anime.chain, ["balanced", "quality", "anime"]);
+        assert_eq!(anime.description.as_deref(), Some("Stronger deband"));
+        assert_eq!(anime.properties["deband-iterations"], PropertyValue::Int(4));
+        // From quality and balanced.
+        assert_eq!(anime.properties["interpolation"], PropertyValue::Bool(true));
+        assert_eq!(anime.properties["dscale"], PropertyValue::Text("mitchell".to_string()));
+    }
+
+    #[test]
+    fn user_profile_overrides_a_builtin() {
+        let profiles = with_user("[profiles.balanced]\nproperties = { deband = false, scale = \"spline36\" }\n");
+        let balanced = profiles.resolve("balanced").unwrap();
+        assert_eq!(balanced.chain, ["balanced"]);
+        assert_eq!(balanced.properties.len(), 2);
+        // Builtins inheriting it see the user version.
+        let quality = profiles.resolve("quality").unwrap();
+        assert_eq!(quality.properties["deband"], PropertyValue::Bool(false));
+        assert!(!quality.properties.contains_key("dscale"));
+
+        let list = profiles.list("balanced");
+        let info = list.profiles.iter().find(|p| p.name == "balanced").unwrap();
+        assert!(!info.builtin);
+    }
+
+    #[test]
+    fn cycles_and_deep_chains_are_errors() {
+        let profiles = with_user("[profiles.a]\ninherits = \"b\"\n[profiles.b]\ninherits = \"a\"\n");
+        assert!(err_message(profiles.resolve("a")).contains("inheritance cycle at 'a'"));
+
+        let deep: String = (0..MAX_INHERITANCE_DEPTH)
+            .map(|i| format!("[profiles.p{}]\ninherits = \"p{}\"\n", i, i + 1))
+            .collect();
+        let profiles = with_user(&format!("{}[profiles.p{}]\n", deep, MAX_INHERITANCE_DEPTH));
+        assert!(err_message(profiles.resolve("p0")).contains("more than 8 levels"));
+        assert!(profiles.resolve("p1").is_ok());
+
+        let list = profiles.list("balanced");
+        let info = list.profiles.iter().find(|p| p.name == "p0").unwrap();
+        assert!(info.error.is_some() && info.properties.is_empty());
+    }
+
+    #[test]
+    fn unknown_profiles_are_errors() {
+        let profiles = with_user("[profiles.orphan]\ninherits = \"missing\"\n");
+        assert!(err_message(profiles.resolve("nope")).contains("unknown quality profile 'nope'"));
+        assert!(err_message(profiles.resolve("orphan")).contains("unknown quality profile 'missing'"));
+    }
+
+    #[test]
+    fn broken_file_keeps_the_previous_profiles() {
+        let dir = scratch("reload");
+        let file = dir.join("quality_profiles.toml");
+        std::fs::write(&file, ANIME_TOML).unwrap();
+        let mut profiles = QualityProfiles::open(dir.clone());
+        assert!(profiles.resolve("anime").is_ok());
+
+        std::fs::write(&file, "[profiles.anime\n").unwrap();
+        // Make the change visible even on coarse mtime filesystems.
+        let later = SystemTime::now() + Duration::from_secs(5);
+        std::fs::File::options().write(true).open(&file).unwrap().set_modified(later).unwrap();
+        profiles.refresh();
+        assert!(profiles.resolve("anime").is_ok());
+        let list = profiles.list("anime");
+        assert!(list.file_error.as_deref().unwrap_or_default().contains("quality_profiles.toml"));
+
+        std::fs::remove_file(&file).unwrap();
+        profiles.refresh();
+        assert!(profiles.resolve("anime").is_err());
+        assert!(profiles.list("balanced").file_error.is_none());
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,189 @@
+// Play queue on top of mpv's own playlist.
+//
+// mpv owns order and the current entry, so auto-advance keeps working while the
+// webview is busy or reloading. We only keep per-URL metadata (title, subtitle,
+// start position) that mpv's playlist can't carry.
+use super::PlayerState;
+use crate::error::FlashplexResult;
+use crate::gds::GdsState;
+use serde::{Deserialize, Serialize};
+#[cfg(any(target_os = "macos", target_os = "linux"))]
+use std::collections::HashMap;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RepeatMode {
+    #[default]
+    Off,
+    One,
+    All,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QueueItem {
+    /// Leave empty for GDS items; the signed URL is built from `path`.
+    #[serde(default)]
+    pub url: String,
+    #[serde(default)]
+    pub title: String,
+    #[serde(default)]
+    pub subtitle_url: Option<String>,
+    #[serde(default)]
+    pub start_pos: Option<f64>,
+    /// GDS path and source of the item, so the UI can map entries back to the library.
+    #[serde(default)]
+    pub path: Option<String>,
+    #[serde(default)]
+    pub source_id: Option<u32>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct QueueEntry {
+    pub index: i64,
+    pub current: bool,
+    #[serde(flatten)]
+    pub item: QueueItem,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct QueueSnapshot {
+    pub entries: Vec<QueueEntry>,
+    /// -1 when nothing is playing.
+    pub current: i64,
+    pub shuffle: bool,
+    pub repeat: RepeatMode,
+}
+
+/// Metadata for entries in mpv's playlist, keyed by URL (mpv's `filename`).
+#[cfg(any(target_os = "macos", target_os = "linux"))]
+#[derive(Default)]
+pub struct QueueMeta {
+    pub items: HashMap<String, QueueItem>,
+    pub shuffle: bool,
+    pub repeat: RepeatMode,
+}
+
+#[cfg(any(target_os = "macos", target_os = "linux"))]
+pub type SharedQueue = std::sync::Arc<std::sync::Mutex<QueueMeta>>;
+
+#[cfg(any(target_os = "macos", target_os = "linux"))]
+impl QueueMeta {
+    pub fn subtitle_for(&self, url: &str) -> Option<String> {
+        self.items
+            .get(url)
+            .and_then(|item| item.subtitle_url.clone())
+            .filter(|s| !s.is_empty())
+    }
+}
+
+impl QueueItem {
+    fn resolve_urls(&mut self, gds: &GdsState) -> FlashplexResult<()> {
+        let (url, subtitle_url) = gds.playback_urls(
+            Some(std::mem::take(&mut self.url)),
+            self.subtitle_url.take(),
+            self.path.as_deref(),
+            self.source_id,
+        )?;
+        self.url = url;
+        self.subtitle_url = subtitle_url;
+        Ok(())
+    }
+}
+
+// Commands
+
+#[tauri::command(rename_all = "snake_case")]
+pub async fn get_queue(state: tauri::State<'_, PlayerState>, player_id: Option<String>) -> FlashplexResult<QueueSnapshot> {
+    state.query(player_id, "get_queue", |player| player.map(|p| p.queue()).unwrap_or_default()).await
+}
+
+#[tauri::command(rename_all = "snake_case")]
+pub async fn queue_enqueue(
+    state: tauri::State<'_, PlayerState>,
+    gds: tauri::State<'_, GdsState>,
+    player_id: Option<String>,
+    mut items: Vec<QueueItem>,
+) -> FlashplexResult<QueueSnapshot> {
+    log_info!("QUEUE", "enqueue {} item(s)", items.len());
+    for item in &mut items {
+        item.resolve_urls(&gds)?;
+    }
+    state.with(player_id, "queue_enqueue", move |player| {
+        player.enqueue(&items)?;
+        Ok(player.queue())
+    }).await
+}
+
+#[tauri::command(rename_all = "snake_case")]
+pub async fn queue_insert_next(
+    state: tauri::State<'_, PlayerState>,
+    gds: tauri::State<'_, GdsState>,
+    player_id: Option<String>,
+    mut item: QueueItem,
+) -> FlashplexResult<QueueSnapshot> {
+    log_info!("QUEUE", "insert next: {}", item.title);
+    item.resolve_urls(&gds)?;
+    state.with(player_id, "queue_insert_next", move |player| {
+        player.insert_next(&item)?;
+        Ok(player.queue())
+    }).await
+}
+
+#[tauri::command(rename_all = "snake_case")]
+pub async fn queue_remove(
+    state: tauri::State<'_, PlayerState>,
+    player_id: Option<String>,
+    index: i64,
+) -> FlashplexResult<QueueSnapshot> {
+    state.with(player_id, "queue_remove", move |player| {
+        player.remove_from_queue(index)?;
+        Ok(player.queue())
+    }).await
+}
+
+#[tauri::command(rename_all = "snake_case")]
+pub async fn queue_move(
+    state: tauri::State<'_, PlayerState>,
+    player_id: Option<String>,
+    from: i64,
+    to: i64,
+) -> FlashplexResult<QueueSnapshot> {
+    state.with(player_id, "queue_move", move |player| {
+        player.move_in_queue(from, to)?;
+        Ok(player.queue())
+    }).await
+}
+
+#[tauri::command(rename_all = "snake_case")]
+pub async fn queue_next(state: tauri::State<'_, PlayerState>, player_id: Option<String>) -> FlashplexResult<()> {
+    state.with(player_id, "queue_next", |player| player.play_next()).await
+}
+
+#[tauri::command(rename_all = "snake_case")]
+pub async fn queue_previous(state: tauri::State<'_, PlayerState>, player_id: Option<String>) -> FlashplexResult<()> {
+    state.with(player_id, "queue_previous", |player| player.play_previous()).await
+}
+
+#[tauri::command(rename_all = "snake_case")]
+pub async fn queue_set_shuffle(
+    state: tauri::State<'_, PlayerState>,
+    player_id: Option<String>,
+    shuffle: bool,
+) -> FlashplexResult<QueueSnapshot> {
+    state.with(player_id, "queue_set_shuffle", move |player| {
+        player.set_shuffle(shuffle)?;
+        Ok(player.queue())
+    }).await
+}
+
+#[tauri::command(rename_all = "snake_case")]
+pub async fn queue_set_repeat(
+    state: tauri::State<'_, PlayerState>,
+    player_id: Option<String>,
+    mode: RepeatMode,
+) -> FlashplexResult<QueueSnapshot> {
+    state.with(player_id, "queue_set_repeat", move |player| {
+        player.set_repeat(mode)?;
+        Ok(player.queue())
+    }).await
+}
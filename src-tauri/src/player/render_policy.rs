@@ -0,0 +1,453 @@
+// Render-path selection for the embedded player.
+//
+// mpv can draw into our window in several ways (CAMetalLayer or NSView wid,
+// gpu or gpu-next, metal / opengl / vulkan) and which one works depends on the
+// machine. A `RenderPathPolicy` lists the candidates in order; the player tries
+// them until libmpv initializes with one and remembers that one, so the next
+// launch starts with it.
+//
+// The policy comes from the `render_policy` setting, else `render_policy.json`
+// in the app config dir, else the built-in default for this OS/arch; the old
+// MPV_WID_EXPERIMENT / MPV_LAYER_VO_LEGACY switches still adjust it. Parsing and
+// ordering don't touch Cocoa or libmpv.
+use crate::error::{FlashplexError, FlashplexResult};
+use crate::storage::write_atomic;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashSet};
+use std::path::PathBuf;
+
+const POLICY_FILE: &str = "render_policy.json";
+const LAST_GOOD_FILE: &str = "render_path.json";
+
+/// What mpv's `wid` points at.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum WidKind {
+    /// CAMetalLayer hosted by our container view (macOS).
+    Layer,
+    /// The container NSView itself (macOS).
+    Nsview,
+    /// No wid; mpv opens its own window (Linux).
+    Window,
+}
+
+impl WidKind {
+    pub fn label(self) -> &'static str {
+        match self {
+            WidKind::Layer => "Layer",
+            WidKind::Nsview => "NSView",
+            WidKind::Window => "Window",
+        }
+    }
+}
+
+/// One candidate: the mpv options set before `mpv_initialize`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RenderProfile {
+    pub name: String,
+    #[serde(default = "default_wid")]
+    pub wid: WidKind,
+    pub vo: String,
+    #[serde(default)]
+    pub gpu_api: Option<String>,
+    #[serde(default)]
+    pub gpu_context: Option<String>,
+    #[serde(default)]
+    pub hwdec: Option<String>,
+    /// Any further options, e.g. `force-window`.
+    #[serde(default)]
+    pub options: BTreeMap<String, String>,
+}
+
+fn default_wid() -> WidKind {
+    WidKind::Window
+}
+
+fn default_true() -> bool {
+    true
+}
+
+impl RenderProfile {
+    fn new(name: &str, wid: WidKind, vo: &str, gpu_api: Option<&str>, gpu_context: Option<&str>, hwdec: Option<&str>) -> Self {
+        RenderProfile {
+            name: name.to_string(),
+            wid,
+            vo: vo.to_string(),
+            gpu_api: gpu_api.map(str::to_string),
+            gpu_context: gpu_context.map(str::to_string),
+            hwdec: hwdec.map(str::to_string),
+            options: BTreeMap::new(),
+        }
+    }
+
+    fn with_option(mut self, key: &str, value: &str) -> Self {
+        self.options.insert(key.to_string(), value.to_string());
+        self
+    }
+
+    /// Vulkan goes through MoltenVK on macOS, which needs VK_ICD_FILENAMES.
+    #[cfg_attr(not(target_os = "macos"), allow(dead_code))]
+    pub fn uses_vulkan(&self) -> bool {
+        self.gpu_api.as_deref() == Some("vulkan") || self.gpu_context.as_deref() == Some("moltenvk")
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RenderPathPolicy {
+    /// Start with the last candidate that worked on this machine.
+    #[serde(default = "default_true")]
+    pub remember_last_good: bool,
+    pub candidates: Vec<RenderProfile>,
+}
+
+impl RenderPathPolicy {
+    /// Built-in candidates for `os`/`arch` (`std::env::consts` values).
+    pub fn for_platform(os: &str, arch: &str) -> Self {
+        let candidates = match (os, arch) {
+            // Intel: cocoa (OpenGL) only. MoltenVK on an NSView wid crashes in
+            // vkCreateMetalSurfaceEXT, so Vulkan is never tried. nsview-gpu leaves
+            // gpu-api to mpv, as every Intel attempt did before policies existed;
+            // videotoolbox stays on everywhere, mpv falls back to software decoding itself.
+            ("macos", "x86_64") => vec![
+                RenderProfile::new("nsview-opengl", WidKind::Nsview, "gpu", Some("opengl"), Some("cocoa"), Some("videotoolbox")),
+                RenderProfile::new("nsview-gpu", WidKind::Nsview, "gpu", None, Some("cocoa"), Some("videotoolbox")),
+                RenderProfile::new("layer-opengl", WidKind::Layer, "gpu", Some("opengl"), Some("cocoa"), Some("videotoolbox")),
+            ],
+            ("macos", _) => vec![
+                RenderProfile::new("layer-metal", WidKind::Layer, "gpu-next", Some("metal"), Some("cocoa"), Some("videotoolbox")),
+                RenderProfile::new("layer-vulkan", WidKind::Layer, "gpu-next", Some("vulkan"), Some("moltenvk"), Some("videotoolbox")),
+                RenderProfile::new("nsview-opengl", WidKind::Nsview, "gpu", Some("opengl"), Some("cocoa"), Some("videotoolbox")),
+            ],
+            _ => vec![
+                RenderProfile::new("linux-window", WidKind::Window, "gpu", None, None, Some("auto-safe"))
+                    .with_option("force-window", "yes"),
+            ],
+        };
+        RenderPathPolicy { remember_last_good: true, candidates }
+    }
+
+    /// No video/audio output (FLASHPLEX_HEADLESS on Linux).
+    pub fn headless() -> Self {
+        RenderPathPolicy {
+            remember_last_good: false,
+            candidates: vec![RenderProfile::new("linux-headless", WidKind::Window, "null", None, None, None)
+                .with_option("ao", "null")],
+        }
+    }
+
+    pub fn from_json(text: &str) -> FlashplexResult<Self> {
+        let policy: RenderPathPolicy = serde_json::from_str(text)
+            .map_err(|e| FlashplexError::invalid_input(format!("render policy: {}", e)))?;
+        policy.validate()?;
+        Ok(policy)
+    }
+
+    pub fn validate(&self) -> FlashplexResult<()> {
+        let invalid = |msg: String| Err(FlashplexError::invalid_input(format!("render policy: {}", msg)));
+        if self.candidates.is_empty() {
+            return invalid("no candidates".to_string());
+        }
+        let mut names = HashSet::new();
+        for profile in &self.candidates {
+            if profile.name.trim().is_empty() {
+                return invalid("candidate without a name".to_string());
+            }
+            if !names.insert(profile.name.as_str()) {
+                return invalid(format!("duplicate candidate '{}'", profile.name));
+            }
+            if profile.vo.trim().is_empty() {
+                return invalid(format!("'{}' has no vo", profile.name));
+            }
+            // wid and the config/script options are owned by the player (see mpv_config.rs).
+            if let Some(key) = profile
+                .options
+                .keys()
+                .find(|k| k.is_empty() || matches!(k.as_str(), "wid" | "config" | "config-dir" | "load-scripts" | "scripts"))
+            {
+                return invalid(format!("'{}' sets reserved option '{}'", profile.name, key));
+            }
+        }
+        Ok(())
+    }
+
+    /// Candidates in the order to try: the remembered one first, then the rest as listed.
+    pub fn ordered(&self, last_good: Option<&str>) -> Vec<&RenderProfile> {
+        let first = last_good
+            .filter(|_| self.remember_last_good)
+            .and_then(|name| self.candidates.iter().find(|p| p.name == name));
+        first
+            .into_iter()
+            .chain(self.candidates.iter().filter(|p| Some(*p) != first))
+            .collect()
+    }
+
+    #[cfg_attr(not(target_os = "macos"), allow(dead_code))]
+    pub fn needs_vulkan(&self) -> bool {
+        self.candidates.iter().any(RenderProfile::uses_vulkan)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PolicySource {
+    Settings,
+    File,
+    Default,
+    Environment,
+}
+
+impl PolicySource {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            PolicySource::Settings => "settings",
+            PolicySource::File => "file",
+            PolicySource::Default => "default",
+            PolicySource::Environment => "environment",
+        }
+    }
+}
+
+/// Picks the first valid policy: setting, then config file text, then the platform default.
+pub fn resolve(
+    setting: Option<&RenderPathPolicy>,
+    file: Option<&str>,
+    os: &str,
+    arch: &str,
+) -> (RenderPathPolicy, PolicySource) {
+    if let Some(policy) = setting {
+        match policy.validate() {
+            Ok(()) => return (policy.clone(), PolicySource::Settings),
+            Err(e) => log_warn!("RENDER", "Ignoring render_policy setting: {}", e),
+        }
+    }
+    if let Some(text) = file {
+        match RenderPathPolicy::from_json(text) {
+            Ok(policy) => return (policy, PolicySource::File),
+            Err(e) => log_warn!("RENDER", "Ignoring {}: {}", POLICY_FILE, e),
+        }
+    }
+    (RenderPathPolicy::for_platform(os, arch), PolicySource::Default)
+}
+
+/// Applies the debugging switches from before render policies existed:
+/// `MPV_WID_EXPERIMENT=nsview|layer` keeps only that wid's candidates, and
+/// `MPV_LAYER_VO_LEGACY=1` replaces the layer candidates with gpu + vulkan/moltenvk
+/// (not on Intel). None when neither changed anything.
+pub fn env_override(
+    policy: &RenderPathPolicy,
+    wid_experiment: Option<&str>,
+    layer_legacy: Option<&str>,
+    arch: &str,
+) -> Option<RenderPathPolicy> {
+    let mut overridden = policy.clone();
+    let mut applied = false;
+    if let Some(value) = wid_experiment.map(str::trim).filter(|v| !v.is_empty()) {
+        let kind = match value.to_ascii_lowercase().as_str() {
+            "nsview" => Some(WidKind::Nsview),
+            "layer" => Some(WidKind::Layer),
+            _ => None,
+        };
+        let kept: Vec<RenderProfile> = match kind {
+            Some(kind) => policy.candidates.iter().filter(|p| p.wid == kind).cloned().collect(),
+            None => Vec::new(),
+        };
+        if kept.is_empty() {
+            log_warn!("RENDER", "Ignoring MPV_WID_EXPERIMENT={}: no matching candidate", value);
+        } else {
+            overridden.candidates = kept;
+            applied = true;
+        }
+    }
+    let legacy = layer_legacy.map(|v| v.trim().to_ascii_lowercase());
+    if matches!(legacy.as_deref(), Some("1") | Some("true")) {
+        let first_layer = overridden.candidates.iter().position(|p| p.wid == WidKind::Layer);
+        match first_layer {
+            _ if arch == "x86_64" => log_warn!("RENDER", "Ignoring MPV_LAYER_VO_LEGACY: Vulkan is never tried on Intel"),
+            None => log_warn!("RENDER", "Ignoring MPV_LAYER_VO_LEGACY: no layer candidate"),
+            Some(index) => {
+                overridden.candidates.retain(|p| p.wid != WidKind::Layer);
+                overridden.candidates.insert(
+                    index,
+                    RenderProfile::new("layer-vulkan-legacy", WidKind::Layer, "gpu", Some("vulkan"), Some("moltenvk"), Some("videotoolbox")),
+                );
+                applied = true;
+            }
+        }
+    }
+    applied.then_some(overridden)
+}
+
+/// The policy for the next player, from the current settings and config dir
+/// (and the `env_override` switches).
+pub fn load(app: &tauri::AppHandle) -> (RenderPathPolicy, PolicySource) {
+    use tauri::Manager;
+    let setting = app
+        .try_state::<crate::settings::SettingsState>()
+        .and_then(|state| state.snapshot().ok())
+        .and_then(|settings| settings.render_policy);
+    let file = app
+        .path()
+        .app_config_dir()
+        .ok()
+        .and_then(|dir| std::fs::read_to_string(dir.join(POLICY_FILE)).ok());
+    let (policy, source) = resolve(setting.as_ref(), file.as_deref(), std::env::consts::OS, std::env::consts::ARCH);
+    let overridden = env_override(
+        &policy,
+        std::env::var("MPV_WID_EXPERIMENT").ok().as_deref(),
+        std::env::var("MPV_LAYER_VO_LEGACY").ok().as_deref(),
+        std::env::consts::ARCH,
+    );
+    match overridden {
+        Some(policy) => {
+            log_info!("RENDER", "Render policy overridden by environment");
+            (policy, PolicySource::Environment)
+        }
+        None => (policy, source),
+    }
+}
+
+// Last working candidate, per machine (app data dir) and arch.
+#[derive(Debug, Serialize, Deserialize)]
+struct LastGood {
+    arch: String,
+    profile: String,
+}
+
+fn last_good_file(app: &tauri::AppHandle) -> Option<PathBuf> {
+    use tauri::Manager;
+    app.path().app_data_dir().ok().map(|dir| dir.join(LAST_GOOD_FILE))
+}
+
+pub fn last_good(app: &tauri::AppHandle) -> Option<String> {
+    let text = std::fs::read_to_string(last_good_file(app)?).ok()?;
+    let last: LastGood = serde_json::from_str(&text).ok()?;
+    // A data dir carried over from another arch (e.g. Rosetta) doesn't count.
+    (last.arch == std::env::consts::ARCH).then_some(last.profile)
+}
+
+pub fn remember(app: &tauri::AppHandle, profile: &str) {
+    let Some(file) = last_good_file(app) else {
+        return;
+    };
+    let last = LastGood {
+        arch: std::env::consts::ARCH.to_string(),
+        profile: profile.to_string(),
+    };
+    let result = serde_json::to_vec_pretty(&last)
+        .map_err(FlashplexError::from)
+        .and_then(|bytes| write_atomic(&file, &bytes));
+    if let Err(e) = result {
+        log_warn!("RENDER", "Could not remember render path {}: {}", profile, e);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn names(profiles: &[&RenderProfile]) -> Vec<String> {
+        profiles.iter().map(|p| p.name.clone()).collect()
+    }
+
+    fn policy(names: &[&str]) -> RenderPathPolicy {
+        RenderPathPolicy {
+            remember_last_good: true,
+            candidates: names
+                .iter()
+                .map(|name| RenderProfile::new(name, WidKind::Window, "gpu", None, None, None))
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn platform_defaults() {
+        let intel = RenderPathPolicy::for_platform("macos", "x86_64");
+        assert_eq!(names(&intel.ordered(None)), ["nsview-opengl", "nsview-gpu", "layer-opengl"]);
+        assert!(!intel.needs_vulkan());
+        assert!(intel.candidates.iter().all(|p| p.hwdec.as_deref() == Some("videotoolbox")));
+        assert_eq!(intel.candidates[1].gpu_api, None);
+
+        let arm = RenderPathPolicy::for_platform("macos", "aarch64");
+        assert_eq!(arm.candidates[0].name, "layer-metal");
+        assert!(arm.needs_vulkan());
+
+        let linux = RenderPathPolicy::for_platform("linux", "x86_64");
+        assert_eq!(linux.candidates.len(), 1);
+        assert_eq!(linux.candidates[0].wid, WidKind::Window);
+        assert_eq!(linux.candidates[0].options.get("force-window").map(String::as_str), Some("yes"));
+
+        for policy in [intel, arm, linux, RenderPathPolicy::headless()] {
+            policy.validate().unwrap();
+        }
+    }
+
+    #[test]
+    fn resolve_prefers_setting_then_file_then_default() {
+        let setting = policy(&["from-setting"]);
+        let file = r#"{"candidates":[{"name":"from-file","vo":"gpu"}]}"#;
+
+        let (resolved, source) = resolve(Some(&setting), Some(file), "linux", "x86_64");
+        assert_eq!((resolved.candidates[0].name.as_str(), source), ("from-setting", PolicySource::Settings));
+
+        let (resolved, source) = resolve(Some(&policy(&[])), Some(file), "linux", "x86_64");
+        assert_eq!((resolved.candidates[0].name.as_str(), source), ("from-file", PolicySource::File));
+        assert!(resolved.remember_last_good);
+
+        let (resolved, source) = resolve(None, Some("{not json"), "macos", "x86_64");
+        assert_eq!(source, PolicySource::Default);
+        assert_eq!(resolved, RenderPathPolicy::for_platform("macos", "x86_64"));
+    }
+
+    #[test]
+    fn env_override_filters_and_swaps_candidates() {
+        let arm = RenderPathPolicy::for_platform("macos", "aarch64");
+        let intel = RenderPathPolicy::for_platform("macos", "x86_64");
+        assert_eq!(env_override(&arm, None, None, "aarch64"), None);
+        assert_eq!(env_override(&arm, Some(" "), Some("0"), "aarch64"), None);
+
+        let nsview = env_override(&arm, Some("NSView"), None, "aarch64").unwrap();
+        assert_eq!(names(&nsview.ordered(None)), ["nsview-opengl"]);
+        let layer = env_override(&intel, Some("layer"), None, "x86_64").unwrap();
+        assert_eq!(names(&layer.ordered(None)), ["layer-opengl"]);
+        // Unknown values and kinds the policy doesn't have are ignored.
+        assert_eq!(env_override(&arm, Some("window"), None, "aarch64"), None);
+        assert_eq!(env_override(&policy(&["a"]), Some("layer"), None, "aarch64"), None);
+
+        let legacy = env_override(&arm, None, Some("true"), "aarch64").unwrap();
+        assert_eq!(names(&legacy.ordered(None)), ["layer-vulkan-legacy", "nsview-opengl"]);
+        assert!(legacy.needs_vulkan());
+        legacy.validate().unwrap();
+        assert_eq!(env_override(&intel, None, Some("1"), "x86_64"), None);
+        assert_eq!(env_override(&arm, Some("nsview"), Some("1"), "aarch64"), Some(nsview));
+    }
+
+    #[test]
+    fn ordered_starts_with_last_good() {
+        let mut p = policy(&["a", "b", "c"]);
+        assert_eq!(names(&p.ordered(Some("c"))), ["c", "a", "b"]);
+        assert_eq!(names(&p.ordered(Some("gone"))), ["a", "b", "c"]);
+        assert_eq!(names(&p.ordered(None)), ["a", "b", "c"]);
+
+        p.remember_last_good = false;
+        assert_eq!(names(&p.ordered(Some("c"))), ["a", "b", "c"]);
+    }
+
+    #[test]
+    fn validate_rejects_bad_candidates() {
+        assert!(policy(&[]).validate().is_err());
+        assert!(policy(&["a", "b", "a"]).validate().is_err());
+        assert!(policy(&["a", " "]).validate().is_err());
+
+        let mut no_vo = policy(&["a"]);
+        no_vo.candidates[0].vo = String::new();
+        assert!(no_vo.validate().is_err());
+
+        let mut reserved = policy(&["a"]);
+        reserved.candidates[0] = reserved.candidates[0].clone().with_option("wid", "1");
+        assert!(reserved.validate().is_err());
+
+        // Unknown wid kinds fail to parse.
+        assert!(RenderPathPolicy::from_json(r#"{"candidates":[{"name":"a","vo":"gpu","wid":"hwnd"}]}"#).is_err());
+        assert!(RenderPathPolicy::from_json(r#"{"candidates":[{"name":"a","vo":"gpu"},{"name":"a","vo":"gpu"}]}"#).is_err());
+    }
+}
@@ -0,0 +1,344 @@
+// Server profiles: named GDS servers (home, office, backup) to switch between.
+//
+// The registry lives in `server_profiles.json` in the app config dir; each profile's
+// API key is kept in the credential store under `profile:<id>`. Activating a profile
+// copies its URL into settings and its key into the active `gds_api_key` slot, so
+// everything downstream (GdsState, playback URLs) keeps using the single active pair.
+use crate::credentials::{CredentialState, GDS_API_KEY};
+use crate::error::{FlashplexError, FlashplexResult};
+use crate::gds::{normalize_server_url, GdsState, ServerCheck};
+use crate::settings::{settings_changed, SettingsState};
+use crate::storage::write_atomic;
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use tauri::{Emitter, Manager};
+
+const PROFILES_FILE: &str = "server_profiles.json";
+const PROFILES_VERSION: u32 = 1;
+pub const SERVER_PROFILE_CHANGED_EVENT: &str = "server-profile-changed";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ServerProfile {
+    pub id: String,
+    pub name: String,
+    pub url: String,
+    #[serde(default)]
+    pub preferred_source_id: Option<u32>,
+}
+
+/// Profile as shown to the UI: never carries the key itself.
+#[derive(Debug, Clone, Serialize)]
+pub struct ProfileView {
+    #[serde(flatten)]
+    pub profile: ServerProfile,
+    pub has_key: bool,
+    pub active: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ProfileList {
+    pub active: Option<String>,
+    pub profiles: Vec<ProfileView>,
+}
+
+/// Create (no `id`) or update a profile. `api_key: None` keeps the stored key.
+#[derive(Debug, Clone, Deserialize)]
+pub struct ProfileInput {
+    #[serde(default)]
+    pub id: Option<String>,
+    pub name: String,
+    pub url: String,
+    #[serde(default)]
+    pub api_key: Option<String>,
+    #[serde(default)]
+    pub preferred_source_id: Option<u32>,
+}
+
+/// What `check_server` should probe: a saved profile by id, or unsaved form values.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(untagged)]
+pub enum ProfileTarget {
+    Saved(String),
+    Draft { url: String, api_key: String },
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ProfileCheck {
+    pub profile: Option<String>,
+    #[serde(flatten)]
+    pub check: ServerCheck,
+    /// True when the check passed and the profile became active.
+    pub activated: bool,
+}
+
+#[derive(Default, Serialize, Deserialize)]
+struct ProfilesFile {
+    version: u32,
+    active: Option<String>,
+    profiles: Vec<ServerProfile>,
+}
+
+fn key_account(id: &str) -> String {
+    format!("profile:{}", id)
+}
+
+pub struct ProfileStore {
+    file: PathBuf,
+    data: ProfilesFile,
+}
+
+impl ProfileStore {
+    /// Loads `file`; a missing or unreadable file starts with no profiles.
+    pub fn open(file: PathBuf) -> Self {
+        let data = match std::fs::read_to_string(&file) {
+            Ok(text) => serde_json::from_str(&text).unwrap_or_else(|e| {
+                log_warn!("PROFILES", "Ignoring unreadable {}: {}", file.display(), e);
+                ProfilesFile::default()
+            }),
+            Err(_) => ProfilesFile::default(),
+        };
+        ProfileStore { file, data }
+    }
+
+    fn save(&mut self) -> FlashplexResult<()> {
+        self.data.version = PROFILES_VERSION;
+        write_atomic(&self.file, &serde_json::to_vec_pretty(&self.data)?)
+    }
+
+    pub fn get(&self, id: &str) -> Option<&ServerProfile> {
+        self.data.profiles.iter().find(|p| p.id == id)
+    }
+
+    pub fn list(&self, credentials: &CredentialState) -> ProfileList {
+        let active = self.data.active.clone();
+        let profiles = self
+            .data
+            .profiles
+            .iter()
+            .map(|p| ProfileView {
+                has_key: credentials.get(&key_account(&p.id)).is_some(),
+                active: active.as_deref() == Some(p.id.as_str()),
+                profile: p.clone(),
+            })
+            .collect();
+        ProfileList { active, profiles }
+    }
+
+    fn next_id(&self) -> String {
+        let base = chrono::Utc::now().timestamp_millis();
+        let mut n = 0;
+        loop {
+            let id = format!("p{}{}", base, if n == 0 { String::new() } else { format!("-{}", n) });
+            if self.get(&id).is_none() {
+                return id;
+            }
+            n += 1;
+        }
+    }
+
+    pub fn upsert(&mut self, input: ProfileInput, credentials: &CredentialState) -> FlashplexResult<ServerProfile> {
+        let name = input.name.trim();
+        if name.is_empty() || input.url.trim().is_empty() {
+            return Err(FlashplexError::invalid_input("profile needs a name and a URL"));
+        }
+        let id = match input.id.filter(|id| !id.is_empty()) {
+            Some(id) if self.get(&id).is_none() => {
+                return Err(FlashplexError::invalid_input(format!("unknown profile '{}'", id)));
+            }
+            Some(id) => id,
+            None => self.next_id(),
+        };
+        let profile = ServerProfile {
+            id: id.clone(),
+            name: name.to_string(),
+            url: normalize_server_url(&input.url),
+            preferred_source_id: input.preferred_source_id,
+        };
+        if let Some(key) = input.api_key {
+            credentials.0.lock()?.set(&key_account(&id), &key)?;
+        }
+        match self.data.profiles.iter_mut().find(|p| p.id == id) {
+            Some(existing) => *existing = profile.clone(),
+            None => self.data.profiles.push(profile.clone()),
+        }
+        self.save()?;
+        Ok(profile)
+    }
+
+    pub fn remove(&mut self, id: &str, credentials: &CredentialState) -> FlashplexResult<()> {
+        let before = self.data.profiles.len();
+        self.data.profiles.retain(|p| p.id != id);
+        if self.data.profiles.len() == before {
+            return Err(FlashplexError::invalid_input(format!("unknown profile '{}'", id)));
+        }
+        if self.data.active.as_deref() == Some(id) {
+            self.data.active = None;
+        }
+        credentials.0.lock()?.delete(&key_account(id))?;
+        self.save()
+    }
+
+    fn set_active(&mut self, id: &str) -> FlashplexResult<()> {
+        self.data.active = Some(id.to_string());
+        self.save()
+    }
+}
+
+pub struct ProfilesState(pub Arc<Mutex<ProfileStore>>);
+
+impl ProfilesState {
+    /// Opens the registry. The first run seeds a "Default" profile from the
+    /// single server configured in settings.
+    pub fn open(app: &tauri::AppHandle) -> Self {
+        let dir = app
+            .path()
+            .app_config_dir()
+            .unwrap_or_else(|_| std::env::temp_dir().join("flashplex"));
+        let mut store = ProfileStore::open(dir.join(PROFILES_FILE));
+        seed_default(&mut store, app);
+        log_info!("PROFILES", "Loaded {} profile(s)", store.data.profiles.len());
+        ProfilesState(Arc::new(Mutex::new(store)))
+    }
+}
+
+/// Creates an active "Default" profile from the server in settings and the active
+/// key when there are no profiles yet; a no-op otherwise.
+fn seed_default(store: &mut ProfileStore, app: &tauri::AppHandle) {
+    if !store.data.profiles.is_empty() {
+        return;
+    }
+    let server_url = app
+        .try_state::<SettingsState>()
+        .and_then(|s| s.snapshot().ok())
+        .map(|s| s.server_url)
+        .unwrap_or_default();
+    let (false, Some(credentials)) = (server_url.is_empty(), app.try_state::<CredentialState>()) else {
+        return;
+    };
+    let input = ProfileInput {
+        id: None,
+        name: "Default".to_string(),
+        url: server_url,
+        api_key: credentials.get(GDS_API_KEY),
+        preferred_source_id: None,
+    };
+    match store.upsert(input, &credentials) {
+        Ok(profile) => {
+            if let Err(e) = store.set_active(&profile.id) {
+                log_warn!("PROFILES", "Could not save default profile: {}", e);
+            }
+        }
+        Err(e) => log_warn!("PROFILES", "Could not create default profile: {}", e),
+    }
+}
+
+/// Seeds the default profile once legacy settings were migrated after start-up.
+pub fn seed_default_profile(app: &tauri::AppHandle) {
+    if let Some(profiles) = app.try_state::<ProfilesState>() {
+        if let Ok(mut store) = profiles.0.lock() {
+            seed_default(&mut store, app);
+        }
+    }
+}
+
+/// Makes `id` the active server: settings URL, active API key, GDS client, events.
+fn activate_profile(app: &tauri::AppHandle, id: &str) -> FlashplexResult<ServerProfile> {
+    let profiles = app.state::<ProfilesState>();
+    let credentials = app.state::<CredentialState>();
+    let profile = {
+        let mut store = profiles.0.lock()?;
+        let profile = store
+            .get(id)
+            .cloned()
+            .ok_or_else(|| FlashplexError::invalid_input(format!("unknown profile '{}'", id)))?;
+        store.set_active(id)?;
+        profile
+    };
+    let key = credentials.get(&key_account(id)).unwrap_or_default();
+    credentials.0.lock()?.set(GDS_API_KEY, &key)?;
+    let settings = app
+        .state::<SettingsState>()
+        .0
+        .lock()?
+        .update(serde_json::json!({ "server_url": profile.url }))?;
+    settings_changed(app, &settings);
+    log_info!("PROFILES", "Active profile: {} ({})", profile.name, profile.url);
+    let _ = app.emit(SERVER_PROFILE_CHANGED_EVENT, profile.clone());
+    Ok(profile)
+}
+
+// Commands
+
+#[tauri::command(rename_all = "snake_case")]
+pub fn list_server_profiles(
+    state: tauri::State<'_, ProfilesState>,
+    credentials: tauri::State<'_, CredentialState>,
+) -> FlashplexResult<ProfileList> {
+    Ok(state.0.lock()?.list(&credentials))
+}
+
+#[tauri::command(rename_all = "snake_case")]
+pub fn save_server_profile(
+    state: tauri::State<'_, ProfilesState>,
+    credentials: tauri::State<'_, CredentialState>,
+    profile: ProfileInput,
+) -> FlashplexResult<ServerProfile> {
+    let saved = state.0.lock()?.upsert(profile, &credentials)?;
+    log_info!("PROFILES", "Saved profile: {} ({})", saved.name, saved.url);
+    Ok(saved)
+}
+
+#[tauri::command(rename_all = "snake_case")]
+pub fn delete_server_profile(
+    state: tauri::State<'_, ProfilesState>,
+    credentials: tauri::State<'_, CredentialState>,
+    id: String,
+) -> FlashplexResult<()> {
+    log_info!("PROFILES", "Delete profile: {}", id);
+    state.0.lock()?.remove(&id, &credentials)
+}
+
+/// Switches without probing (e.g. server known to be down for maintenance).
+#[tauri::command(rename_all = "snake_case")]
+pub fn set_active_server_profile(app: tauri::AppHandle, id: String) -> FlashplexResult<ServerProfile> {
+    activate_profile(&app, &id)
+}
+
+/// Probes a server with `search?limit=1`. A saved profile that passes becomes the
+/// active one unless `activate` is false; drafts are never activated.
+#[tauri::command(rename_all = "snake_case")]
+pub async fn check_server(
+    app: tauri::AppHandle,
+    gds: tauri::State<'_, GdsState>,
+    profile: ProfileTarget,
+    activate: Option<bool>,
+) -> FlashplexResult<ProfileCheck> {
+    let (id, url, key) = match profile {
+        ProfileTarget::Saved(id) => {
+            let url = app
+                .state::<ProfilesState>()
+                .0
+                .lock()?
+                .get(&id)
+                .map(|p| p.url.clone())
+                .ok_or_else(|| FlashplexError::invalid_input(format!("unknown profile '{}'", id)))?;
+            let key = app.state::<CredentialState>().get(&key_account(&id)).unwrap_or_default();
+            (Some(id), url, key)
+        }
+        ProfileTarget::Draft { url, api_key } => (None, url, api_key),
+    };
+    let check = gds.client_for(&url, &key).check().await;
+    log_info!(
+        "PROFILES", "Check {}: ok={} auth={:?} latency={}ms",
+        check.url, check.ok, check.auth, check.latency_ms
+    );
+    let activated = match id.as_deref() {
+        Some(id) if check.ok && activate.unwrap_or(true) => {
+            activate_profile(&app, id)?;
+            true
+        }
+        _ => false,
+    };
+    Ok(ProfileCheck { profile: id, check, activated })
+}
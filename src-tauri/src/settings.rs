@@ -0,0 +1,400 @@
+// App settings owned by the backend.
+//
+// Replaces the `gds_*` / `flashplex_*` localStorage keys so Rust can act on them
+// (GDS client at startup, player defaults when mpv is created). Stored as a
+// versioned JSON file in the app config dir; every change is broadcast as
+// `settings-changed`. The API key lives in the credential store, not here.
+use crate::credentials::{CredentialState, GDS_API_KEY};
+use crate::error::{FlashplexError, FlashplexResult};
+use crate::gds::GdsState;
+use crate::player::diagnostics::normalize_mpv_log_level;
+use crate::player::render_policy::RenderPathPolicy;
+use crate::player::quality::{normalize_quality_profile, QualityProfile};
+use crate::player::{PlayerBackend, SubtitleStyle, TrackPreferences};
+use crate::storage::write_atomic;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use tauri::{Emitter, Manager};
+
+const SETTINGS_FILE: &str = "settings.json";
+pub const SETTINGS_VERSION: u32 = 2;
+pub const SETTINGS_CHANGED_EVENT: &str = "settings-changed";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    pub version: u32,
+    pub server_url: String,
+    pub volume: i64,
+    pub quality_profile: String,
+    pub subtitle_scale: f64,
+    pub subtitle_pos: f64,
+    pub debug_logs: bool,
+    /// Minimum libmpv log level forwarded to the app log; applies to the next player.
+    pub mpv_log_level: String,
+    /// mpv scripts loaded into new players, by name (e.g. "gds_osc"); see player/mpv_config.rs.
+    pub mpv_scripts: Vec<String>,
+    pub alang: Option<String>,
+    pub slang: Option<String>,
+    /// Overrides render_policy.json and the built-in render path candidates.
+    pub render_policy: Option<RenderPathPolicy>,
+    /// Where new offline downloads go; None is `downloads/` in the app data dir.
+    pub download_dir: Option<String>,
+    /// Downloads transferring at the same time.
+    pub download_concurrency: u32,
+    /// Player instances that may run at once (main player, previews, ...).
+    pub max_players: u32,
+    /// Create the main player hidden at startup so the first title starts faster.
+    pub prewarm_player: bool,
+    /// Closing the main player only stops playback and hides it.
+    pub keep_player_alive: bool,
+    /// Set once the old localStorage keys were imported.
+    pub legacy_migrated: bool,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Settings {
+            version: SETTINGS_VERSION,
+            server_url: String::new(),
+            volume: 100,
+            quality_profile: "balanced".to_string(),
+            subtitle_scale: 1.0,
+            // mpv sub-pos: 100 is the bottom of the screen.
+            subtitle_pos: 100.0,
+            debug_logs: false,
+            mpv_log_level: "info".to_string(),
+            mpv_scripts: vec!["gds_osc".to_string()],
+            alang: None,
+            slang: None,
+            render_policy: None,
+            download_dir: None,
+            download_concurrency: 2,
+            max_players: 2,
+            prewarm_player: false,
+            keep_player_alive: false,
+            legacy_migrated: false,
+        }
+    }
+}
+
+impl Settings {
+    fn normalize(&mut self) {
+        self.version = SETTINGS_VERSION;
+        self.server_url = self.server_url.trim().trim_end_matches('/').to_string();
+        self.volume = self.volume.clamp(0, 130);
+        self.quality_profile = normalize_quality_profile(&self.quality_profile);
+        self.mpv_log_level = normalize_mpv_log_level(&self.mpv_log_level).to_string();
+        let mut scripts: Vec<String> = Vec::new();
+        for name in self.mpv_scripts.iter().map(|n| n.trim()).filter(|n| !n.is_empty()) {
+            if !scripts.iter().any(|s| s == name) {
+                scripts.push(name.to_string());
+            }
+        }
+        self.mpv_scripts = scripts;
+        self.download_concurrency = self.download_concurrency.clamp(1, 6);
+        self.max_players = self.max_players.clamp(1, 4);
+        if !self.subtitle_scale.is_finite() || self.subtitle_scale <= 0.0 {
+            self.subtitle_scale = 1.0;
+        }
+        self.subtitle_pos = if self.subtitle_pos.is_finite() {
+            self.subtitle_pos.clamp(0.0, 150.0)
+        } else {
+            100.0
+        };
+    }
+
+    pub fn track_preferences(&self) -> TrackPreferences {
+        TrackPreferences {
+            alang: self.alang.clone(),
+            slang: self.slang.clone(),
+        }
+    }
+
+    /// Player defaults applied when a new mpv instance is created. `quality` is the
+    /// resolved `quality_profile` (None when it couldn't be resolved).
+    pub fn apply_to_player(&self, player: &dyn PlayerBackend, quality: Option<&QualityProfile>) {
+        if let Err(e) = player.set_volume(self.volume) {
+            log_warn!("SETTINGS", "volume not applied: {}", e);
+        }
+        let style = SubtitleStyle {
+            scale: Some(self.subtitle_scale),
+            pos: Some(self.subtitle_pos.round() as i64),
+        };
+        if let Err(e) = player.set_subtitle_style(style) {
+            log_warn!("SETTINGS", "subtitle style not applied: {}", e);
+        }
+        match quality {
+            Some(profile) => {
+                player.set_quality_profile(profile);
+            }
+            None => log_warn!("SETTINGS", "quality profile '{}' not applied", self.quality_profile),
+        }
+        if let Err(e) = player.set_track_preferences(&self.track_preferences()) {
+            log_warn!("SETTINGS", "track preferences not applied: {}", e);
+        }
+    }
+
+    // Old localStorage key -> field. Unparseable values keep the default.
+    fn apply_legacy(&mut self, values: &HashMap<String, String>) {
+        let get = |key: &str| values.get(key).map(|v| v.trim()).filter(|v| !v.is_empty());
+        if let Some(v) = get("gds_server_url") {
+            self.server_url = v.to_string();
+        }
+        if let Some(v) = get("flashplex_volume").and_then(|v| v.parse::<f64>().ok()) {
+            self.volume = v.round() as i64;
+        }
+        if let Some(v) = get("flashplex_quality_profile") {
+            self.quality_profile = v.to_string();
+        }
+        if let Some(v) = get("flashplex_sub_size").and_then(|v| v.parse().ok()) {
+            self.subtitle_scale = v;
+        }
+        if let Some(v) = get("flashplex_sub_pos").and_then(|v| v.parse().ok()) {
+            self.subtitle_pos = v;
+        }
+        if let Some(v) = get("flashplex_debug_logs") {
+            self.debug_logs = v == "1" || v == "true";
+        }
+    }
+}
+
+// Upgrades an on-disk settings document to the current layout. Returns the
+// plaintext API key of a pre-v2 file so the caller can move it to the credential store.
+fn upgrade(mut raw: serde_json::Value) -> FlashplexResult<(Settings, Option<String>)> {
+    let version = raw.get("version").and_then(|v| v.as_u64()).unwrap_or(0);
+    if version > SETTINGS_VERSION as u64 {
+        log_info!("SETTINGS", "File version {} is newer than {}; reading known fields", version, SETTINGS_VERSION);
+    }
+    // v0 (no version field) -> v1: same fields, missing ones take defaults.
+    // v1 -> v2: `api_key` moves out of the file.
+    let legacy_key = raw
+        .as_object_mut()
+        .and_then(|map| map.remove("api_key"))
+        .and_then(|v| v.as_str().map(|s| s.trim().to_string()))
+        .filter(|s| !s.is_empty());
+    let mut settings: Settings = serde_json::from_value(raw)?;
+    settings.normalize();
+    Ok((settings, legacy_key))
+}
+
+pub struct SettingsStore {
+    file: PathBuf,
+    settings: Settings,
+    /// API key found in an old settings file, not yet moved to the credential store.
+    legacy_api_key: Option<String>,
+}
+
+impl SettingsStore {
+    /// Loads `file`; a missing or unreadable file starts from defaults.
+    pub fn open(file: PathBuf) -> Self {
+        let (settings, legacy_api_key) = match std::fs::read_to_string(&file) {
+            Ok(text) => match serde_json::from_str(&text).map_err(FlashplexError::from).and_then(upgrade) {
+                Ok(upgraded) => upgraded,
+                Err(e) => {
+                    log_warn!("SETTINGS", "Ignoring unreadable {}: {}", file.display(), e);
+                    (Settings::default(), None)
+                }
+            },
+            Err(_) => (Settings::default(), None),
+        };
+        SettingsStore { file, settings, legacy_api_key }
+    }
+
+    pub fn get(&self) -> &Settings {
+        &self.settings
+    }
+
+    fn save(&self) -> FlashplexResult<()> {
+        write_atomic(&self.file, &serde_json::to_vec_pretty(&self.settings)?)
+    }
+
+    /// Merges the top-level fields of `patch` into the settings and saves them.
+    pub fn update(&mut self, patch: serde_json::Value) -> FlashplexResult<Settings> {
+        let patch = match patch {
+            serde_json::Value::Object(map) => map,
+            _ => return Err(FlashplexError::invalid_input("settings patch must be an object")),
+        };
+        let mut merged = serde_json::to_value(&self.settings)?;
+        let fields = merged
+            .as_object_mut()
+            .ok_or_else(|| FlashplexError::internal("settings did not serialize to an object"))?;
+        for (key, value) in patch {
+            if key == "version" {
+                continue;
+            }
+            if !fields.contains_key(&key) {
+                return Err(FlashplexError::invalid_input(format!("unknown setting '{}'", key)));
+            }
+            fields.insert(key, value);
+        }
+        let mut next: Settings = serde_json::from_value(merged)
+            .map_err(|e| FlashplexError::invalid_input(format!("settings: {}", e)))?;
+        if let Some(policy) = &next.render_policy {
+            policy.validate()?;
+        }
+        next.normalize();
+        self.settings = next;
+        self.save()?;
+        Ok(self.settings.clone())
+    }
+
+    fn migrate_legacy(&mut self, values: &HashMap<String, String>) -> FlashplexResult<Settings> {
+        if !self.settings.legacy_migrated {
+            self.settings.apply_legacy(values);
+            self.settings.legacy_migrated = true;
+            self.settings.normalize();
+            self.legacy_api_key = values
+                .get("gds_api_key")
+                .map(|v| v.trim().to_string())
+                .filter(|v| !v.is_empty());
+            self.save()?;
+            log_info!("SETTINGS", "Imported {} legacy localStorage keys", values.len());
+        }
+        Ok(self.settings.clone())
+    }
+
+    /// Moves a plaintext API key (old settings file or localStorage) into `credentials`.
+    fn store_legacy_api_key(&mut self, credentials: &CredentialState) -> FlashplexResult<()> {
+        let Some(key) = self.legacy_api_key.take() else {
+            return Ok(());
+        };
+        credentials.0.lock()?.set(GDS_API_KEY, &key)?;
+        // Rewrite so the plaintext copy is gone from disk.
+        self.save()?;
+        log_info!("SETTINGS", "Moved API key to the credential store");
+        Ok(())
+    }
+}
+
+pub struct SettingsState(pub Arc<Mutex<SettingsStore>>);
+
+impl SettingsState {
+    pub fn open(app: &tauri::AppHandle) -> Self {
+        let dir = app
+            .path()
+            .app_config_dir()
+            .unwrap_or_else(|_| std::env::temp_dir().join("flashplex"));
+        let mut store = SettingsStore::open(dir.join(SETTINGS_FILE));
+        log_info!("SETTINGS", "Loaded from {}", store.file.display());
+        if let Some(credentials) = app.try_state::<CredentialState>() {
+            if let Err(e) = store.store_legacy_api_key(&credentials) {
+                log_warn!("SETTINGS", "API key migration failed: {}", e);
+            }
+        }
+        SettingsState(Arc::new(Mutex::new(store)))
+    }
+
+    pub fn snapshot(&self) -> FlashplexResult<Settings> {
+        Ok(self.0.lock()?.get().clone())
+    }
+}
+
+/// Pushes settings the backend acts on (GDS server, debug logging, downloads) and notifies the webview.
+pub fn settings_changed(app: &tauri::AppHandle, settings: &Settings) {
+    crate::logging::set_debug(settings.debug_logs);
+    configure_gds(app, settings);
+    crate::downloads::apply_settings(app, settings);
+    let _ = app.emit(SETTINGS_CHANGED_EVENT, settings.clone());
+}
+
+pub fn configure_gds(app: &tauri::AppHandle, settings: &Settings) {
+    let api_key = app
+        .try_state::<CredentialState>()
+        .and_then(|credentials| credentials.get(GDS_API_KEY))
+        .unwrap_or_default();
+    if settings.server_url.is_empty() || api_key.is_empty() {
+        return;
+    }
+    if let Some(gds) = app.try_state::<GdsState>() {
+        gds.configure(&settings.server_url, &api_key);
+    }
+}
+
+/// Re-runs `configure_gds` with the current settings (after the API key changed).
+pub fn reconfigure_gds(app: &tauri::AppHandle) {
+    if let Some(state) = app.try_state::<SettingsState>() {
+        if let Ok(settings) = state.snapshot() {
+            configure_gds(app, &settings);
+        }
+    }
+}
+
+// Commands
+
+#[tauri::command(rename_all = "snake_case")]
+pub fn get_settings(state: tauri::State<'_, SettingsState>) -> FlashplexResult<Settings> {
+    state.snapshot()
+}
+
+#[tauri::command(rename_all = "snake_case")]
+pub fn update_settings(
+    app: tauri::AppHandle,
+    state: tauri::State<'_, SettingsState>,
+    patch: serde_json::Value,
+) -> FlashplexResult<Settings> {
+    let settings = state.0.lock()?.update(patch)?;
+    settings_changed(&app, &settings);
+    Ok(settings)
+}
+
+/// One-time import of the old localStorage keys; a no-op once done.
+/// `gds_api_key` goes to the credential store.
+#[tauri::command(rename_all = "snake_case")]
+pub fn migrate_legacy_settings(
+    app: tauri::AppHandle,
+    state: tauri::State<'_, SettingsState>,
+    credentials: tauri::State<'_, CredentialState>,
+    values: HashMap<String, String>,
+) -> FlashplexResult<Settings> {
+    let (settings, changed) = {
+        let mut store = state.0.lock()?;
+        let already = store.get().legacy_migrated;
+        let settings = store.migrate_legacy(&values)?;
+        store.store_legacy_api_key(&credentials)?;
+        (settings, !already)
+    };
+    if changed {
+        settings_changed(&app, &settings);
+        crate::profiles::seed_default_profile(&app);
+    }
+    Ok(settings)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn legacy(pairs: &[(&str, &str)]) -> HashMap<String, String> {
+        pairs.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect()
+    }
+
+    #[test]
+    fn subtitles_default_to_the_bottom() {
+        let mut settings = Settings::default();
+        assert_eq!(settings.subtitle_pos, 100.0);
+        settings.apply_legacy(&legacy(&[("flashplex_sub_size", "1.2")]));
+        assert_eq!(settings.subtitle_pos, 100.0);
+    }
+
+    #[test]
+    fn legacy_sub_pos_is_kept_as_mpv_sub_pos() {
+        let mut settings = Settings::default();
+        settings.apply_legacy(&legacy(&[("flashplex_sub_pos", "85"), ("flashplex_volume", "70.4")]));
+        settings.normalize();
+        assert_eq!(settings.subtitle_pos, 85.0);
+        assert_eq!(settings.volume, 70);
+
+        settings.apply_legacy(&legacy(&[("flashplex_sub_pos", "not a number")]));
+        assert_eq!(settings.subtitle_pos, 85.0);
+    }
+
+    #[test]
+    fn upgrade_fills_missing_sub_pos_with_default() {
+        let (settings, key) = upgrade(serde_json::json!({ "version": 1, "api_key": " k " })).unwrap();
+        assert_eq!(settings.subtitle_pos, 100.0);
+        assert_eq!(key.as_deref(), Some("k"));
+    }
+}
@@ -0,0 +1,14 @@
+// Small helpers for the JSON files we keep in the app data/config dirs.
+use crate::error::{FlashplexError, FlashplexResult};
+use std::path::Path;
+
+/// Writes `bytes` to a sibling temp file and renames it over `path`.
+pub fn write_atomic(path: &Path, bytes: &[u8]) -> FlashplexResult<()> {
+    let io_err = |e: std::io::Error| FlashplexError::internal(format!("Failed to write {}: {}", path.display(), e));
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir).map_err(io_err)?;
+    }
+    let tmp = path.with_extension("tmp");
+    std::fs::write(&tmp, bytes).map_err(io_err)?;
+    std::fs::rename(&tmp, path).map_err(io_err)
+}
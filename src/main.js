@@ -8,11 +8,21 @@
 const isAndroid = /Android/i.test(navigator.userAgent);
 const isIOS = /iPhone|iPad|iPod/i.test(navigator.userAgent);
 const isDesktop = !isAndroid && !isIOS;
-const DEBUG_LOG = localStorage.getItem("flashplex_debug_logs") === "1";
+// Follows the backend `debug_logs` setting once it is loaded (see applyBackendSettings).
+let DEBUG_LOG = ["1", "true"].includes(localStorage.getItem("flashplex_debug_logs"));
 
 // [PHASE 1-3 OPTIMIZATION] Utility functions and LRU Cache class MUST be defined before state
-const dlog = (...args) => { if (DEBUG_LOG) console.log(...args); };
-const dwarn = (...args) => { if (DEBUG_LOG) console.warn(...args); };
+// Mask apikey query values before a URL reaches any log.
+const redactSecrets = (text) => String(text).replace(/(apikey=)[^&#\s"']+/gi, "$1***");
+// Debug lines also go to the backend log so exported logs show both sides.
+const forwardLog = (level, args) => {
+  const invoke = window.__TAURI__ && window.__TAURI__.core ? window.__TAURI__.core.invoke : null;
+  if (!invoke) return;
+  const msg = args.map((a) => (typeof a === "string" ? a : (() => { try { return JSON.stringify(a); } catch (_) { return String(a); } })())).join(" ");
+  invoke("native_log", { msg, level, target: "JS" }).catch(() => {});
+};
+const dlog = (...args) => { if (DEBUG_LOG) { console.log(...args); forwardLog("debug", args); } };
+const dwarn = (...args) => { if (DEBUG_LOG) { console.warn(...args); forwardLog("warn", args); } };
 
 const debounce = (fn, delay) => {
   let timeout;
@@ -116,7 +126,9 @@ class LRUCache {
 // State Management (Hardcoded for testing as requested)
 const state = {
   serverUrl: "https://music.yommi.mywire.org",
-  apiKey: "gommikey",
+  apiKey: "gommikey", // Browser-only; with Tauri it seeds the default profile and is then cleared.
+  hasApiKey: false,
+  mediaProxyBase: null, // media_proxy.rs base URL, set at startup with Tauri
   currentView: "library",
   category: "tv_show", // Default to TV/Show
   query: "", // Current search query
@@ -124,7 +136,8 @@ const state = {
   currentPath: "", // For folder navigation
   pathStack: [], // Navigation history
   categoryMapping: {},
-  subtitleSize: parseFloat(localStorage.getItem("flashplex_sub_size") || "1.1"), // Default font scale
+  // Overwritten by the backend settings (settings.rs) at startup; localStorage is the browser-only fallback.
+  subtitleSize: parseFloat(localStorage.getItem("flashplex_sub_size") || "1.0"), // Default font scale
   subtitlePos: parseFloat(localStorage.getItem("flashplex_sub_pos") || "100.0"), // Default vertical offset (Bottom)
   volume: parseInt(localStorage.getItem("flashplex_volume") || "100"),
   qualityProfile: localStorage.getItem("flashplex_quality_profile") || "balanced",
@@ -401,7 +414,7 @@ async function resolveNextEpisodeItem(currentPath, sourceId) {
   const bpath = toUrlSafeBase64(parentPath);
   if (!bpath) return null;
 
-  const endpoint = `explorer/list?bpath=${bpath}&source_id=${normalizeSourceId(sourceId)}&limit=200&apikey=${state.apiKey}`;
+  const endpoint = `explorer/list?bpath=${bpath}&source_id=${normalizeSourceId(sourceId)}&limit=200`;
   const data = await gdsFetch(endpoint);
   const list = data?.list || data?.data || data?.items || [];
   const episodes = list.filter((it) => {
@@ -414,12 +427,62 @@ async function resolveNextEpisodeItem(currentPath, sourceId) {
   return episodes[idx + 1] || null;
 }
 
+function buildNativeQueueItem(item) {
+  let cleanPath = (item.path || "").normalize("NFC");
+  if (cleanPath && !cleanPath.startsWith("/")) cleanPath = "/" + cleanPath;
+  const sourceId = normalizeSourceId(item.source_id);
+  // url/subtitle_url are filled in by the backend with the stored API key.
+  return {
+    title: item.meta_title || item.title || item.name || "",
+    path: cleanPath,
+    source_id: Number(sourceId),
+  };
+}
+
+// Hand the next episode to mpv's playlist so it auto-advances even if the webview is busy.
+async function queueNextEpisodeNative(nextItem) {
+  const invoke = getTauriInvoke();
+  if (!invoke) return;
+  const queue = await invoke("get_queue");
+  const entries = Array.isArray(queue?.entries) ? queue.entries : [];
+  if (entries.length > (queue?.current ?? -1) + 1) return; // something is already queued
+  await invoke("queue_enqueue", { items: [buildNativeQueueItem(nextItem)] });
+  console.log("[NEXT-EP] queued in mpv:", nextItem.path || nextItem.name || "");
+}
+
+// mpv moved to another playlist entry on its own (auto-advance / queue_next).
+async function syncNativeQueueCurrent() {
+  const invoke = getTauriInvoke();
+  if (!invoke) return;
+  const queue = await invoke("get_queue");
+  const entry = (queue?.entries || []).find((e) => e.current);
+  if (!entry || !entry.path || entry.path === state.currentMediaPath) return;
+  console.log("[QUEUE] now playing:", entry.path);
+  state.currentMediaPath = entry.path;
+  state.currentMediaSourceId = normalizeSourceId(entry.source_id);
+  state.currentMediaTitle = entry.title;
+  state.nativeSource = {
+    title: entry.title,
+    url: null,
+    subtitleUrl: null,
+    path: entry.path,
+    source_id: normalizeSourceId(entry.source_id),
+  };
+  state.nextEpisodeItem = null;
+  state.openingSkipDismissPath = "";
+  if (ui.playerTitle) ui.playerTitle.textContent = getOverlayTitleText(entry.title);
+  if (ui.oscTitle) ui.oscTitle.textContent = getOverlayTitleText(entry.title);
+  updateNextEpisodeUI();
+  refreshNextEpisodeCandidate().catch(() => {});
+}
+
 function updateNextEpisodeUI() {
   const btn = ui.btnOscNextEpisode;
   if (!btn) return;
   const hasPlayback = !!(state.isNativeActive || (ui.mainPlayer && ui.playerOverlay && ui.playerOverlay.classList.contains("active")));
   if (!hasPlayback || !state.nextEpisodeItem) {
     btn.classList.add("hidden");
+    sendNativeOscNextEpisode("");
     return;
   }
   const nextEpNo = extractEpisodeNumber(state.nextEpisodeItem);
@@ -427,6 +490,15 @@ function updateNextEpisodeUI() {
     ? `다음화: ${nextEpNo}화`
     : "다음화";
   btn.classList.remove("hidden");
+  sendNativeOscNextEpisode(btn.textContent);
+}
+
+// Mirrors the next-episode button into the mpv OSC (gds_osc.lua); an empty label hides it.
+function sendNativeOscNextEpisode(label) {
+  const invoke = getTauriInvoke();
+  if (!invoke || !state.isNativeActive) return;
+  invoke("send_script_message", { target: "gds_osc", args: ["next-episode", label] })
+    .catch((err) => dwarn("[NEXT-EP] OSC update failed:", err?.message || err));
 }
 
 async function refreshNextEpisodeCandidate() {
@@ -442,6 +514,7 @@ async function refreshNextEpisodeCandidate() {
     const nextItem = await resolveNextEpisodeItem(currentPath, sourceId);
     if (seq !== state.nextEpisodeResolveSeq) return;
     state.nextEpisodeItem = nextItem || null;
+    if (nextItem && state.isNativeActive) queueNextEpisodeNative(nextItem).catch(() => {});
   } catch (err) {
     if (seq !== state.nextEpisodeResolveSeq) return;
     state.nextEpisodeItem = null;
@@ -915,7 +988,7 @@ function getTauriInvoke() {
   if (invoke && !window.tlog) {
     window.tlog = (msg) => { 
         invoke("native_log", { msg }).catch(() => {});
-        console.log("[NATIVE-LOG]", msg);
+        console.log("[NATIVE-LOG]", redactSecrets(msg));
     };
   }
   return invoke;
@@ -935,9 +1008,261 @@ async function detectNativeArch() {
   }
 }
 
+// With Tauri the key stays in the backend credential store; the UI only knows whether one is set.
+function hasServerCredentials() {
+  if (!state.serverUrl) return false;
+  return getTauriInvoke() ? state.hasApiKey : !!state.apiKey;
+}
+
+// Backend settings field -> old localStorage key (migration source, and the store without Tauri).
+const LEGACY_SETTING_KEYS = {
+  server_url: "gds_server_url",
+  // Not a backend setting: migrated into the credential store (set_api_key).
+  api_key: "gds_api_key",
+  volume: "flashplex_volume",
+  quality_profile: "flashplex_quality_profile",
+  subtitle_scale: "flashplex_sub_size",
+  subtitle_pos: "flashplex_sub_pos",
+  debug_logs: "flashplex_debug_logs",
+};
+
+// Backend-only toggles (player/actor.rs); take effect at the next start / close.
+const PLAYER_LIFECYCLE_SETTINGS = ["prewarm_player", "keep_player_alive"];
+
+function applyBackendSettings(settings) {
+  if (!settings || typeof settings !== "object") return;
+  if (settings.server_url) state.serverUrl = settings.server_url;
+  if (typeof settings.volume === "number") state.volume = settings.volume;
+  if (settings.quality_profile) state.qualityProfile = normalizeQualityProfile(settings.quality_profile);
+  if (typeof settings.subtitle_scale === "number") state.subtitleSize = settings.subtitle_scale;
+  if (typeof settings.subtitle_pos === "number") state.subtitlePos = settings.subtitle_pos;
+  if (typeof settings.debug_logs === "boolean") DEBUG_LOG = settings.debug_logs;
+  PLAYER_LIFECYCLE_SETTINGS.forEach((field) => {
+    const input = document.getElementById(field.replace(/_/g, "-"));
+    if (input && typeof settings[field] === "boolean") input.checked = settings[field];
+  });
+}
+
+async function loadBackendSettings() {
+  const invoke = getTauriInvoke();
+  if (!invoke) return;
+  try {
+    let settings = await invoke("get_settings");
+    if (!settings.legacy_migrated) {
+      const values = {};
+      Object.values(LEGACY_SETTING_KEYS).forEach((key) => {
+        const value = localStorage.getItem(key);
+        if (value !== null) values[key] = value;
+      });
+      settings = await invoke("migrate_legacy_settings", { values });
+    }
+    // The key now lives in the backend credential store only.
+    localStorage.removeItem(LEGACY_SETTING_KEYS.api_key);
+    applyBackendSettings(settings);
+    console.log("[SETTINGS] Loaded backend settings");
+    await ensureDefaultServerProfile(invoke);
+    state.apiKey = "";
+    state.hasApiKey = await invoke("has_api_key");
+    state.mediaProxyBase = await invoke("gds_media_base").catch(() => null);
+    const listen = window.__TAURI__ && window.__TAURI__.event ? window.__TAURI__.event.listen : null;
+    if (listen) {
+      listen("settings-changed", (event) => applyBackendSettings(event.payload));
+      listen("server-profile-changed", (event) => applyServerProfile(event.payload));
+      listen("quality-profile-changed", (event) => onQualityProfileChanged(event.payload));
+      listen("categories-changed", (event) => renderCategoryRulesStatus(event.payload));
+      listen("categories-invalid", (event) => onCategoriesInvalid(event.payload));
+      listen("downloads-changed", (event) => renderDownloads(event.payload));
+      listen("download-progress", (event) => onDownloadProgress(event.payload));
+    }
+    invoke("get_categories").then(renderCategoryRulesStatus).catch(() => {});
+    invoke("download_list").then(renderDownloads).catch(() => {});
+  } catch (err) {
+    console.warn("[SETTINGS] Backend settings unavailable:", err);
+  }
+}
+
+function formatCategoryError(error) {
+  if (!error) return "";
+  const where = error.line ? ` (line ${error.line}${error.column ? `, column ${error.column}` : ""})` : "";
+  return `${error.message}${where}`;
+}
+
+// category.yaml is validated and hot-reloaded by the backend; show which rules are in use.
+function renderCategoryRulesStatus(view) {
+  const el = document.getElementById("category-rules-status");
+  if (!el || !view) return;
+  if (view.error) {
+    el.style.color = "var(--danger)";
+    el.textContent = `category.yaml 오류: ${formatCategoryError(view.error)} — 이전 규칙 사용 중`;
+  } else {
+    el.style.color = "var(--text-secondary)";
+    el.textContent = view.source === "user" ? `category.yaml: ${view.file}` : "category.yaml: 기본 규칙";
+  }
+}
+
+function onCategoriesInvalid(payload) {
+  console.warn("[CATEGORY] Invalid category.yaml:", payload);
+  renderCategoryRulesStatus({ error: payload, file: payload?.file });
+  alert(`category.yaml 오류\n${formatCategoryError(payload)}\n\n이전 규칙을 계속 사용합니다.`);
+}
+
+// Offline downloads are run by the backend (downloads.rs); this only lists them.
+const DOWNLOAD_STATUS_LABELS = {
+  queued: "대기 중",
+  downloading: "다운로드 중",
+  paused: "일시정지",
+  completed: "완료",
+  failed: "실패",
+};
+
+function describeDownload(item) {
+  const done = formatSize(item.bytes_done);
+  const progress = item.bytes_total ? `${done} / ${formatSize(item.bytes_total)}` : done;
+  const status = DOWNLOAD_STATUS_LABELS[item.status] || item.status;
+  if (item.status === "failed" && item.error) return `${status}: ${item.error}`;
+  if (item.status === "completed") return `${status} · ${formatSize(item.bytes_total || item.bytes_done)}`;
+  return `${status} · ${progress}`;
+}
+
+function renderDownloads(items) {
+  const list = document.getElementById("download-list");
+  if (!list || !Array.isArray(items)) return;
+  list.innerHTML = "";
+  if (!items.length) {
+    list.innerHTML = `<p style="font-size: 0.8rem; color: var(--text-secondary);">다운로드한 항목이 없습니다.</p>`;
+    return;
+  }
+  const invoke = getTauriInvoke();
+  items.forEach((item) => {
+    const row = document.createElement("div");
+    row.className = "settings-group download-row";
+    row.dataset.downloadId = item.id;
+    row.style.cssText = "display: flex; align-items: center; gap: 8px;";
+    const info = document.createElement("div");
+    info.style.cssText = "flex: 1; min-width: 0;";
+    const title = document.createElement("div");
+    title.textContent = item.title || item.path;
+    title.style.cssText = "overflow: hidden; text-overflow: ellipsis; white-space: nowrap;";
+    const detail = document.createElement("div");
+    detail.className = "download-detail";
+    detail.style.cssText = "font-size: 0.8rem; color: var(--text-secondary);";
+    detail.textContent = describeDownload(item);
+    info.append(title, detail);
+    row.appendChild(info);
+
+    const action = (label, command) => {
+      const btn = document.createElement("button");
+      btn.className = "btn btn-secondary";
+      btn.textContent = label;
+      btn.addEventListener("click", () => {
+        invoke(command, { id: item.id }).catch((err) => alert(`❌ ${err?.message || err}`));
+      });
+      row.appendChild(btn);
+    };
+    if (item.status === "downloading" || item.status === "queued") action("일시정지", "download_pause");
+    if (item.status === "paused" || item.status === "failed") action("재개", "download_resume");
+    action(item.status === "completed" ? "삭제" : "취소", "download_cancel");
+    list.appendChild(row);
+  });
+}
+
+function onDownloadProgress(progress) {
+  if (!progress) return;
+  const row = document.querySelector(`.download-row[data-download-id="${CSS.escape(progress.id)}"]`);
+  const detail = row && row.querySelector(".download-detail");
+  if (!detail) return;
+  const total = progress.bytes_total ? ` / ${formatSize(progress.bytes_total)}` : "";
+  detail.textContent = `${DOWNLOAD_STATUS_LABELS.downloading} · ${formatSize(progress.bytes_done)}${total} · ${formatSize(progress.bytes_per_sec)}/s`;
+}
+
+async function downloadCurrentMedia() {
+  const invoke = getTauriInvoke();
+  if (!invoke || !state.currentMediaPath) return;
+  try {
+    await invoke("download_enqueue", {
+      request: {
+        title: state.currentMediaTitle || state.currentMediaPath,
+        path: state.currentMediaPath,
+        source_id: Number(normalizeSourceId(state.currentMediaSourceId)),
+      },
+    });
+    alert("다운로드 목록에 추가했습니다.");
+  } catch (err) {
+    alert(`❌ Download failed: ${err?.message || err}`);
+  }
+}
+
+// A fresh install has no server in settings, so the backend seeds no profile; start
+// from the built-in test server. Migrated settings get theirs from the backend.
+async function ensureDefaultServerProfile(invoke) {
+  const list = await invoke("list_server_profiles");
+  if ((list?.profiles || []).length || !state.serverUrl || !state.apiKey) return;
+  if (await invoke("has_api_key")) return;
+  const profile = await invoke("save_server_profile", {
+    profile: { name: "Default", url: state.serverUrl, api_key: state.apiKey },
+  });
+  await invoke("set_active_server_profile", { id: profile.id });
+}
+
+async function applyServerProfile(profile) {
+  if (!profile) return;
+  const invoke = getTauriInvoke();
+  state.serverUrl = profile.url;
+  if (invoke) state.hasApiKey = await invoke("has_api_key").catch(() => false);
+  if (typeof profile.preferred_source_id === "number") state.sourceId = profile.preferred_source_id;
+  clearApiResponseCache("profile-change");
+  console.log("[PROFILES] Active server:", profile.name);
+}
+
+// Sliders fire on every step; batch changes into one update_settings call.
+let pendingSettingsPatch = {};
+let pendingSettingsTimer = null;
+
+function persistSettings(patch) {
+  const invoke = getTauriInvoke();
+  if (!invoke) {
+    Object.entries(patch).forEach(([field, value]) => {
+      const key = LEGACY_SETTING_KEYS[field];
+      if (key) localStorage.setItem(key, String(value));
+    });
+    return;
+  }
+  Object.assign(pendingSettingsPatch, patch);
+  clearTimeout(pendingSettingsTimer);
+  pendingSettingsTimer = setTimeout(() => {
+    const next = pendingSettingsPatch;
+    pendingSettingsPatch = {};
+    invoke("update_settings", { patch: next }).catch((err) => {
+      console.warn("[SETTINGS] update_settings failed:", err);
+    });
+  }, 300);
+}
+
+async function persistApiKey(key) {
+  const invoke = getTauriInvoke();
+  if (!invoke) {
+    localStorage.setItem(LEGACY_SETTING_KEYS.api_key, key);
+    return;
+  }
+  try {
+    await invoke("set_api_key", { key });
+  } catch (err) {
+    console.warn("[SETTINGS] set_api_key failed:", err);
+  }
+}
+
+// Profiles are defined by the backend (built-ins + quality_profiles.toml/yaml).
 function normalizeQualityProfile(profile) {
-  if (profile === "quality" || profile === "smooth") return profile;
-  return "balanced";
+  const name = typeof profile === "string" ? profile.trim() : "";
+  return name || "balanced";
+}
+
+function onQualityProfileChanged(report) {
+  if (!report?.profile) return;
+  state.qualityProfile = report.profile;
+  (report.rejected || []).forEach((r) => {
+    console.warn(`[QUALITY] ${report.profile}: mpv rejected ${r.name}=${r.value} (${r.error})`);
+  });
 }
 
 function normalizeSourceId(value) {
@@ -1137,14 +1462,14 @@ async function applyNativeQualityProfile(profile, silent = false) {
   const invoke = getTauriInvoke();
   const normalized = normalizeQualityProfile(profile);
   state.qualityProfile = normalized;
-  localStorage.setItem("flashplex_quality_profile", normalized);
+  persistSettings({ quality_profile: normalized });
 
   if (!invoke || !state.isNativeActive) return normalized;
   try {
-    const applied = await invoke("set_quality_profile", { profile: normalized });
-    const finalProfile = normalizeQualityProfile(applied || normalized);
+    const report = await invoke("set_quality_profile", { profile: normalized });
+    const finalProfile = normalizeQualityProfile(report?.profile || normalized);
     state.qualityProfile = finalProfile;
-    localStorage.setItem("flashplex_quality_profile", finalProfile);
+    if (finalProfile !== normalized) persistSettings({ quality_profile: finalProfile });
     if (!silent) console.log("[QUALITY] Applied profile:", finalProfile);
     return finalProfile;
   } catch (err) {
@@ -1195,10 +1520,12 @@ async function recreateNativePlayerAfterResize(reason = "fullscreen") {
 
     await invoke("launch_mpv_player", {
       title: source.title,
-      url: source.url,
+      url: source.url || null,
       subtitle_url: source.subtitleUrl || null,
       start_pos: mpvState.position,
       start_paused: true,
+      path: source.path || null,
+      source_id: Number(normalizeSourceId(source.source_id)),
     });
 
     // Wait until mpv is ready enough to accept seek reliably.
@@ -1445,16 +1772,15 @@ async function checkAndSelectSubtitles(retries = 4) {
 
 async function resolveBestSubtitleForAndroid(item, bpath, fallbackUrl) {
   try {
-    const videoInfoUrl = `${state.serverUrl}/gds_dviewer/normal/get_video_info?bpath=${bpath}&source_id=${normalizeSourceId(item.source_id)}&apikey=${state.apiKey}`;
-    if (window.tlog) window.tlog(`[SUB-ANDROID] Fetching video info: ${videoInfoUrl}`);
-    const res = await fetch(videoInfoUrl).then((r) => r.json());
+    const videoInfoEndpoint = `get_video_info?bpath=${bpath}&source_id=${normalizeSourceId(item.source_id)}`;
+    if (window.tlog) window.tlog(`[SUB-ANDROID] Fetching video info: ${videoInfoEndpoint}`);
+    const res = await gdsFetch(videoInfoEndpoint);
     const tracks = res?.data?.subtitles || [];
 
     const toFullUrl = (s) => {
       if (!s?.url) return null;
       if (s.url.startsWith('http')) return s.url;
-      const u = `${state.serverUrl}${s.url}`;
-      return u.includes('apikey=') ? u : `${u}${u.includes('?') ? '&' : '?'}apikey=${state.apiKey}`;
+      return gdsMediaUrlFromPath(s.url);
     };
 
     const isKo = (s) => {
@@ -1575,7 +1901,7 @@ function initElements() {
 }
 
 // Initialize
-window.addEventListener("DOMContentLoaded", () => {
+window.addEventListener("DOMContentLoaded", async () => {
   console.log("[STARTUP] Application booting...");
   document.documentElement.classList.add("appletv-skin");
   document.body.classList.add("appletv-skin");
@@ -1655,6 +1981,7 @@ window.addEventListener("DOMContentLoaded", () => {
     console.warn("[STARTUP] Bridge check failed:", err);
   }
 
+  await loadBackendSettings();
   initElements();
   detectNativeArch();
   if (window.lucide) optimizedCreateIcons();
@@ -1693,7 +2020,7 @@ window.addEventListener("DOMContentLoaded", () => {
   }
 
   // Initial data load
-  if (state.serverUrl && state.apiKey) {
+  if (hasServerCredentials()) {
     console.log("[STARTUP] Starting initial library load...");
     loadLibrary();
     switchView("library");
@@ -2119,6 +2446,10 @@ function clearApiResponseCache(reason = "manual") {
   state.apiCacheHits = 0;
   state.apiCacheMisses = 0;
   clearApiDiskCache().catch(() => {});
+  const invoke = getTauriInvoke();
+  if (invoke) {
+    invoke("clear_cache").catch((err) => dwarn("[GDS-CACHE] clear_cache failed:", err));
+  }
 
   // [PHASE 1] Clear other unbounded caches
   state.episodeMetaCache.clear();
@@ -2250,7 +2581,7 @@ function showCategorySubMenu(category, tabEl) {
 
 // Global API Fetch
 async function gdsFetch(endpoint, options = {}) {
-  if (!state.serverUrl || !state.apiKey) throw new Error("Setup required");
+  if (!hasServerCredentials()) throw new Error("Setup required");
 
   let baseUrl = state.serverUrl.trim();
   if (!baseUrl.startsWith("http://") && !baseUrl.startsWith("https://")) {
@@ -2259,6 +2590,24 @@ async function gdsFetch(endpoint, options = {}) {
 
   const url = `${baseUrl.replace(/\/$/, "")}/gds_dviewer/normal/${endpoint.replace(/^\//, "")}`;
   const method = options.method || "GET";
+
+  // With Tauri every call goes through the Rust client, which holds the API key and
+  // owns the response cache (memory + disk, per-endpoint TTLs, ETag revalidation).
+  // The direct fetch below is only for running the UI in a plain browser.
+  const invoke = getTauriInvoke();
+  if (invoke) {
+    const path = endpoint.replace(/^\//, "");
+    const data = String(method).toUpperCase() === "POST"
+      ? await invoke("gds_post", {
+          endpoint: path,
+          form: Array.from(new URLSearchParams(options.body || "").entries()),
+        })
+      : await invoke("gds_get", { endpoint: path, cache_mode: options.cacheMode || null });
+    if (options.signal && options.signal.aborted) {
+      throw new DOMException("Aborted", "AbortError");
+    }
+    return data;
+  }
   const cacheable = shouldUseApiResponseCache(method, endpoint, options);
   const cacheKey = buildApiCacheKey(baseUrl, endpoint, method, options.source_id);
   const cacheTtlMs = getApiCacheTtlMs(endpoint);
@@ -2552,7 +2901,7 @@ async function loadLibrary(forceRefresh = false, isAppend = false) {
       let __base = (state.serverUrl || "").trim();
       if (!/^https?:\/\//i.test(__base)) __base = `http://${__base}`;
       __base = __base.replace(/\/$/, "");
-      console.log(`[${virtualCfg.debugTag}] ${__base}/gds_dviewer/normal/${virtualCfg.endpoint}?${params.toString()}&apikey=${state.apiKey}`);
+      console.log(`[${virtualCfg.debugTag}] ${__base}/gds_dviewer/normal/${virtualCfg.endpoint}?${params.toString()}`);
       const responses = [await gdsFetch(`${virtualCfg.endpoint}?${params.toString()}`, { signal: requestSignal })];
       if (isStaleFreshRequest()) return;
 
@@ -3221,7 +3570,7 @@ function renderFolderContextPanel(items = []) {
     firstFolder?.meta_poster || firstFolder?.poster ||
     firstMedia?.meta_poster || firstMedia?.poster || (
     mediaPath
-      ? `${state.serverUrl}/gds_dviewer/normal/thumbnail?bpath=${toUrlSafeBase64(mediaPath)}&source_id=${normalizeSourceId(firstMedia?.source_id)}&w=960&apikey=${state.apiKey}`
+      ? gdsThumbnailUrl(toUrlSafeBase64(mediaPath), firstMedia?.source_id, 960)
       : `${state.serverUrl}/gds_dviewer/static/img/no_poster.png`
   );
   const summary = (
@@ -3755,10 +4104,10 @@ function renderGrid(container, items, isFolderCategory = false, isAppend = false
         const category = item.category || "other";
         if (["video", "animation", "music_video"].includes(category)) {
           const bpath = toUrlSafeBase64(item.path || "");
-          poster = `${state.serverUrl}/gds_dviewer/normal/thumbnail?bpath=${bpath}&source_id=${normalizeSourceId(item.source_id)}&w=${adaptiveThumbWidth}&apikey=${state.apiKey}`;
+          poster = gdsThumbnailUrl(bpath, item.source_id, adaptiveThumbWidth);
         } else if (item.category === "audio") {
           const bpath = toUrlSafeBase64(item.path || "");
-          poster = `${state.serverUrl}/gds_dviewer/normal/album_art?bpath=${bpath}&source_id=${normalizeSourceId(item.source_id)}&apikey=${state.apiKey}`;
+          poster = gdsAlbumArtUrl(bpath, item.source_id);
         }
       }
     }
@@ -4024,8 +4373,6 @@ async function performSearch(query) {
       console.log(`[SEARCH] Background search for "${query}"...`);
       const data = await invoke("search_gds", {
         query: query,
-        serverUrl: state.serverUrl,
-        apiKey: state.apiKey,
         category: "video",
       });
       if (data && (data.ret === "success" || data.list)) {
@@ -4117,7 +4464,7 @@ function getStreamUrlFromItem(item) {
   if (cleanPath && !cleanPath.startsWith("/")) cleanPath = "/" + cleanPath;
   const bpath = toUrlSafeBase64(cleanPath);
   if (!bpath) return "";
-  return `${state.serverUrl}/gds_dviewer/normal/stream?bpath=${bpath}&source_id=${normalizeSourceId(item.source_id)}&apikey=${state.apiKey}`;
+  return gdsMediaUrl("stream", { bpath, source_id: normalizeSourceId(item.source_id) });
 }
 
 function getPreviewStreamUrlFromItem(item) {
@@ -4128,7 +4475,7 @@ function getPreviewStreamUrlFromItem(item) {
   if (!bpath) return "";
   const ext = getPathExtension(cleanPath);
   const endpoint = ext === "mkv" ? "stream_preview" : "stream";
-  return `${state.serverUrl}/gds_dviewer/normal/${endpoint}?bpath=${bpath}&source_id=${normalizeSourceId(item.source_id)}&apikey=${state.apiKey}`;
+  return gdsMediaUrl(endpoint, { bpath, source_id: normalizeSourceId(item.source_id) });
 }
 
 async function resolvePlayablePreviewItem(item) {
@@ -4162,7 +4509,7 @@ async function resolvePlayablePreviewItem(item) {
 
       const bpath = toUrlSafeBase64(cur.path);
       if (!bpath) continue;
-      const endpoint = `explorer/list?bpath=${bpath}&source_id=${sourceId}&limit=80&apikey=${state.apiKey}`;
+      const endpoint = `explorer/list?bpath=${bpath}&source_id=${sourceId}&limit=80`;
       const data = await gdsFetch(endpoint);
       const list = data?.list || data?.data || data?.items || [];
       list.forEach((child) => {
@@ -4224,7 +4571,12 @@ function resolvePosterUrl(rawUrl, item, width = 640) {
   }
 
   // Already-proxied/ready URLs from gds_dviewer should be used as-is.
-  if (raw.includes("/gds_dviewer/normal/proxy_image") || raw.includes("/gds_dviewer/normal/thumbnail")) {
+  if (
+    raw.includes("/gds_dviewer/normal/proxy_image") ||
+    raw.includes("/gds_dviewer/normal/thumbnail") ||
+    raw.startsWith("gdsimg://") ||
+    raw.startsWith("http://gdsimg.localhost/")
+  ) {
     return raw;
   }
 
@@ -4239,7 +4591,7 @@ function resolvePosterUrl(rawUrl, item, width = 640) {
 
   // Absolute external URL -> proxy through gds_dviewer image proxy.
   if (/^https?:\/\//i.test(raw)) {
-    return `${state.serverUrl}/gds_dviewer/normal/proxy_image?url=${encodeURIComponent(raw)}&apikey=${state.apiKey}`;
+    return gdsProxyImageUrl(raw);
   }
 
   // If metadata stores a file-ish path, build thumbnail from that path directly.
@@ -4248,7 +4600,7 @@ function resolvePosterUrl(rawUrl, item, width = 640) {
     if (!rawPath.startsWith("/")) rawPath = `/${rawPath}`;
     const rawBpath = toUrlSafeBase64(rawPath);
     if (rawBpath) {
-      return `${state.serverUrl}/gds_dviewer/normal/thumbnail?bpath=${rawBpath}&source_id=${normalizeSourceId(item?.source_id)}&w=${width}&apikey=${state.apiKey}`;
+      return gdsThumbnailUrl(rawBpath, item?.source_id, width);
     }
   }
 
@@ -4257,7 +4609,7 @@ function resolvePosterUrl(rawUrl, item, width = 640) {
   if (thumbPath && !thumbPath.startsWith("/")) thumbPath = `/${thumbPath}`;
   const bpath = toUrlSafeBase64(thumbPath);
   if (!bpath) return noPoster;
-  return `${state.serverUrl}/gds_dviewer/normal/thumbnail?bpath=${bpath}&source_id=${normalizeSourceId(item?.source_id)}&w=${width}&apikey=${state.apiKey}`;
+  return gdsThumbnailUrl(bpath, item?.source_id, width);
 }
 
 function getPosterUrlFromItem(item, width = 640, preferThumb = false) {
@@ -4274,7 +4626,58 @@ function getPosterUrlFromItem(item, width = 640, preferThumb = false) {
   if (thumbPath && !thumbPath.startsWith("/")) thumbPath = `/${thumbPath}`;
   const bpath = toUrlSafeBase64(thumbPath);
   if (!bpath) return noPoster;
-  return `${state.serverUrl}/gds_dviewer/normal/thumbnail?bpath=${bpath}&source_id=${normalizeSourceId(item.source_id)}&w=${width}&apikey=${state.apiKey}`;
+  return gdsThumbnailUrl(bpath, item.source_id, width);
+}
+
+// Posters and thumbnails go through the backend `gdsimg://` protocol, which caches
+// them on disk and keeps the apikey out of the DOM. Without Tauri they fall back to
+// signed server URLs.
+function gdsImageUrl(kind, params) {
+  const query = gdsQueryString(params);
+  if (getTauriInvoke()) {
+    // WebView2 / Android webviews only route custom schemes as http://<scheme>.localhost.
+    const origin = /Windows|Android/i.test(navigator.userAgent) ? "http://gdsimg.localhost" : "gdsimg://localhost";
+    return `${origin}/${kind}?${query}`;
+  }
+  return `${state.serverUrl}/gds_dviewer/normal/${kind}?${query}&apikey=${state.apiKey}`;
+}
+
+function gdsThumbnailUrl(bpath, sourceId, width) {
+  return gdsImageUrl("thumbnail", { bpath, source_id: normalizeSourceId(sourceId), w: width });
+}
+
+function gdsAlbumArtUrl(bpath, sourceId) {
+  return gdsImageUrl("album_art", { bpath, source_id: normalizeSourceId(sourceId) });
+}
+
+function gdsProxyImageUrl(url) {
+  return gdsImageUrl("proxy_image", { url });
+}
+
+// Streams and subtitles that <video>/<track> or ExoPlayer load by URL go through the
+// backend media proxy (media_proxy.rs), which adds the apikey. Without Tauri they fall
+// back to signed server URLs.
+function gdsMediaUrl(endpoint, params) {
+  const query = gdsQueryString(params);
+  if (state.mediaProxyBase) return `${state.mediaProxyBase}/${endpoint}?${query}`;
+  return `${state.serverUrl}/gds_dviewer/normal/${endpoint}?${query}&apikey=${state.apiKey}`;
+}
+
+// Server-relative media path from an API response (e.g. a sidecar subtitle
+// "/gds_dviewer/normal/external_subtitle?..."); null if the proxy can't serve it.
+function gdsMediaUrlFromPath(path) {
+  const match = String(path || "").match(/^\/?gds_dviewer\/normal\/(.+)$/);
+  if (!match) return null;
+  if (state.mediaProxyBase) return `${state.mediaProxyBase}/${match[1]}`;
+  const separator = match[1].includes("?") ? "&" : "?";
+  return `${state.serverUrl}/gds_dviewer/normal/${match[1]}${separator}apikey=${state.apiKey}`;
+}
+
+function gdsQueryString(params) {
+  return Object.entries(params)
+    .filter(([, v]) => v !== undefined && v !== null && v !== "")
+    .map(([k, v]) => `${k}=${encodeURIComponent(v)}`)
+    .join("&");
 }
 
 function buildFolderThumbUrl(pathValue, sourceId, width = 640) {
@@ -4282,7 +4685,7 @@ function buildFolderThumbUrl(pathValue, sourceId, width = 640) {
   if (!clean) return "";
   const bpath = toUrlSafeBase64(clean.startsWith("/") ? clean : `/${clean}`);
   if (!bpath) return "";
-  return `${state.serverUrl}/gds_dviewer/normal/thumbnail?bpath=${bpath}&source_id=${normalizeSourceId(sourceId)}&w=${width}&apikey=${state.apiKey}`;
+  return gdsThumbnailUrl(bpath, sourceId, width);
 }
 
 function ensureLeadingSlash(pathValue) {
@@ -5948,30 +6351,101 @@ function setupSettings() {
   const btnResetCategories = document.getElementById("btn-reset-categories");
   const categoryMappingList = document.getElementById("category-mapping-list");
 
+  const profileSelect = document.getElementById("server-profile");
+  const profileNameInput = document.getElementById("server-profile-name");
+  const profileSourceInput = document.getElementById("server-profile-source");
+  const btnDeleteProfile = document.getElementById("btn-delete-profile");
+  let serverProfiles = [];
+
   serverUrlInput.value = state.serverUrl;
   apiKeyInput.value = state.apiKey;
 
+  function fillProfileForm(id) {
+    const profile = serverProfiles.find((p) => p.id === id);
+    profileNameInput.value = profile ? profile.name : "";
+    serverUrlInput.value = profile ? profile.url : "";
+    // Keys never leave the backend; a blank field keeps the stored one.
+    apiKeyInput.value = "";
+    apiKeyInput.placeholder = profile && profile.has_key ? "Stored (leave blank to keep)" : "Enter your GDS API Key";
+    profileSourceInput.value = profile && typeof profile.preferred_source_id === "number" ? profile.preferred_source_id : "";
+  }
+
+  async function refreshServerProfiles(selectId) {
+    const invoke = getTauriInvoke();
+    if (!invoke) {
+      profileSelect.closest(".settings-group").style.display = "none";
+      profileNameInput.closest(".settings-group").style.display = "none";
+      profileSourceInput.closest(".settings-group").style.display = "none";
+      return;
+    }
+    try {
+      const list = await invoke("list_server_profiles");
+      serverProfiles = list?.profiles || [];
+      profileSelect.innerHTML = "";
+      serverProfiles.forEach((p) => {
+        const opt = document.createElement("option");
+        opt.value = p.id;
+        opt.textContent = p.active ? `${p.name} (active)` : p.name;
+        profileSelect.appendChild(opt);
+      });
+      const newOpt = document.createElement("option");
+      newOpt.value = "";
+      newOpt.textContent = "+ New profile";
+      profileSelect.appendChild(newOpt);
+      profileSelect.value = selectId ?? list?.active ?? "";
+      fillProfileForm(profileSelect.value);
+    } catch (err) {
+      console.warn("[PROFILES] list_server_profiles failed:", err);
+    }
+  }
+
+  // Picking a saved profile health-checks it and switches to it when reachable.
+  profileSelect.addEventListener("change", async () => {
+    const id = profileSelect.value;
+    fillProfileForm(id);
+    const invoke = getTauriInvoke();
+    if (!invoke || !id) return;
+    try {
+      const result = await invoke("check_server", { profile: id });
+      if (result.activated) {
+        await refreshServerProfiles(id);
+        loadLibrary(true);
+      } else {
+        alert(`❌ ${profileNameInput.value} is not reachable: ${result.error?.message || result.auth}`);
+      }
+    } catch (err) {
+      console.warn("[PROFILES] check_server failed:", err);
+    }
+  });
+
+  btnDeleteProfile.addEventListener("click", async () => {
+    const id = profileSelect.value;
+    const invoke = getTauriInvoke();
+    if (!invoke || !id || !confirm(`Delete profile "${profileNameInput.value}"?`)) return;
+    try {
+      await invoke("delete_server_profile", { id });
+    } catch (err) {
+      console.warn("[PROFILES] delete_server_profile failed:", err);
+    }
+    await refreshServerProfiles();
+  });
+
+  refreshServerProfiles();
+
   // [NEW] Subtitle Settings Initialization
   const subtitleSizeInput = document.getElementById("subtitle-size");
   const subtitlePosInput = document.getElementById("subtitle-pos");
   const subtitleSizeVal = document.getElementById("subtitle-size-val");
   const subtitlePosVal = document.getElementById("subtitle-pos-val");
 
-  // Load from state/localStorage
-  state.subtitleSize = parseFloat(
-    localStorage.getItem("flashplex_sub_size") || "1.0",
-  );
-  state.subtitlePos = parseFloat(
-    localStorage.getItem("flashplex_sub_pos") || "0.0",
-  );
-
+  // state.subtitleSize / subtitlePos were loaded from backend settings at startup.
   if (subtitleSizeInput) {
     subtitleSizeInput.value = state.subtitleSize;
     subtitleSizeVal.innerText = state.subtitleSize.toFixed(1) + "x";
     subtitleSizeInput.addEventListener("input", (e) => {
       state.subtitleSize = parseFloat(e.target.value);
       subtitleSizeVal.innerText = state.subtitleSize.toFixed(1) + "x";
-      localStorage.setItem("flashplex_sub_size", state.subtitleSize);
+      persistSettings({ subtitle_scale: state.subtitleSize });
       // [NEW] Apply in real-time if native player is active
       const inv = getTauriInvoke();
       if (state.isNativeActive && inv) {
@@ -5980,13 +6454,62 @@ function setupSettings() {
     });
   }
 
+  PLAYER_LIFECYCLE_SETTINGS.forEach((field) => {
+    const input = document.getElementById(field.replace(/_/g, "-"));
+    if (input) input.addEventListener("change", (e) => persistSettings({ [field]: e.target.checked }));
+  });
+
+  const debugLogsInput = document.getElementById("debug-logs");
+  if (debugLogsInput) {
+    debugLogsInput.checked = DEBUG_LOG;
+    debugLogsInput.addEventListener("change", (e) => {
+      DEBUG_LOG = e.target.checked;
+      persistSettings({ debug_logs: DEBUG_LOG });
+    });
+  }
+
+  const btnExportLogs = document.getElementById("btn-export-logs");
+  if (btnExportLogs) {
+    btnExportLogs.addEventListener("click", async () => {
+      const invoke = getTauriInvoke();
+      if (!invoke) return;
+      try {
+        const path = await invoke("export_logs");
+        alert(`Logs saved to:\n${path}`);
+      } catch (err) {
+        alert("❌ Log export failed: " + (err?.message || err));
+      }
+    });
+  }
+
+  const btnPlayerDiagnostics = document.getElementById("btn-player-diagnostics");
+  if (btnPlayerDiagnostics) {
+    btnPlayerDiagnostics.addEventListener("click", async () => {
+      const invoke = getTauriInvoke();
+      if (!invoke) return;
+      try {
+        const report = await invoke("player_diagnostics", { log_limit: 200 });
+        const text = JSON.stringify(report, null, 2);
+        try {
+          await navigator.clipboard.writeText(text);
+          alert("Player diagnostics copied to clipboard.");
+        } catch (_) {
+          // Clipboard access can be denied in the webview; show it for manual copy instead.
+          prompt("Player diagnostics:", text);
+        }
+      } catch (err) {
+        alert("❌ Diagnostics failed: " + (err?.message || err));
+      }
+    });
+  }
+
   if (subtitlePosInput) {
     subtitlePosInput.value = state.subtitlePos;
     subtitlePosVal.innerText = state.subtitlePos.toFixed(0) + "px";
     subtitlePosInput.addEventListener("input", (e) => {
       state.subtitlePos = parseFloat(e.target.value);
       subtitlePosVal.innerText = state.subtitlePos.toFixed(0) + "px";
-      localStorage.setItem("flashplex_sub_pos", state.subtitlePos);
+      persistSettings({ subtitle_pos: state.subtitlePos });
       // [NEW] Apply in real-time if native player is active
       const inv = getTauriInvoke();
       if (state.isNativeActive && inv) {
@@ -6016,7 +6539,7 @@ function setupSettings() {
   });
 
   async function loadCategoryMapping() {
-    if (!state.serverUrl || !state.apiKey) return;
+    if (!hasServerCredentials()) return;
 
     try {
       const data = await gdsFetch("get_category_mapping");
@@ -6065,7 +6588,6 @@ function setupSettings() {
 
       const bodyParams = new URLSearchParams();
       bodyParams.append("mapping", JSON.stringify(newMapping));
-      bodyParams.append("apikey", state.apiKey);
 
       const data = await gdsFetch(`save_category_mapping`, {
         method: "POST",
@@ -6131,7 +6653,7 @@ function setupSettings() {
     const url = serverUrlInput.value.trim();
     const key = apiKeyInput.value.trim();
 
-    if (!url || !key) {
+    if (!url || (!key && !profileSelect.value)) {
       alert("Please enter both Server URL and API Key");
       return;
     }
@@ -6141,6 +6663,30 @@ function setupSettings() {
       '<i data-lucide="loader-2" class="animate-spin"></i> Testing...';
     optimizedCreateIcons();
 
+    const invoke = getTauriInvoke();
+    if (invoke) {
+      // Unsaved edits are probed as a draft; a blank key means "use the stored one".
+      const id = profileSelect.value;
+      const target = id && !key ? id : { url, api_key: key };
+      try {
+        const result = await invoke("check_server", { profile: target, activate: false });
+        if (result.ok) {
+          alert(`✅ Connection successful! (${result.latency_ms} ms${result.version ? `, ${result.version}` : ""})`);
+        } else if (result.auth === "rejected") {
+          alert("❌ Server reachable but the API key was rejected.");
+        } else {
+          alert("❌ Connection failed: " + (result.error?.message || "Check logs"));
+        }
+      } catch (err) {
+        alert("❌ Connection check failed: " + (err?.message || err));
+      } finally {
+        btnTestConnection.disabled = false;
+        btnTestConnection.innerHTML = '<i data-lucide="zap"></i> Test';
+        optimizedCreateIcons();
+      }
+      return;
+    }
+
     try {
       const testUrl = `${url.replace(/\/$/, "")}/gds_dviewer/normal/search?query=&limit=1&apikey=${key}`;
       const response = await fetch(testUrl);
@@ -6162,10 +6708,45 @@ function setupSettings() {
     }
   }
 
+  async function saveServerProfile(url, key) {
+    const invoke = getTauriInvoke();
+    const id = profileSelect.value || null;
+    const existing = serverProfiles.find((p) => p.id === id);
+    if (!url || (!key && !existing?.has_key)) {
+      alert("Please enter both Server URL and API Key.");
+      return;
+    }
+    const source = profileSourceInput.value.trim();
+    try {
+      const saved = await invoke("save_server_profile", {
+        profile: {
+          id,
+          name: profileNameInput.value.trim() || url,
+          url,
+          api_key: key || null,
+          preferred_source_id: source === "" ? null : Number(source),
+        },
+      });
+      // server-profile-changed updates state.serverUrl / hasApiKey.
+      await invoke("set_active_server_profile", { id: saved.id });
+      await refreshServerProfiles(saved.id);
+    } catch (err) {
+      alert("❌ Could not save profile: " + (err?.message || err));
+      return;
+    }
+    loadLibrary(true);
+    switchView("library");
+  }
+
   function saveSettings() {
     const url = serverUrlInput.value.trim().replace(/\/$/, "");
     const key = apiKeyInput.value.trim();
 
+    if (getTauriInvoke()) {
+      saveServerProfile(url, key);
+      return;
+    }
+
     if (!url || !key) {
       alert("Please enter both Server URL and API Key.");
       return;
@@ -6179,8 +6760,8 @@ function setupSettings() {
       clearApiResponseCache("settings-change");
     }
 
-    localStorage.setItem("gds_server_url", url);
-    localStorage.setItem("gds_api_key", key);
+    persistSettings({ server_url: url });
+    persistApiKey(key);
 
     loadLibrary();
     switchView("library");
@@ -6419,61 +7000,127 @@ function hideWebOscForAndroidExo() {
   document.documentElement.classList.remove("native-player-active");
 }
 
+function applyNativeStateSnapshot(data) {
+  if (!data) return;
+  if (typeof data.position === "number") state.nativePos = data.position;
+  if (typeof data.duration === "number") state.nativeDuration = data.duration;
+  if (typeof data.pause === "boolean") state.nativePaused = data.pause;
+
+  if (ui.oscCurrentTime) ui.oscCurrentTime.textContent = formatTime(state.nativePos);
+  if (ui.oscTotalTime) ui.oscTotalTime.textContent = formatTime(state.nativeDuration);
+  if (ui.oscSubtitle) {
+    ui.oscSubtitle.textContent = `${formatTime(state.nativePos)} / ${formatTime(state.nativeDuration)}`;
+  }
+
+  if (ui.oscHwBadge && typeof data.hwdec === "string") {
+    const rawHw = (data.hwdec || "").toLowerCase();
+    if (state.lastHwDec !== rawHw) {
+      console.log("[PLAYER] HW Status:", rawHw);
+      state.lastHwDec = rawHw;
+    }
+    const isHw = rawHw !== "no" && rawHw !== "" && (
+      rawHw.includes("videotoolbox") ||
+      rawHw.includes("vtb") ||
+      rawHw.includes("auto") ||
+      rawHw.includes("yes")
+    );
+    ui.oscHwBadge.textContent = isHw ? "HW" : "SW";
+    ui.oscHwBadge.className = isHw ? "hw-badge hw" : "hw-badge sw";
+  }
+
+  const percent = state.nativeDuration > 0 ? (state.nativePos / state.nativeDuration) * 100 : 0;
+  if (ui.oscProgressFill) ui.oscProgressFill.style.width = percent + "%";
+  if (ui.oscProgressSlider && !state.isDraggingOscSlider) ui.oscProgressSlider.value = percent;
+  updateOpeningSkipUI();
+  updateNextEpisodeUI();
+
+  const icon = state.nativePaused ? "play" : "pause";
+  if (ui.btnOscPlayPause) {
+    ui.btnOscPlayPause.innerHTML = `<i data-lucide="${icon}"></i>`;
+    if (window.lucide) optimizedCreateIcons();
+  }
+  if (ui.btnOscCenterPlay) {
+    ui.btnOscCenterPlay.style.display = state.nativePaused ? "flex" : "none";
+    ui.btnOscCenterPlay.innerHTML = `<i data-lucide="${icon}"></i>`;
+    if (window.lucide) optimizedCreateIcons();
+  }
+}
+
+// mpv pushes time-pos/pause/duration as Tauri events (see player/events.rs);
+// polling is only used when the event API is unavailable.
+let nativeStateUnlisten = [];
+
+// Events carry the id of the player they came from; the UI follows the main one.
+const MAIN_PLAYER_ID = "main";
+function isMainPlayerEvent(event) {
+  const id = event && event.payload ? event.payload.player_id : null;
+  return !id || id === MAIN_PLAYER_ID;
+}
+
+// OSC buttons and mpv key bindings ask for app actions with
+// `script-message gds-app-action <action>` (see gds_osc.lua).
+function handleNativeScriptMessage(payload) {
+  if (payload.name !== "gds-app-action") return;
+  const action = (payload.args || [])[0];
+  console.log("[PLAYER] App action from mpv:", action);
+  if (action === "next-episode") {
+    if (state.nextEpisodeItem && ui.btnOscNextEpisode) {
+      ui.btnOscNextEpisode.click();
+    } else {
+      const invoke = getTauriInvoke();
+      if (invoke) invoke("queue_next").catch((err) => dwarn("[NEXT-EP] queue_next failed:", err?.message || err));
+    }
+  } else if (action === "subtitle-picker") {
+    if (ui.btnOscSubtitles) ui.btnOscSubtitles.click();
+  }
+}
+
+async function startNativeStateEvents() {
+  const listen = window.__TAURI__ && window.__TAURI__.event ? window.__TAURI__.event.listen : null;
+  if (!listen || nativeStateUnlisten.length) return !!listen;
+  const invoke = getTauriInvoke();
+  const onEvent = (mapper) => (event) => {
+    if (!state.isNativeActive || !isMainPlayerEvent(event)) return;
+    applyNativeStateSnapshot(mapper(event.payload || {}));
+  };
+  nativeStateUnlisten = await Promise.all([
+    listen("mpv-time-pos", onEvent((p) => ({ position: p.position }))),
+    listen("mpv-pause", onEvent((p) => ({ pause: p.paused }))),
+    listen("mpv-duration", onEvent((p) => ({ duration: p.duration }))),
+    // hwdec is only known once decoding starts; fetch one full snapshot then.
+    listen("mpv-playback-restart", (event) => {
+      if (!state.isNativeActive || !invoke || !isMainPlayerEvent(event)) return;
+      invoke("get_mpv_state").then(applyNativeStateSnapshot).catch(() => {});
+    }),
+    listen("mpv-playlist", (event) => {
+      if (!state.isNativeActive || !isMainPlayerEvent(event)) return;
+      syncNativeQueueCurrent().catch(() => {});
+    }),
+    listen("mpv-script-message", (event) => {
+      if (!state.isNativeActive || !isMainPlayerEvent(event)) return;
+      handleNativeScriptMessage(event.payload || {});
+    }),
+    listen("player-first-frame", (event) => {
+      const t = event.payload || {};
+      console.log(`[PLAYER] First frame (${t.player_id}) in ${t.ms} ms, ${t.cold ? "cold" : "warm"} start`);
+    }),
+  ]);
+  console.log("[PLAYER] Subscribed to native mpv events");
+  return true;
+}
+
 function startNativeStatePolling() {
   if (nativeStatePollTimer) return;
   const invoke = getTauriInvoke();
   if (!invoke) return;
-  console.log("[PLAYER] Starting polling loop for native state...");
-  nativeStatePollTimer = setInterval(() => {
-    if (!state.isNativeActive) return;
-    invoke("get_mpv_state")
-      .then((data) => {
-        if (!data) return;
-        state.nativePos = typeof data.position === "number" ? data.position : 0;
-        state.nativeDuration = typeof data.duration === "number" ? data.duration : 0;
-        state.nativePaused = !!data.pause;
-
-        if (ui.oscCurrentTime) ui.oscCurrentTime.textContent = formatTime(state.nativePos);
-        if (ui.oscTotalTime) ui.oscTotalTime.textContent = formatTime(state.nativeDuration);
-        if (ui.oscSubtitle) {
-          ui.oscSubtitle.textContent = `${formatTime(state.nativePos)} / ${formatTime(state.nativeDuration)}`;
-        }
-
-        if (ui.oscHwBadge) {
-          const rawHw = (data.hwdec || "").toLowerCase();
-          if (state.lastHwDec !== rawHw) {
-            console.log("[PLAYER] HW Status:", rawHw);
-            state.lastHwDec = rawHw;
-          }
-          const isHw = rawHw !== "no" && rawHw !== "" && (
-            rawHw.includes("videotoolbox") ||
-            rawHw.includes("vtb") ||
-            rawHw.includes("auto") ||
-            rawHw.includes("yes")
-          );
-          ui.oscHwBadge.textContent = isHw ? "HW" : "SW";
-          ui.oscHwBadge.className = isHw ? "hw-badge hw" : "hw-badge sw";
-        }
-
-        const percent = state.nativeDuration > 0 ? (state.nativePos / state.nativeDuration) * 100 : 0;
-        if (ui.oscProgressFill) ui.oscProgressFill.style.width = percent + "%";
-        if (ui.oscProgressSlider && !state.isDraggingOscSlider) ui.oscProgressSlider.value = percent;
-        updateOpeningSkipUI();
-        updateNextEpisodeUI();
-
-        const icon = state.nativePaused ? "play" : "pause";
-        if (ui.btnOscPlayPause) {
-          ui.btnOscPlayPause.innerHTML = `<i data-lucide="${icon}"></i>`;
-          if (window.lucide) optimizedCreateIcons();
-        }
-        if (ui.btnOscCenterPlay) {
-          ui.btnOscCenterPlay.style.display = state.nativePaused ? "flex" : "none";
-          ui.btnOscCenterPlay.innerHTML = `<i data-lucide="${icon}"></i>`;
-          if (window.lucide) optimizedCreateIcons();
-        }
-      })
-      .catch(() => {});
-  }, 500);
+  startNativeStateEvents().then((subscribed) => {
+    if (subscribed || nativeStatePollTimer) return;
+    console.log("[PLAYER] Starting polling loop for native state...");
+    nativeStatePollTimer = setInterval(() => {
+      if (!state.isNativeActive) return;
+      invoke("get_mpv_state").then(applyNativeStateSnapshot).catch(() => {});
+    }, 500);
+  });
 }
 
 function stopNativeStatePolling() {
@@ -6527,7 +7174,7 @@ function playVideo(item) {
     if (window.tlog) window.tlog("[PLAY] Folder detected, resolving first video...");
     const bpath = toUrlSafeBase64(item.path || "");
     // [FIX] Use existing list_directory API instead of non-existent 'playlist'
-    const endpoint = `explorer/list?bpath=${bpath}&source_id=${normalizeSourceId(item.source_id)}&limit=50&apikey=${state.apiKey}`;
+    const endpoint = `explorer/list?bpath=${bpath}&source_id=${normalizeSourceId(item.source_id)}&limit=50`;
     
     if (window.tlog) window.tlog(`[PLAY] Fetching folder contents: ${endpoint}`);
     
@@ -6634,13 +7281,13 @@ function playVideo(item) {
   const bpath = toUrlSafeBase64(cleanPath);
   const encodedPath = encodeURIComponent(cleanPath);
 
-  let streamUrl = `${state.serverUrl}/gds_dviewer/normal/stream?bpath=${bpath}&source_id=${normalizeSourceId(item.source_id)}&apikey=${state.apiKey}`;
-  const subtitleUrl = bpath ? `${state.serverUrl}/gds_dviewer/normal/external_subtitle?bpath=${bpath}&source_id=${normalizeSourceId(item.source_id)}&apikey=${state.apiKey}` : null;
+  let streamUrl = gdsMediaUrl("stream", { bpath, source_id: normalizeSourceId(item.source_id) });
+  const subtitleUrl = bpath ? gdsMediaUrl("external_subtitle", { bpath, source_id: normalizeSourceId(item.source_id) }) : null;
 
   const extension = (cleanPath || "").split(".").pop().toLowerCase();
   console.log("[PLAY] Extension Detected:", extension);
-  console.log("[PLAY] Standardized Stream URL (path):", streamUrl);
-  console.log("[PLAY] Standardized Subtitle URL (bpath):", subtitleUrl);
+  console.log("[PLAY] Standardized Stream URL (path):", redactSecrets(streamUrl));
+  console.log("[PLAY] Standardized Subtitle URL (bpath):", subtitleUrl && redactSecrets(subtitleUrl));
 
   // 4. [HYBRID] Native Playback Routing
   const isAudio =
@@ -6701,10 +7348,11 @@ function playVideo(item) {
     // Desktop Native (MPV for Video)
     if (isDesktop && !isAudio) {
       console.log("[PLAYBACK] Launching Native MPV for:", cleanTitle);
+      // No URLs here: the backend signs stream/subtitle URLs from path + source_id.
       state.nativeSource = {
         title: cleanTitle,
-        url: streamUrl,
-        subtitleUrl,
+        url: null,
+        subtitleUrl: null,
         path: cleanPath,
         source_id: normalizeSourceId(item.source_id),
       };
@@ -6719,16 +7367,21 @@ function playVideo(item) {
       document.body.classList.add("native-player-active");
       document.documentElement.classList.add("native-player-active");
 
-      const cmd = "launch_mpv_player";
       state.isNativeActive = true;
 
-      invoke(cmd, {
-        title: cleanTitle,
-        url: streamUrl,
-        subtitle_url: subtitleUrl,
+      // Resume where the backend history says we stopped (null when finished / never played).
+      invoke("history_get_resume", {
+        media: { path: cleanPath, source_id: Number(normalizeSourceId(item.source_id)) },
       })
+        .catch(() => null)
+        .then((resumePos) => invoke("launch_mpv_player", {
+          title: cleanTitle,
+          start_pos: typeof resumePos === "number" ? resumePos : null,
+          path: cleanPath,
+          source_id: Number(normalizeSourceId(item.source_id)),
+        }))
         .then(() => {
-          console.log(`[PLAYBACK] ${cmd} Success`);
+          console.log("[PLAYBACK] launch_mpv_player Success");
           startNativeStatePolling();
           invoke("resize_native_player", {}).catch(() => {});
           invoke("set_quality_profile", { profile: normalizeQualityProfile(state.qualityProfile) }).catch(() => {});
@@ -6756,36 +7409,20 @@ function playVideo(item) {
           setTimeout(() => {
             checkAndSelectSubtitles();
             
-            // [NEW] Use native_log for terminal visibility
-            const videoInfoUrl = `${state.serverUrl}/gds_dviewer/normal/get_video_info?bpath=${bpath}&source_id=${normalizeSourceId(item.source_id)}&apikey=${state.apiKey}`;
-            if (window.tlog) window.tlog(`[SUB] Fetching video info: ${videoInfoUrl}`);
-            
-            fetch(videoInfoUrl)
-              .then(r => r.json())
-              .then(res => {
-                if (window.tlog) window.tlog(`[SUB] API Response: ${res.ret}, tracks: ${res.data?.subtitles?.length || 0}`);
-                if (res.ret === 'success' && res.data && res.data.subtitles) {
-                  const sidecars = res.data.subtitles.filter(s => s.type === 'sidecar');
-                  if (window.tlog) window.tlog(`[SUB] Sidecar match count: ${sidecars.length}`);
-                  
-                  const sidecarPromises = sidecars.map(s => {
-                    let fullUrl = s.url.startsWith('http') ? s.url : `${state.serverUrl}${s.url}&apikey=${state.apiKey}`;
-                    if (window.tlog) window.tlog(`[SUB] Injecting: ${s.title}`);
-                    return invoke("native_sub_add", { url: fullUrl, title: s.title }).catch(err => {
-                         console.warn("[SUB] native_sub_add failed:", err);
-                    });
-                  });
-
-                  // Once all sidecars are sent, wait a bit and re-run selection to pick the best one
-                  Promise.all(sidecarPromises).then(() => {
-                    setTimeout(() => {
-                        if (window.tlog) window.tlog("[SUB] Re-running selection after sidecar injection");
-                        checkAndSelectSubtitles(3); // Retry 3 times
-                    }, 1000);
-                  });
-                }
-              }).catch(e => {
-                  if (window.tlog) window.tlog(`[SUB] Fetch Error: ${e.message}`);
+            // Sidecar URLs are signed in the backend; re-run selection once they are in.
+            invoke("native_add_sidecar_subtitles", {
+              media: { path: cleanPath, source_id: Number(normalizeSourceId(item.source_id)) },
+            })
+              .then((count) => {
+                if (window.tlog) window.tlog(`[SUB] Sidecar subtitles added: ${count}`);
+                if (!count) return;
+                setTimeout(() => {
+                  if (window.tlog) window.tlog("[SUB] Re-running selection after sidecar injection");
+                  checkAndSelectSubtitles(3); // Retry 3 times
+                }, 1000);
+              })
+              .catch((e) => {
+                if (window.tlog) window.tlog(`[SUB] Sidecar injection failed: ${e?.message || e}`);
               });
 
             // [NEW] Apply saved settings (Volume & Subtitles)
@@ -6863,7 +7500,7 @@ function startWebPlayback(item, streamUrl, isAudio = false) {
     const bpath = toUrlSafeBase64(item.path || "");
     const albumArtUrl =
       item.meta_poster ||
-      `${state.serverUrl}/gds_dviewer/normal/album_art?bpath=${bpath}&source_id=${normalizeSourceId(item.source_id)}&apikey=${state.apiKey}`;
+      gdsAlbumArtUrl(bpath, item.source_id);
     ui.audioPoster.src = albumArtUrl;
     ui.playerBg.style.backgroundImage = `url(${albumArtUrl})`;
     ui.playerBg.style.display = "block";
@@ -6874,7 +7511,7 @@ function startWebPlayback(item, streamUrl, isAudio = false) {
 
     // Add Subtitles
     const bpath = toUrlSafeBase64(item.path || "");
-    const subtitleUrl = `${state.serverUrl}/gds_dviewer/normal/external_subtitle?bpath=${bpath}&source_id=${normalizeSourceId(item.source_id)}&apikey=${state.apiKey}`;
+    const subtitleUrl = gdsMediaUrl("external_subtitle", { bpath, source_id: normalizeSourceId(item.source_id) });
     const track = document.createElement("track");
     Object.assign(track, {
       kind: "subtitles",
@@ -6974,7 +7611,6 @@ async function initHeroCarousel() {
       recursive: "true",
       has_metadata: "true", 
       source_id: String(state.sourceId ?? 0),
-      apikey: state.apiKey
     });
 
     params.append("category", "tv_show,movie,animation");
@@ -7046,10 +7682,10 @@ function renderHeroSlides(items, container) {
     let imgUrl = '';
     
     if (rawImgUrl) {
-        imgUrl = `${state.serverUrl}/gds_dviewer/normal/proxy?url=${encodeURIComponent(rawImgUrl)}&apikey=${state.apiKey}`;
+        imgUrl = gdsProxyImageUrl(rawImgUrl);
     } else {
         const bpath = toUrlSafeBase64(item.path || "");
-        imgUrl = `${state.serverUrl}/gds_dviewer/normal/thumbnail?bpath=${bpath}&source_id=${normalizeSourceId(item.source_id)}&w=1080&apikey=${state.apiKey}`;
+        imgUrl = gdsThumbnailUrl(bpath, item.source_id, 1080);
     }
     slide.style.background = `center center / cover no-repeat url("${imgUrl}")`;
 
@@ -7926,6 +8562,14 @@ function setupPremiumOSC() {
     playVideo(nextItem);
   });
 
+  const btnDownload = document.getElementById("btn-osc-download");
+  if (btnDownload) {
+    btnDownload.onclick = (e) => {
+      e.stopPropagation();
+      downloadCurrentMedia();
+    };
+  }
+
   // [FIX] Force select button to ensure binding
   const btnSub = document.getElementById("btn-osc-subtitles");
   if (btnSub) {
@@ -8107,7 +8751,7 @@ function setupPremiumOSC() {
     }
   }
 
-  function showQualityMenu() {
+  async function showQualityMenu() {
     const existing = document.getElementById("quality-menu-overlay");
     if (existing) existing.remove();
 
@@ -8116,11 +8760,28 @@ function setupPremiumOSC() {
     overlay.className = "item-options-overlay active";
 
     const current = normalizeQualityProfile(state.qualityProfile);
-    const options = [
+    let options = [
       { id: "balanced", label: "Balanced", desc: "기본 추천" },
       { id: "quality", label: "Quality", desc: "고화질(부하 증가)" },
       { id: "smooth", label: "Smooth", desc: "부드러움 우선" },
     ];
+    const invoke = getTauriInvoke();
+    if (invoke) {
+      try {
+        const list = await invoke("list_quality_profiles");
+        if (list?.file_error) console.warn("[QUALITY]", list.file_error);
+        const profiles = (list?.profiles || []).filter((p) => !p.error);
+        if (profiles.length) {
+          options = profiles.map((p) => ({
+            id: p.name,
+            label: p.name.charAt(0).toUpperCase() + p.name.slice(1),
+            desc: p.description || (p.inherits ? `${p.inherits} 기반` : ""),
+          }));
+        }
+      } catch (err) {
+        console.warn("[QUALITY] list_quality_profiles failed:", err);
+      }
+    }
 
     overlay.innerHTML = `
       <div class="options-content" style="max-height: 55vh; display:flex; flex-direction:column;">
@@ -8252,8 +8913,7 @@ function setupPremiumOSC() {
 
     const updateStyle = () => {
       invoke("set_subtitle_style", { scale: state.subtitleSize, pos: state.subtitlePos });
-      localStorage.setItem("flashplex_sub_size", state.subtitleSize);
-      localStorage.setItem("flashplex_sub_pos", state.subtitlePos);
+      persistSettings({ subtitle_scale: state.subtitleSize, subtitle_pos: state.subtitlePos });
     };
 
     document.getElementById("sub-size-up").addEventListener("click", () => {
@@ -8318,7 +8978,7 @@ function setupPremiumOSC() {
     window.addEventListener('mouseup', () => {
       if (isDraggingSub) {
         isDraggingSub = false;
-        localStorage.setItem("flashplex_sub_pos", state.subtitlePos);
+        persistSettings({ subtitle_pos: state.subtitlePos });
         console.log("[SUB-DRAG] Finished dragging. Saved Pos:", state.subtitlePos);
       }
     });
@@ -8379,7 +9039,7 @@ function setupPremiumOSC() {
     ui.oscVolumeSlider.addEventListener("input", (e) => {
       const vol = parseInt(e.target.value);
       state.volume = vol;
-      localStorage.setItem("flashplex_volume", vol);
+      persistSettings({ volume: vol });
       console.log("[OSC-DRAG] Volume change:", vol);
       if (state.isNativeActive) {
         const invoke = getTauriInvoke();